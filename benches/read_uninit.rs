@@ -0,0 +1,61 @@
+//! Compares reading into freshly zeroed buffers with `read_uninit()`.
+//!
+//! Each iteration sends a chunk through a pseudo terminal pair and reads it into a new buffer,
+//! like a pipeline that hands every buffer off after filling it. `read()` needs the buffer to be
+//! zeroed first, which `read_uninit()` skips. Run it with `cargo bench --bench read_uninit`.
+
+#[cfg(unix)]
+fn main() {
+    use std::io::{Read, Write};
+    use std::mem::MaybeUninit;
+    use std::time::{Duration, Instant};
+
+    use serialport::{SerialPort, TTYPort};
+
+    const BUFFER_SIZE: usize = 1024 * 1024;
+    const CHUNK: [u8; 1024] = [0x55; 1024];
+    const ITERATIONS: u32 = 2000;
+
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create pseudo-terminal pair");
+    slave
+        .set_timeout(Duration::from_secs(1))
+        .expect("Unable to set the timeout");
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        master.write_all(&CHUNK).unwrap();
+        let mut buf = vec![0u8; BUFFER_SIZE];
+        let mut received = 0;
+        while received < CHUNK.len() {
+            received += slave.read(&mut buf[received..]).unwrap();
+        }
+    }
+    let zeroed = start.elapsed() / ITERATIONS;
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        master.write_all(&CHUNK).unwrap();
+        let mut buf: Vec<u8> = Vec::with_capacity(BUFFER_SIZE);
+        let spare = unsafe {
+            std::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut MaybeUninit<u8>, BUFFER_SIZE)
+        };
+        let mut received = 0;
+        while received < CHUNK.len() {
+            received += slave.read_uninit(&mut spare[received..]).unwrap();
+        }
+        unsafe { buf.set_len(received) };
+    }
+    let uninit = start.elapsed() / ITERATIONS;
+
+    println!(
+        "{} byte chunks into {} byte buffers, {} iterations:",
+        CHUNK.len(),
+        BUFFER_SIZE,
+        ITERATIONS
+    );
+    println!("  read() into a zeroed buffer: {:?} per chunk", zeroed);
+    println!("  read_uninit():               {:?} per chunk", uninit);
+}
+
+#[cfg(not(unix))]
+fn main() {}
@@ -10,6 +10,12 @@ fn main() {
             };
             for p in ports {
                 println!("  {}", p.port_name);
+                if let Some(driver_name) = &p.driver_name {
+                    println!("    Driver: {}", driver_name);
+                }
+                if let Some(by_id_path) = &p.by_id_path {
+                    println!("    Stable path: {}", by_id_path);
+                }
                 match p.port_type {
                     SerialPortType::UsbPort(info) => {
                         println!("    Type: USB");
@@ -0,0 +1,132 @@
+//! Shell-style wildcard patterns for matching port names
+
+use crate::{Error, ErrorKind, Result};
+
+/// A single element of a parsed pattern
+#[derive(Debug)]
+enum Token {
+    /// `?`, matches any single character
+    Any,
+    /// `*`, matches any number of characters
+    AnyRun,
+    /// `[...]` or `[!...]`, matches a single character in (or not in) the ranges
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    /// Any other character, matching itself
+    Literal(char),
+}
+
+/// A parsed wildcard pattern, see `available_ports_matching_name()` for the syntax
+#[derive(Debug)]
+pub(crate) struct Glob {
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Result<Glob> {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '?' => Token::Any,
+                '*' => Token::AnyRun,
+                '[' => {
+                    let mut class: Vec<char> = Vec::new();
+                    loop {
+                        match chars.next() {
+                            // A `]` right after the opening bracket is part of the class
+                            Some(']') if !class.is_empty() && class != ['!'] => break,
+                            Some(c) => class.push(c),
+                            None => {
+                                return Err(Error::new(
+                                    ErrorKind::InvalidInput,
+                                    format!("Unterminated `[` in pattern \"{}\"", pattern),
+                                ))
+                            }
+                        }
+                    }
+                    let negated = class[0] == '!';
+                    let class = if negated { &class[1..] } else { &class[..] };
+                    let mut ranges = Vec::new();
+                    let mut i = 0;
+                    while i < class.len() {
+                        if i + 2 < class.len() && class[i + 1] == '-' {
+                            ranges.push((class[i], class[i + 2]));
+                            i += 3;
+                        } else {
+                            ranges.push((class[i], class[i]));
+                            i += 1;
+                        }
+                    }
+                    Token::Class { negated, ranges }
+                }
+                c => Token::Literal(c),
+            };
+            tokens.push(token);
+        }
+        Ok(Glob { tokens })
+    }
+
+    pub(crate) fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        matches_from(&self.tokens, &text)
+    }
+}
+
+fn matches_from(tokens: &[Token], text: &[char]) -> bool {
+    match tokens.split_first() {
+        None => text.is_empty(),
+        Some((Token::AnyRun, rest)) => {
+            (0..=text.len()).any(|skip| matches_from(rest, &text[skip..]))
+        }
+        Some((token, rest)) => match text.split_first() {
+            Some((&c, text)) => matches_char(token, c) && matches_from(rest, text),
+            None => false,
+        },
+    }
+}
+
+fn matches_char(token: &Token, c: char) -> bool {
+    match *token {
+        Token::Any => true,
+        Token::AnyRun => unreachable!(),
+        Token::Class {
+            negated,
+            ref ranges,
+        } => ranges.iter().any(|&(low, high)| low <= c && c <= high) != negated,
+        Token::Literal(literal) => literal == c,
+    }
+}
+
+#[test]
+fn test_glob_matches() {
+    let glob = Glob::new("ttyUSB*").unwrap();
+    assert!(glob.matches("ttyUSB0"));
+    assert!(glob.matches("ttyUSB"));
+    assert!(!glob.matches("ttyACM0"));
+
+    let glob = Glob::new("COM[3-9]").unwrap();
+    assert!(glob.matches("COM3"));
+    assert!(glob.matches("COM9"));
+    assert!(!glob.matches("COM1"));
+    assert!(!glob.matches("COM10"));
+
+    let glob = Glob::new("tty[!S]?*").unwrap();
+    assert!(glob.matches("ttyUSB0"));
+    assert!(glob.matches("ttyA0"));
+    assert!(!glob.matches("ttyS0"));
+    assert!(!glob.matches("ttyA"));
+
+    let glob = Glob::new("[]-]").unwrap();
+    assert!(glob.matches("]"));
+    assert!(glob.matches("-"));
+    assert!(!glob.matches("a"));
+}
+
+#[test]
+fn test_glob_invalid() {
+    let err = Glob::new("COM[3-9").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
@@ -26,21 +26,32 @@
 // doc tests.
 #![doc(test(attr(allow(unused_must_use))))]
 
+use bitflags::bitflags;
+use std::collections::BTreeSet;
 use std::convert::From;
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[cfg(unix)]
 mod posix;
 #[cfg(unix)]
-pub use posix::{BreakDuration, TTYPort};
+pub use posix::{BreakDuration, RawPortConfig, TTYPort};
+#[cfg(target_os = "linux")]
+pub use posix::{ClosingWait, FifoLevel, SerialStruct};
+
+mod glob;
+
+mod tee;
+pub use tee::TeePort;
 
 #[cfg(windows)]
 mod windows;
 #[cfg(windows)]
-pub use windows::COMPort;
+pub use windows::{COMPort, CommEventMask, CommTimeouts, DtrRtsControl, RawPortConfig};
 
 /// A type for results generated by interacting with serial ports
 ///
@@ -109,6 +120,11 @@ impl StdError for Error {
 
 impl From<io::Error> for Error {
     fn from(io_error: io::Error) -> Error {
+        // Errors produced by this crate and passed through `io::Read`/`io::Write` keep their kind,
+        // so `ErrorKind::NoDevice` survives the round trip.
+        if let Some(error) = io_error.get_ref().and_then(|e| e.downcast_ref::<Error>()) {
+            return error.clone();
+        }
         Error::new(ErrorKind::Io(io_error.kind()), format!("{}", io_error))
     }
 }
@@ -122,7 +138,7 @@ impl From<Error> for io::Error {
             ErrorKind::Io(kind) => kind,
         };
 
-        io::Error::new(kind, error.description)
+        io::Error::new(kind, error)
     }
 }
 
@@ -143,6 +159,18 @@ pub enum DataBits {
     Eight,
 }
 
+impl DataBits {
+    /// Returns the number of data bits per character.
+    pub fn bits(self) -> u8 {
+        match self {
+            DataBits::Five => 5,
+            DataBits::Six => 6,
+            DataBits::Seven => 7,
+            DataBits::Eight => 8,
+        }
+    }
+}
+
 /// Parity checking modes
 ///
 /// When parity checking is enabled (`Odd` or `Even`) an extra bit is transmitted with
@@ -192,9 +220,76 @@ pub enum FlowControl {
     Hardware,
 }
 
+/// What happens once the input buffer fills up
+///
+/// See [`set_overflow_policy`].
+///
+/// [`set_overflow_policy`]: trait.SerialPort.html#method.set_overflow_policy
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverflowPolicy {
+    /// Bytes received while the buffer is full are dropped, and the sender is never paused.
+    DropIncoming,
+
+    /// The sender is paused with the configured flow control before the buffer is full.
+    AssertFlowControl,
+}
+
+/// Flow control configuration of a port, saved with [`save_flow_control`]
+///
+/// This holds the raw driver state behind the flow control mode, which `FlowControl` can't
+/// fully express. Restoring it with [`restore_flow_control`] also brings back an overflow
+/// policy, XON/XOFF passthrough, or on Windows the RTS control mode.
+///
+/// [`save_flow_control`]: trait.SerialPort.html#method.save_flow_control
+/// [`restore_flow_control`]: trait.SerialPort.html#method.restore_flow_control
+#[derive(Debug, Copy, Clone)]
+pub struct SavedFlowControl {
+    /// The mode `flow_control()` reported when the configuration was saved
+    flow_control: FlowControl,
+    /// The raw driver state, or `None` if it was saved by the provided `save_flow_control()`
+    #[cfg(unix)]
+    raw: Option<posix::RawFlowControl>,
+    #[cfg(windows)]
+    raw: Option<windows::RawFlowControl>,
+}
+
+/// Line endings used when writing text
+///
+/// See [`write_str`] and [`write_line`].
+///
+/// [`write_str`]: trait.SerialPort.html#method.write_str
+/// [`write_line`]: trait.SerialPort.html#method.write_line
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineEnding {
+    /// Text is written unchanged, and lines end with `\n`.
+    None,
+
+    /// Line breaks are written as `\r`.
+    Cr,
+
+    /// Line breaks are written as `\n`.
+    Lf,
+
+    /// Line breaks are written as `\r\n`.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the line terminator written for this setting
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::None | LineEnding::Lf => "\n",
+            LineEnding::Cr => "\r",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
 /// Specifies which buffer or buffers to purge when calling [`clear`]
 ///
-/// [`clear`]: trait.SerialPort.html#tymethod.clear
+/// [`clear`]: trait.SerialPort.html#method.clear
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ClearBuffer {
@@ -206,6 +301,408 @@ pub enum ClearBuffer {
     All,
 }
 
+/// A stimulus to wake up a device's UART before talking to it
+///
+/// See [`wake`].
+///
+/// [`wake`]: trait.SerialPort.html#method.wake
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WakePattern {
+    /// Send this many NUL bytes
+    NulBytes(usize),
+    /// Hold a break for this long
+    Break(Duration),
+    /// Deassert DTR for this long, then assert it again
+    DtrToggle(Duration),
+}
+
+/// Snapshot of the signals involved in hardware flow control
+///
+/// See [`hardware_flow_diagnostic`].
+///
+/// [`hardware_flow_diagnostic`]: trait.SerialPort.html#method.hardware_flow_diagnostic
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HwFlowStatus {
+    /// The flow control mode the port is configured with
+    pub flow_control: FlowControl,
+    /// Whether the other end currently asserts CTS, i.e. is ready to receive data, or `None` if
+    /// the driver doesn't report the modem status
+    pub clear_to_send: Option<bool>,
+    /// Whether we currently assert RTS, i.e. are ready to receive data, or `None` if it can't be
+    /// read back
+    pub rts: Option<bool>,
+    /// Whether writes are currently held back by the driver because CTS isn't asserted
+    pub write_blocked: bool,
+}
+
+/// Round-trip timings collected by [`measure_latency`]
+///
+/// The timing fields are `None` if none of the samples got a reply.
+///
+/// [`measure_latency`]: trait.SerialPort.html#method.measure_latency
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Number of samples that got a reply
+    pub replies: usize,
+    /// Number of samples that timed out waiting for the reply
+    pub timeouts: usize,
+    /// Shortest round-trip time
+    pub min: Option<Duration>,
+    /// Longest round-trip time
+    pub max: Option<Duration>,
+    /// Average round-trip time
+    pub mean: Option<Duration>,
+}
+
+/// Transfer rates measured by [`measure_throughput`]
+///
+/// [`measure_throughput`]: trait.SerialPort.html#method.measure_throughput
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ThroughputStats {
+    /// Number of bytes written
+    pub bytes_written: usize,
+    /// Number of bytes read back before the timeout
+    pub bytes_read: usize,
+    /// Number of bytes read back that differ from the ones written at the same position
+    pub corrupted: usize,
+    /// Time from the start of the first write until all bytes were transmitted
+    pub write_time: Duration,
+    /// Time from the start of the first write until the last byte was read back
+    pub read_time: Duration,
+}
+
+impl ThroughputStats {
+    /// Returns the rate at which data was written in bytes per second
+    pub fn write_rate(&self) -> f64 {
+        rate(self.bytes_written, self.write_time)
+    }
+
+    /// Returns the rate at which data was read back in bytes per second
+    pub fn read_rate(&self) -> f64 {
+        rate(self.bytes_read, self.read_time)
+    }
+
+    /// Returns the number of bytes that were written but not read back
+    pub fn missing(&self) -> usize {
+        self.bytes_written.saturating_sub(self.bytes_read)
+    }
+}
+
+fn rate(bytes: usize, time: Duration) -> f64 {
+    if time == Duration::from_secs(0) {
+        return 0.0;
+    }
+    bytes as f64 / time.as_secs_f64()
+}
+
+/// Size of the length field read by [`read_length_prefixed`]
+///
+/// [`read_length_prefixed`]: trait.SerialPort.html#method.read_length_prefixed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LengthFieldSize {
+    /// A single byte
+    One,
+    /// Two bytes
+    Two,
+    /// Four bytes
+    Four,
+}
+
+impl LengthFieldSize {
+    fn len(self) -> usize {
+        match self {
+            LengthFieldSize::One => 1,
+            LengthFieldSize::Two => 2,
+            LengthFieldSize::Four => 4,
+        }
+    }
+}
+
+/// Byte order of a multi-byte field
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Endian {
+    /// Most significant byte first
+    Big,
+    /// Least significant byte first
+    Little,
+}
+
+bitflags! {
+    /// A set of control signals of a serial port
+    ///
+    /// See [`SerialPort::modem_lines`] and [`SerialPort::set_modem_lines_masked`].
+    ///
+    /// [`SerialPort::modem_lines`]: trait.SerialPort.html#method.modem_lines
+    /// [`SerialPort::set_modem_lines_masked`]: trait.SerialPort.html#method.set_modem_lines_masked
+    pub struct ModemLines: u8 {
+        /// RTS (Request To Send), an output
+        const RTS = 0x01;
+        /// DTR (Data Terminal Ready), an output
+        const DTR = 0x02;
+        /// CTS (Clear To Send), an input
+        const CTS = 0x04;
+        /// DSR (Data Set Ready), an input
+        const DSR = 0x08;
+        /// RI (Ring Indicator), an input
+        const RI = 0x10;
+        /// CD (Carrier Detect), an input
+        const CD = 0x20;
+    }
+}
+
+bitflags! {
+    /// A set of optional operations a serial port supports
+    ///
+    /// See [`SerialPort::supported_operations`].
+    ///
+    /// [`SerialPort::supported_operations`]: trait.SerialPort.html#method.supported_operations
+    pub struct SupportedOps: u32 {
+        /// Baud rates outside the standard list can be set with `set_baud_rate()`
+        const ARBITRARY_BAUD_RATES = 0x01;
+        /// `FlowControl::Hardware` can be set with `set_flow_control()`
+        const HARDWARE_FLOW_CONTROL = 0x02;
+        /// `set_break()` and `clear_break()` put a break condition on the line
+        const BREAK = 0x04;
+        /// The control signals can be read with `modem_lines()` and friends
+        const MODEM_LINES = 0x08;
+        /// The driver reports its buffer sizes through `rx_buffer_capacity()` and
+        /// `tx_buffer_capacity()`
+        const BUFFER_CAPACITY = 0x10;
+        /// The UART clock divisor can be set with `TTYPort::set_custom_divisor()` (Linux only)
+        const CUSTOM_DIVISOR = 0x20;
+        /// RTS can be driven by the driver during transmission for RS-485 with
+        /// `COMPort::set_rts_control_mode(DtrRtsControl::Toggle)` (Windows only)
+        const RS485_RTS_TOGGLE = 0x40;
+    }
+}
+
+/// State of the input control signals of a serial port
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModemStatus {
+    /// CTS (Clear To Send)
+    pub clear_to_send: bool,
+    /// DSR (Data Set Ready)
+    pub data_set_ready: bool,
+    /// RI (Ring Indicator)
+    pub ring_indicator: bool,
+    /// CD (Carrier Detect)
+    pub carrier_detect: bool,
+}
+
+impl ModemStatus {
+    /// Returns which signals differ between `self` and `other`, as `true` fields
+    fn changes(&self, other: &ModemStatus) -> ModemStatus {
+        ModemStatus {
+            clear_to_send: self.clear_to_send != other.clear_to_send,
+            data_set_ready: self.data_set_ready != other.data_set_ready,
+            ring_indicator: self.ring_indicator != other.ring_indicator,
+            carrier_detect: self.carrier_detect != other.carrier_detect,
+        }
+    }
+}
+
+/// A change of the input control signals, as returned by [`SignalEventStream`]
+///
+/// [`SignalEventStream`]: struct.SignalEventStream.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SignalEvent {
+    /// The state of all signals after the change
+    pub status: ModemStatus,
+    /// The signals that changed, marked as `true`
+    ///
+    /// This can be all `false` if a signal changed and changed back before its state was read,
+    /// such as a short ring indicator pulse.
+    pub changed: ModemStatus,
+}
+
+/// An iterator over changes of the input control signals of a port
+///
+/// This is returned by [`signal_events`]. Each call to `next()` blocks until a signal changes
+/// and never returns `None`. If the stream's timeout elapses first, an `Io(TimedOut)` error is
+/// returned and iterating can continue.
+///
+/// [`signal_events`]: trait.SerialPort.html#method.signal_events
+pub struct SignalEventStream {
+    port: Box<dyn SerialPort>,
+    timeout: Option<Duration>,
+    status: ModemStatus,
+}
+
+impl fmt::Debug for SignalEventStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SignalEventStream")
+            .field("port", &self.port.name())
+            .field("timeout", &self.timeout)
+            .field("status", &self.status)
+            .finish()
+    }
+}
+
+impl Iterator for SignalEventStream {
+    type Item = Result<SignalEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.port.wait_for_modem_change(self.timeout).map(|status| {
+            let changed = self.status.changes(&status);
+            self.status = status;
+            SignalEvent { status, changed }
+        }))
+    }
+}
+
+/// A background thread receiving data from a port
+///
+/// This is returned by [`spawn_reader`] and [`read_to_channel`]. The thread reads from its own
+/// clone of the port and passes every chunk of received data on. It runs until `stop()` is
+/// called, the handle is dropped, or reading fails.
+///
+/// [`spawn_reader`]: trait.SerialPort.html#method.spawn_reader
+/// [`read_to_channel`]: trait.SerialPort.html#method.read_to_channel
+#[derive(Debug)]
+pub struct ReaderHandle {
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<Result<()>>>,
+}
+
+impl ReaderHandle {
+    /// Returns whether the thread has stopped reading, e.g. because of an error.
+    ///
+    /// The error is returned by `stop()`.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    /// Stops the thread and waits for it to exit.
+    ///
+    /// The thread waits for data for up to the port's timeout at a time and checks whether it
+    /// should stop in between, so this may block for that long, or for as long as the callback
+    /// takes to return. Ports with a timeout of zero are waited on for [`REASONABLE_TIMEOUT`]
+    /// instead, so the thread doesn't spin.
+    ///
+    /// [`REASONABLE_TIMEOUT`]: constant.REASONABLE_TIMEOUT.html
+    ///
+    /// ## Errors
+    ///
+    /// This function returns the error that made the thread stop reading, or `Unknown` if the
+    /// callback panicked.
+    pub fn stop(mut self) -> Result<()> {
+        self.stop.store(true, Ordering::SeqCst);
+        match self.thread.take().map(|thread| thread.join()) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(Error::new(ErrorKind::Unknown, "Reader callback panicked")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ReaderHandle {
+    fn drop(&mut self) {
+        // Let the thread exit on its own instead of blocking on it
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Fill levels of the input and output buffers of a serial port
+///
+/// See [`queue_status`].
+///
+/// [`queue_status`]: trait.SerialPort.html#method.queue_status
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueueStatus {
+    /// Number of bytes received but not read yet, as returned by `bytes_to_read()`
+    pub bytes_to_read: u32,
+    /// Number of bytes written but not transmitted yet, as returned by `bytes_to_write()`
+    pub bytes_to_write: u32,
+    /// Size of the input buffer, if the driver reports it
+    pub rx_capacity: Option<u32>,
+    /// Size of the output buffer, if the driver reports it
+    pub tx_capacity: Option<u32>,
+}
+
+/// The baud rate a serial port was asked to use and the one its driver reports
+///
+/// See [`baud_rate_info`].
+///
+/// [`baud_rate_info`]: trait.SerialPort.html#method.baud_rate_info
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BaudRateInfo {
+    /// The baud rate last set on the builder or with `set_baud_rate()`
+    pub requested: u32,
+    /// The baud rate the driver reports for the line, if it can be read back
+    pub actual: Option<u32>,
+}
+
+/// The settings a serial port supports
+///
+/// See [`capabilities`].
+///
+/// [`capabilities`]: trait.SerialPort.html#method.capabilities
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortCapabilities {
+    /// The standard baud rates the driver supports, if it reports them
+    pub baud_rates: Option<Vec<u32>>,
+    /// Whether baud rates other than the standard ones can be set, if known
+    pub arbitrary_baud_rates: Option<bool>,
+    /// The highest baud rate, if known
+    pub max_baud_rate: Option<u32>,
+    /// The supported numbers of data bits
+    pub data_bits: Vec<DataBits>,
+    /// The supported parity modes
+    pub parity: Vec<Parity>,
+    /// The supported numbers of stop bits
+    pub stop_bits: Vec<StopBits>,
+    /// The supported flow control modes
+    pub flow_control: Vec<FlowControl>,
+}
+
+/// Byte and line error counters maintained by the serial driver
+///
+/// See [`counters`] for how these are measured.
+///
+/// [`counters`]: trait.SerialPort.html#method.counters
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineCounters {
+    /// Number of bytes received
+    pub rx: u32,
+    /// Number of bytes transmitted
+    pub tx: u32,
+    /// Number of framing errors
+    pub frame: u32,
+    /// Number of hardware overruns, where the UART received data faster than it could be read out
+    pub overrun: u32,
+    /// Number of parity errors
+    pub parity: u32,
+    /// Number of breaks received
+    pub brk: u32,
+    /// Number of bytes dropped because the driver's receive buffer was full
+    pub buf_overrun: u32,
+}
+
+impl LineCounters {
+    /// Returns the counts accumulated since `baseline` was taken.
+    ///
+    /// The driver counters wrap around, so the difference is computed with wrapping arithmetic.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub(crate) fn since(&self, baseline: &LineCounters) -> LineCounters {
+        LineCounters {
+            rx: self.rx.wrapping_sub(baseline.rx),
+            tx: self.tx.wrapping_sub(baseline.tx),
+            frame: self.frame.wrapping_sub(baseline.frame),
+            overrun: self.overrun.wrapping_sub(baseline.overrun),
+            parity: self.parity.wrapping_sub(baseline.parity),
+            brk: self.brk.wrapping_sub(baseline.brk),
+            buf_overrun: self.buf_overrun.wrapping_sub(baseline.buf_overrun),
+        }
+    }
+}
+
 /// A struct containing all serial port settings
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SerialPortBuilder {
@@ -213,6 +710,8 @@ pub struct SerialPortBuilder {
     path: String,
     /// The baud rate in symbols-per-second
     baud_rate: u32,
+    /// How far the achieved baud rate may deviate from `baud_rate`, in percent
+    baud_rate_tolerance: u32,
     /// Number of bits used to represent a character sent on the line
     data_bits: DataBits,
     /// The type of signalling to use for controlling data transfer
@@ -223,6 +722,28 @@ pub struct SerialPortBuilder {
     stop_bits: StopBits,
     /// Amount of time to wait to receive data before timing out
     timeout: Duration,
+    /// Amount of time to wait after opening the port before returning it
+    settle_delay: Option<Duration>,
+    /// Line ending translation applied when writing text
+    line_ending: LineEnding,
+    /// Whether other handles may open the port at the same time
+    #[cfg(windows)]
+    shared: bool,
+    /// Whether the port may become the controlling terminal of the process
+    #[cfg(unix)]
+    controlling_tty: bool,
+    /// Whether the terminal is put into raw mode when opening the port
+    #[cfg(unix)]
+    raw: bool,
+    /// Whether to open the port with `O_APPEND`
+    #[cfg(unix)]
+    append: bool,
+    /// Whether to open the port with `O_EXCL`
+    #[cfg(unix)]
+    exclusive_open: bool,
+    /// Whether a symbolic link may be followed to the device
+    #[cfg(unix)]
+    follow_symlinks: bool,
 }
 
 impl SerialPortBuilder {
@@ -240,6 +761,18 @@ impl SerialPortBuilder {
         self
     }
 
+    /// Set how far the baud rate achieved by the driver may deviate from the requested one
+    ///
+    /// The tolerance is in percent and defaults to 2%, which is about what a UART tolerates.
+    /// Opening a port fails with `InvalidInput` if the achieved rate deviates by more, which is
+    /// only checked on Linux, where the driver reports the rate its clock divisor produces. See
+    /// `TTYPort::set_baud_rate_tolerance()`.
+    #[must_use]
+    pub fn baud_rate_tolerance(mut self, percent: u32) -> Self {
+        self.baud_rate_tolerance = percent;
+        self
+    }
+
     /// Set the number of bits used to represent a character sent on the line
     #[must_use]
     pub fn data_bits(mut self, data_bits: DataBits) -> Self {
@@ -269,12 +802,163 @@ impl SerialPortBuilder {
     }
 
     /// Set the amount of time to wait to receive data before timing out
+    ///
+    /// Defaults to [`DEFAULT_TIMEOUT`], which is zero: reads return immediately with a `TimedOut`
+    /// error if no data has been received yet.
+    ///
+    /// [`DEFAULT_TIMEOUT`]: constant.DEFAULT_TIMEOUT.html
     #[must_use]
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
         self
     }
 
+    /// Use settings that are more forgiving for interactive use than the defaults of [`new`]
+    ///
+    /// This sets the timeout to [`REASONABLE_TIMEOUT`] so reads wait for data instead of
+    /// returning immediately. Other settings are left unchanged.
+    ///
+    /// [`new`]: fn.new.html
+    /// [`REASONABLE_TIMEOUT`]: constant.REASONABLE_TIMEOUT.html
+    #[must_use]
+    pub fn with_reasonable_defaults(self) -> Self {
+        self.timeout(REASONABLE_TIMEOUT)
+    }
+
+    /// Set an amount of time to wait after the port has been opened and configured
+    ///
+    /// Some USB to serial adapters (notably those based on the CH340) garble the first bytes
+    /// written right after the port has been opened. Setting a settle delay of a few milliseconds
+    /// works around this, at the cost of a slower open. Defaults to `None`, which doesn't wait.
+    #[must_use]
+    pub fn settle_delay(mut self, settle_delay: Option<Duration>) -> Self {
+        self.settle_delay = settle_delay;
+        self
+    }
+
+    /// Set the line ending translation applied by `write_str()` and `write_line()`
+    ///
+    /// Defaults to `LineEnding::None`, which doesn't translate anything. See
+    /// [`SerialPort::set_line_ending`](trait.SerialPort.html#tymethod.set_line_ending).
+    #[must_use]
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Set whether the port may be opened by other handles at the same time
+    ///
+    /// COM ports are opened exclusively by default. Sharing is mostly useful for virtual ports and
+    /// monitoring tools; if a real hardware port is shared, reads and writes from all handles are
+    /// interleaved on the same line.
+    #[cfg(windows)]
+    #[must_use]
+    pub fn shared(mut self, shared: bool) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Set whether the port may become the controlling terminal of the process
+    ///
+    /// Ports are opened with `O_NOCTTY` by default. Without it, a process that has no
+    /// controlling terminal (such as a daemon) acquires the port as one when opening it, and is
+    /// then sent `SIGHUP` when the line hangs up, which terminates it by default. This is only
+    /// useful for programs such as `getty` that run a login session on the port.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn controlling_tty(mut self, controlling_tty: bool) -> Self {
+        self.controlling_tty = controlling_tty;
+        self
+    }
+
+    /// Set whether the terminal is put into raw mode when opening the port
+    ///
+    /// By default (`true`), opening a port replaces the terminal settings found on the device
+    /// with the ones for binary data: `CREAD` and `CLOCAL` are set, and `cfmakeraw()` clears
+    /// `IGNBRK`, `BRKINT`, `PARMRK`, `ISTRIP`, `INLCR`, `IGNCR` and `ICRNL` in the input flags,
+    /// `OPOST` in the output flags and `ECHO`, `ECHONL`, `ICANON`, `ISIG` and `IEXTEN` in the local
+    /// flags, and sets `VMIN` to 1 and `VTIME` to 0.
+    ///
+    /// When `false`, these are left as they were found, e.g. as configured with `stty` beforehand.
+    /// Either way, the settings of this builder are then applied, which touch the baud rate,
+    /// `CSIZE`, `CSTOPB`, `PARENB` and `PARODD` in the control flags, `INPCK`, `IGNPAR`, `IXON`
+    /// and `IXOFF` in the input flags, and `CRTSCTS`. Note that a terminal left in canonical
+    /// mode only returns complete lines from reads.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn raw(mut self, raw: bool) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Set whether the port is opened with `O_APPEND`
+    ///
+    /// This has no effect on terminal devices, but makes writes go to the end of the file when
+    /// `path` is a regular file standing in for a port. Defaults to `false`.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Set whether the port is opened with `O_EXCL`
+    ///
+    /// Without `O_CREAT` the meaning of `O_EXCL` is left to the system: Linux uses it to fail with
+    /// `EBUSY` if a block device is in use, and ignores it for other files. It's no substitute for
+    /// the exclusive access to terminal devices that `TTYPort::set_exclusive()` controls.
+    /// Defaults to `false`.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn exclusive_open(mut self, exclusive_open: bool) -> Self {
+        self.exclusive_open = exclusive_open;
+        self
+    }
+
+    /// Set whether `path` may be a symbolic link to the device
+    ///
+    /// When `false`, the port is opened with `O_NOFOLLOW` and opening fails if the last component
+    /// of `path` is a symbolic link, so that a link placed in a writable directory can't redirect
+    /// the program to another device. Links in the directories leading up to it are still
+    /// followed. Defaults to `true`.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Checks the settings against the constraints of this platform, without opening the port
+    ///
+    /// This lets a UI reject combinations that can never work before touching any hardware. It
+    /// only finds settings that are invalid for every port, such as a baud rate of 0, all
+    /// non-standard baud rates on platforms which only support the standard ones (Linux with
+    /// musl or on PowerPC), or 5 data bits with 2 stop bits on Windows, which Windows doesn't
+    /// support. The device may still reject settings it doesn't support when the port is
+    /// opened, and the path isn't checked.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` with a description of the problem if the settings are invalid.
+    pub fn validate(&self) -> Result<()> {
+        if self.baud_rate == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Baud rate must not be zero",
+            ));
+        }
+        #[cfg(unix)]
+        posix::check_baud_rate(self.baud_rate)?;
+        #[cfg(windows)]
+        if self.data_bits == DataBits::Five && self.stop_bits == StopBits::Two {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "5 data bits can't be used with 2 stop bits",
+            ));
+        }
+        Ok(())
+    }
+
     /// Open a cross-platform interface to the port with the specified settings
     pub fn open(self) -> Result<Box<dyn SerialPort>> {
         #[cfg(unix)]
@@ -290,6 +974,96 @@ impl SerialPortBuilder {
         ))
     }
 
+    /// Open a cross-platform interface to the port and check that the settings were applied
+    ///
+    /// Some drivers silently ignore or clamp settings they don't support. This reads the
+    /// settings back from the driver after opening the port and fails if the baud rate (within
+    /// 2%), data bits, flow control, parity or stop bits differ from the requested ones. The
+    /// timeout isn't checked, since it isn't a driver setting. Use `open()` to skip the check for
+    /// setups where the driver is known to report settings differently from what it uses.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if the settings read back don't match, with a description listing the
+    ///   differences.
+    /// * Any error returned by `open()` or by reading the settings back.
+    pub fn open_verified(self) -> Result<Box<dyn SerialPort>> {
+        self.open_checked()?.map_err(settings_mismatch)
+    }
+
+    /// Open the port like `open_verified()`, retrying if the settings don't stick
+    ///
+    /// Some adapters, notably clones of the CH340 and PL2303, occasionally ignore the settings
+    /// applied right after the port is opened but accept them on the next try. This opens the
+    /// port and checks the settings like `open_verified()`, and if they don't match, closes the
+    /// port and tries again, up to `attempts` times in total. Other errors, such as a missing
+    /// device, are returned right away.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `attempts` is 0, or if the settings still didn't match on the last
+    ///   attempt, with a description listing the differences.
+    /// * Any other error returned by `open()` or by reading the settings back.
+    pub fn open_with_settings_retry(self, attempts: u32) -> Result<Box<dyn SerialPort>> {
+        if attempts == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "At least one attempt is needed",
+            ));
+        }
+        retry_settings_mismatch(attempts, || self.clone().open_checked())
+    }
+
+    /// Opens the port and reads the settings back, returning the differences if they don't match
+    ///
+    /// This keeps a settings mismatch apart from errors of `open()` that have the same kind.
+    fn open_checked(self) -> Result<std::result::Result<Box<dyn SerialPort>, Vec<String>>> {
+        let port = self.clone().open()?;
+        let actual = port.settings()?;
+        let differences = self.differences(&actual);
+        if differences.is_empty() {
+            Ok(Ok(port))
+        } else {
+            Ok(Err(differences))
+        }
+    }
+
+    /// Lists the driver settings of `actual` that don't match those of `self`
+    fn differences(&self, actual: &SerialPortBuilder) -> Vec<String> {
+        let mut differences = Vec::new();
+        if !baud_rate_matches(self.baud_rate, actual.baud_rate, self.baud_rate_tolerance) {
+            differences.push(format!(
+                "baud rate {} (requested {})",
+                actual.baud_rate, self.baud_rate
+            ));
+        }
+        if actual.data_bits != self.data_bits {
+            differences.push(format!(
+                "data bits {:?} (requested {:?})",
+                actual.data_bits, self.data_bits
+            ));
+        }
+        if actual.flow_control != self.flow_control {
+            differences.push(format!(
+                "flow control {:?} (requested {:?})",
+                actual.flow_control, self.flow_control
+            ));
+        }
+        if actual.parity != self.parity {
+            differences.push(format!(
+                "parity {:?} (requested {:?})",
+                actual.parity, self.parity
+            ));
+        }
+        if actual.stop_bits != self.stop_bits {
+            differences.push(format!(
+                "stop bits {:?} (requested {:?})",
+                actual.stop_bits, self.stop_bits
+            ));
+        }
+        differences
+    }
+
     /// Open a platform-specific interface to the port with the specified settings
     #[cfg(unix)]
     pub fn open_native(self) -> Result<TTYPort> {
@@ -307,8 +1081,25 @@ impl SerialPortBuilder {
 ///
 /// This trait is all that's necessary to implement a new serial port driver
 /// for a new platform.
-pub trait SerialPort: Send + io::Read + io::Write {
-    // Port settings getters
+///
+/// ## Live and cached settings
+///
+/// The getters for the line settings (`baud_rate()`, `data_bits()`, `flow_control()`,
+/// `parity()`, `stop_bits()` and `settings()`) query the driver every time they are called, so a
+/// reconfiguration through another handle or by another process is visible. The only exceptions
+/// are `timeout()`, which is a property of each handle and not of the device, and `baud_rate()` on
+/// macOS, where the baud rate can't be retrieved from the OS and the last value set through this
+/// handle is returned instead.
+///
+/// ## Cancelled reads and writes
+///
+/// On Windows, a `read()` or `write()` cancelled from another thread with `CancelIoEx()` or
+/// `CancelSynchronousIo()` fails with `io::ErrorKind::Interrupted`. The `Read` and `Write`
+/// adapters like `read_exact()` and `write_all()` retry on that kind, which means a signal on
+/// POSIX, so a cancel inside them is silently retried. Call `read()` and `write()` directly to be
+/// able to cancel a transfer. The helpers of this trait stop on a cancel.
+pub trait SerialPort: Send + io::Read + io::Write {
+    // Port settings getters
 
     /// Returns the name of this port if it exists.
     ///
@@ -316,6 +1107,33 @@ pub trait SerialPort: Send + io::Read + io::Write {
     /// Additionally it may not exist for virtual ports.
     fn name(&self) -> Option<String>;
 
+    /// Returns how the device of this port is connected, e.g. via USB or PCI.
+    ///
+    /// This is looked up when the port is opened, using the same queries as `available_ports()`,
+    /// so it matches the `port_type` of the port's `SerialPortInfo`. It's
+    /// `SerialPortType::Unknown` if the port isn't listed by `available_ports()`, e.g. for
+    /// pseudo terminals or ports created from a raw handle.
+    ///
+    /// The provided implementation returns `SerialPortType::Unknown`.
+    fn port_type(&self) -> SerialPortType {
+        SerialPortType::Unknown
+    }
+
+    /// Returns whether the port is a virtual port rather than a real UART.
+    ///
+    /// Virtual ports usually accept but ignore the line settings such as the baud rate and
+    /// parity, and don't have real control signals, so tests and diagnostics can use this to
+    /// skip hardware-specific checks. On POSIX this detects both ends of pseudo terminals, by
+    /// their device numbers on Linux and by their names elsewhere. On Windows, this detects the
+    /// ports of com0com, a driver for pairs of virtual ports, by their driver name.
+    ///
+    /// Other virtual ports, such as those of USB CDC devices implemented in software, can't be
+    /// told apart from real ones and are reported as not virtual. The provided implementation
+    /// returns `false`.
+    fn is_virtual(&self) -> bool {
+        false
+    }
+
     /// Returns the current baud rate.
     ///
     /// This may return a value different from the last specified baud rate depending on the
@@ -323,6 +1141,32 @@ pub trait SerialPort: Send + io::Read + io::Write {
     /// baud rate.
     fn baud_rate(&self) -> Result<u32>;
 
+    /// Returns both the requested baud rate and the one the driver reports using.
+    ///
+    /// This is meant for debugging timing mismatches, where a driver quietly runs at a rate other
+    /// than the one asked for. The actual rate is read from `termios` on Linux and the BSDs,
+    /// taking a custom divisor set with `TTYPort::set_custom_divisor()` into account, and from
+    /// the `DCB` on Windows. It can't be read back on macOS, where `actual` is always `None`.
+    ///
+    /// For ports that weren't opened from a `SerialPortBuilder`, such as those created with
+    /// `TTYPort::pair()`, the requested rate isn't known and the rate from the driver is used.
+    /// The provided implementation reports the rate from `baud_rate()` as the requested one, and
+    /// no actual rate.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the settings could not be read from the underlying
+    /// hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn baud_rate_info(&self) -> Result<BaudRateInfo> {
+        Ok(BaudRateInfo {
+            requested: self.baud_rate()?,
+            actual: None,
+        })
+    }
+
     /// Returns the character size.
     ///
     /// This function returns `None` if the character size could not be determined. This may occur
@@ -355,8 +1199,66 @@ pub trait SerialPort: Send + io::Read + io::Write {
     fn stop_bits(&self) -> Result<StopBits>;
 
     /// Returns the current timeout.
+    ///
+    /// The timeout is cached per handle and is not shared with other handles to the same device.
     fn timeout(&self) -> Duration;
 
+    /// Returns the smallest increment in which the timeout set with `set_timeout()` is honored.
+    ///
+    /// Timeouts are truncated to a multiple of this, so a timeout shorter than it makes reads
+    /// return right away if no data is available. The timeout is applied with `ppoll()` on Linux,
+    /// `poll()` on the other POSIX platforms and `COMMTIMEOUTS` on Windows, all of which count in
+    /// milliseconds, so this is 1ms for the ports of this crate. It doesn't cover the
+    /// scheduler granularity of the OS, which can make waits last longer than requested.
+    ///
+    /// The inter-byte timeout set with `set_read_mode()` is coarser on POSIX, where `VTIME`
+    /// counts in tenths of a second.
+    ///
+    /// The provided implementation returns 1ms as well.
+    fn timeout_resolution(&self) -> Duration {
+        Duration::from_millis(1)
+    }
+
+    /// Returns the line ending translation applied by `write_str()` and `write_line()`.
+    fn line_ending(&self) -> LineEnding;
+
+    /// Returns a snapshot of the current port settings.
+    ///
+    /// The line settings are read from the driver in a single query rather than being returned
+    /// from a cached copy, so this reflects changes made by other handles or processes. The
+    /// timeout is the one cached by this handle. The path of the returned builder is set to the
+    /// name of this port, or is empty if the port has no name.
+    ///
+    /// The provided implementation calls the individual getters one after another instead, so
+    /// its result isn't a consistent snapshot.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the settings could not be read from the underlying
+    /// hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn settings(&self) -> Result<SerialPortBuilder> {
+        Ok(new(self.name().unwrap_or_default(), self.baud_rate()?)
+            .data_bits(self.data_bits()?)
+            .flow_control(self.flow_control()?)
+            .parity(self.parity()?)
+            .stop_bits(self.stop_bits()?)
+            .timeout(self.timeout())
+            .line_ending(self.line_ending()))
+    }
+
+    /// Returns a one-line summary of the port and its settings, for logging.
+    ///
+    /// The summary lists the name, the baud rate, the framing in the usual `8N1` notation, the
+    /// flow control and the timeout, like `/dev/ttyUSB0: 115200 baud 8N1, no flow control,
+    /// timeout 10ms`. The settings are read with `settings()`, and if that fails the error is
+    /// described instead. This is also what `Debug` prints for a `dyn SerialPort`.
+    fn describe(&self) -> String {
+        describe_port(self.name(), self.settings())
+    }
+
     // Port settings setters
 
     /// Sets the baud rate.
@@ -369,6 +1271,20 @@ pub trait SerialPort: Send + io::Read + io::Write {
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()>;
 
     /// Sets the character size.
+    ///
+    /// Some USB serial chipsets don't support all character sizes and silently fall back to 8
+    /// data bits, which corrupts data. For chipsets known to have this limitation, such as FTDI
+    /// devices, which only support 7 and 8 data bits, unsupported sizes are rejected upfront. For
+    /// other devices, the setting is read back after applying it.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if the device doesn't support `data_bits`. The previous character size
+    ///   stays in effect.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
     fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()>;
 
     /// Sets the flow control mode.
@@ -380,112 +1296,393 @@ pub trait SerialPort: Send + io::Read + io::Write {
     /// Sets the number of stop bits.
     fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()>;
 
-    /// Sets the timeout for future I/O operations.
-    fn set_timeout(&mut self, timeout: Duration) -> Result<()>;
-
-    // Functions for setting non-data control signal pins
-
-    /// Sets the state of the RTS (Request To Send) control signal.
+    /// Applies the settings of `new` at once and returns the previous settings.
     ///
-    /// Setting a value of `true` asserts the RTS control signal. `false` clears the signal.
+    /// The baud rate, data bits, flow control, parity and stop bits are written to the driver in
+    /// a single update (one `tcsetattr()` on POSIX, one `SetCommState()` on Windows), so the line
+    /// never runs with a mix of the old and new settings, as it would when calling the individual
+    /// setters one after another. macOS sets non-standard baud rates separately, right after the
+    /// other settings. The timeout and line ending of `new` are applied to this handle as well.
+    /// The path and the options only used when opening a port are ignored.
+    ///
+    /// The returned settings are the ones `settings()` reported beforehand, so passing them back
+    /// restores the previous configuration, e.g. after a test.
+    ///
+    /// The update is all-or-nothing: the settings are read back after writing them, and if any
+    /// step fails or the driver didn't take one of them, the previous configuration is written
+    /// back before the error is returned. Only if the device fails that as well, e.g. because it
+    /// was unplugged, can it be left with a mix of both.
     ///
     /// ## Errors
     ///
-    /// This function returns an error if the RTS control signal could not be set to the desired
-    /// state on the underlying hardware:
+    /// This function may return the following errors:
     ///
+    /// * `InvalidInput` if the device doesn't support the data bits or, on Linux, the baud rate
+    ///   of `new`, or silently ignored one of the other settings. The previous settings stay in
+    ///   effect. The provided implementation always returns this error, as it can't apply the
+    ///   settings at once.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn write_request_to_send(&mut self, level: bool) -> Result<()>;
+    fn swap_settings(&mut self, new: &SerialPortBuilder) -> Result<SerialPortBuilder> {
+        let _ = new;
+        Err(unsupported(ErrorKind::InvalidInput, "Swapping settings"))
+    }
 
-    /// Writes to the Data Terminal Ready pin
+    /// Switches a port in use to the settings of `new` without mixing up data of both.
     ///
-    /// Setting a value of `true` asserts the DTR control signal. `false` clears the signal.
+    /// This runs the sequence that changing the framing or baud rate of an active link needs:
+    /// it waits for pending output to be transmitted with the old settings, discards the input
+    /// received so far, applies `new` with `swap_settings()`, compares the settings read back
+    /// with `new`, and finally discards the input received while switching, which is likely
+    /// garbled.
     ///
     /// ## Errors
     ///
-    /// This function returns an error if the DTR control signal could not be set to the desired
-    /// state on the underlying hardware:
+    /// This function may return the following errors:
     ///
+    /// * `InvalidInput` if the settings couldn't be applied or read back differently, with a
+    ///   description of the differences. The previous settings are restored.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()>;
+    fn safe_reconfigure(&mut self, new: &SerialPortBuilder) -> Result<()> {
+        self.flush()?;
+        self.clear(ClearBuffer::Input)?;
+        let old = self.swap_settings(new)?;
+        let differences = new.differences(&self.settings()?);
+        if !differences.is_empty() {
+            let _ = self.swap_settings(&old);
+            return Err(settings_mismatch(differences));
+        }
+        self.clear(ClearBuffer::Input)
+    }
 
-    // Functions for reading additional pins
+    /// Returns whether the parity of received characters is checked.
+    ///
+    /// See `set_parity_check()`.
+    fn parity_check(&self) -> Result<bool> {
+        Err(unsupported(ErrorKind::InvalidInput, "Parity checking"))
+    }
 
-    /// Reads the state of the CTS (Clear To Send) control signal.
+    /// Sets whether the parity of received characters is checked.
     ///
-    /// This function returns a boolean that indicates whether the CTS control signal is asserted.
+    /// The parity mode set with `set_parity()` controls both the parity bit that is generated
+    /// for transmitted characters and the one expected on received characters. Checking the
+    /// received parity is a separate setting though: with checking disabled, the parity bit is
+    /// still generated on transmit, but received characters are passed through even if their
+    /// parity bit is wrong. This suits devices that expect parity but don't generate it
+    /// reliably. Enabling checking without parity has no effect.
+    ///
+    /// This controls `INPCK` on POSIX and `fParity` of the `DCB` on Windows. It's reset by
+    /// `set_parity()`, which enables checking unless the parity is `Parity::None`.
     ///
     /// ## Errors
     ///
-    /// This function returns an error if the state of the CTS control signal could not be read
-    /// from the underlying hardware:
+    /// This function may return the following errors:
     ///
+    /// * `InvalidInput` if the port doesn't support disabling the parity check separately. This
+    ///   is what the provided implementation returns.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn read_clear_to_send(&mut self) -> Result<bool>;
+    fn set_parity_check(&mut self, enabled: bool) -> Result<()> {
+        let _ = enabled;
+        Err(unsupported(ErrorKind::InvalidInput, "Parity checking"))
+    }
 
-    /// Reads the state of the Data Set Ready control signal.
+    /// Sets the timeout for future I/O operations.
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()>;
+
+    /// Sets the line ending translation applied by `write_str()` and `write_line()`.
     ///
-    /// This function returns a boolean that indicates whether the DSR control signal is asserted.
+    /// Like the timeout, this is cached per handle and doesn't affect the raw `io::Write`
+    /// methods.
+    fn set_line_ending(&mut self, line_ending: LineEnding);
+
+    /// Sets how many bytes a read waits for once data started arriving.
     ///
-    /// ## Errors
+    /// After the first byte has been received (which still takes up to the port timeout), a
+    /// read returns as soon as `min_bytes` bytes have been received, or once no byte has been
+    /// received for `inter_byte_timeout`, whichever comes first. The inter-byte timer restarts
+    /// with every byte, so this doesn't bound the total duration of a read. An
+    /// `inter_byte_timeout` of zero is only accepted with a `min_bytes` of 0 or 1, as a larger
+    /// `min_bytes` would block forever on POSIX if fewer bytes arrive. Ports are opened with `min_bytes` of 1 and no inter-byte timeout, so reads return
+    /// whatever data is available.
     ///
-    /// This function returns an error if the state of the DSR control signal could not be read
-    /// from the underlying hardware:
+    /// On POSIX this sets `VMIN` and `VTIME`, so `inter_byte_timeout` is rounded up to a multiple
+    /// of 100ms. Windows can't wait for a minimum number of bytes, so `min_bytes` is ignored
+    /// there: reads return once the inter-byte timeout elapsed, `buf` is full, or the port
+    /// timeout elapsed since the read started.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
     ///
+    /// * `InvalidInput` if `inter_byte_timeout` is longer than supported (25.5s on POSIX), if it
+    ///   is zero while `min_bytes` is greater than 1, or if the port doesn't support read modes,
+    ///   as with the provided implementation.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn read_data_set_ready(&mut self) -> Result<bool>;
+    fn set_read_mode(&mut self, min_bytes: u8, inter_byte_timeout: Duration) -> Result<()> {
+        let _ = (min_bytes, inter_byte_timeout);
+        Err(unsupported(
+            ErrorKind::InvalidInput,
+            "Setting the read mode",
+        ))
+    }
 
-    /// Reads the state of the Ring Indicator control signal.
+    /// Returns the delay inserted between written bytes.
     ///
-    /// This function returns a boolean that indicates whether the RI control signal is asserted.
+    /// See `set_inter_byte_write_delay()`.
+    fn inter_byte_write_delay(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    /// Sets a delay to insert between written bytes.
+    ///
+    /// With a non-zero delay, `write()` sends one byte at a time, waits for each to be
+    /// transmitted and then sleeps for `delay` before sending the next. This paces the output
+    /// for slow receivers without flow control that drop bytes at full speed. It comes at a
+    /// large cost in throughput, as every byte incurs a drain and a sleep on top of its
+    /// transmission time, so it should only be used for devices that need it.
+    ///
+    /// Ports are opened with a delay of zero, which writes data in bulk. Like the timeout, this
+    /// is cached per handle, so it currently always succeeds for the ports of this crate. The
+    /// provided implementation only accepts a delay of zero and fails with `InvalidInput`
+    /// otherwise.
+    fn set_inter_byte_write_delay(&mut self, delay: Duration) -> Result<()> {
+        if delay == Duration::from_secs(0) {
+            Ok(())
+        } else {
+            Err(unsupported(
+                ErrorKind::InvalidInput,
+                "An inter-byte write delay",
+            ))
+        }
+    }
+
+    /// Returns whether writes wait for their data to be transmitted.
+    ///
+    /// See `set_write_unbuffered()`.
+    fn write_unbuffered(&self) -> bool {
+        false
+    }
+
+    /// Makes every write wait until its data has been transmitted.
+    ///
+    /// Normally `write()` returns as soon as the data was copied into the driver's output
+    /// buffer, and it's sent out later. When `unbuffered` is `true`, every `write()` is followed
+    /// by a drain (as with `flush()`), so it returns once the data left the port and no data
+    /// queues up behind it. This makes the timing of the output deterministic, for example for
+    /// controlling test equipment, at the cost of throughput. If the drain fails, `write()` still
+    /// returns the number of bytes written, and the error shows up on the next call.
+    ///
+    /// This is best-effort: the data may still sit in the FIFO of the UART or USB adapter when
+    /// the drain returns, depending on how precisely the driver tracks it. The sizes of the
+    /// driver buffers are left alone, as drivers commonly ignore the sizes requested with
+    /// `SetupComm()` on Windows and the tty buffers on POSIX can't be resized.
+    ///
+    /// Ports are opened with buffered writes. Like the timeout, this is cached per handle, so it
+    /// currently always succeeds for the ports of this crate. The provided implementation only
+    /// accepts `false` and fails with `InvalidInput` otherwise.
+    fn set_write_unbuffered(&mut self, unbuffered: bool) -> Result<()> {
+        if unbuffered {
+            Err(unsupported(ErrorKind::InvalidInput, "Unbuffered writing"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the cap on the number of bytes a single `read()` returns, if any.
+    ///
+    /// See `set_max_read_size()`.
+    fn max_read_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Caps the number of bytes a single `read()` returns, regardless of the buffer size.
+    ///
+    /// This makes the port hand out data in pieces of at most `max` bytes, like a device that
+    /// dribbles bytes or a driver with a small buffer, which is useful for testing how protocol
+    /// parsers deal with fragmented reads, e.g. together with `TTYPort::pair()`. It applies to
+    /// all reads through this handle, including the helpers built on `read()`. `None` removes
+    /// the cap, which is the default.
+    ///
+    /// Like the timeout, this is cached per handle.
     ///
     /// ## Errors
     ///
-    /// This function returns an error if the state of the RI control signal could not be read from
-    /// the underlying hardware:
+    /// * `InvalidInput` if `max` is `Some(0)`, as a read of zero bytes means the end of the data.
+    ///   The provided implementation fails with this for every cap other than `None`.
+    fn set_max_read_size(&mut self, max: Option<usize>) -> Result<()> {
+        check_max_read_size(max)?;
+        match max {
+            Some(_) => Err(unsupported(
+                ErrorKind::InvalidInput,
+                "Capping the read size",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether the port is closed in child processes.
+    ///
+    /// See `set_close_on_exec()`.
+    ///
+    /// # Errors
     ///
+    /// This function returns an error if the flag couldn't be read. The provided implementation
+    /// always fails with `InvalidInput`.
+    fn close_on_exec(&self) -> Result<bool> {
+        Err(unsupported(ErrorKind::InvalidInput, "Close-on-exec"))
+    }
+
+    /// Sets whether the port is closed in child processes.
+    ///
+    /// A child process that inherits the port keeps it open after this process closed it, which
+    /// then can't be reopened until the child exits. Ports are therefore opened with the
+    /// close-on-exec flag set (`FD_CLOEXEC` on POSIX), and aren't inheritable on Windows (the
+    /// `HANDLE_FLAG_INHERIT` flag isn't set). This also applies to handles returned by
+    /// `try_clone()`. Clear the flag to pass the port to a child process on purpose.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the flag couldn't be changed. The provided
+    /// implementation always fails with `InvalidInput`.
+    fn set_close_on_exec(&mut self, cloexec: bool) -> Result<()> {
+        let _ = cloexec;
+        Err(unsupported(ErrorKind::InvalidInput, "Close-on-exec"))
+    }
+
+    /// Enables or disables a fully transparent 8-bit data path.
+    ///
+    /// Ports are opened in raw mode already, but settings made by other programs or with
+    /// `set_flow_control(FlowControl::Software)` may still alter the data. With `binary` set, no
+    /// byte is translated, dropped or inserted in either direction, and characters are 8 bits
+    /// wide. This also disables software flow control, while parity, stop bits and hardware
+    /// flow control are left unchanged.
+    ///
+    /// On POSIX this clears `ISTRIP`, `IXON`, `IXOFF`, `INLCR`, `ICRNL`, `IGNCR` and `PARMRK` in
+    /// the input flags, `OPOST` in the output flags and `ICANON`, `ECHO`, `ECHONL`, `ISIG` and
+    /// `IEXTEN` in the local flags, and sets the character size to `CS8`. Disabling binary
+    /// mode sets `ICRNL`, `OPOST` and `ONLCR` to translate line endings.
+    ///
+    /// On Windows this sets `fBinary` and a `ByteSize` of 8 and clears `fNull`, `fErrorChar`,
+    /// `fOutX` and `fInX` in the `DCB`. Windows doesn't support disabling binary mode.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if `binary` is `false` on Windows, or always for the provided
+    ///   implementation.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn read_ring_indicator(&mut self) -> Result<bool>;
+    fn set_binary_mode(&mut self, binary: bool) -> Result<()> {
+        let _ = binary;
+        Err(unsupported(ErrorKind::InvalidInput, "Binary mode"))
+    }
 
-    /// Reads the state of the Carrier Detect control signal.
+    /// Sets the input buffer levels at which software flow control pauses and resumes the sender.
     ///
-    /// This function returns a boolean that indicates whether the CD control signal is asserted.
+    /// The driver sends XOFF once more than `high` bytes are waiting in the input buffer, and XON
+    /// once they drop back below `low`. These only matter with `FlowControl::Software`.
     ///
-    /// ## Errors
+    /// This is only supported on Windows. The thresholds of the Linux and BSD tty layers are
+    /// fixed.
     ///
-    /// This function returns an error if the state of the CD control signal could not be read from
-    /// the underlying hardware:
+    /// # Errors
+    ///
+    /// This function may return the following errors:
     ///
+    /// * `InvalidInput` if `low` isn't lower than `high`, `high` exceeds the size of the input
+    ///   buffer, or watermarks can't be configured on this platform or port. The provided
+    ///   implementation always returns this error.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn read_carrier_detect(&mut self) -> Result<bool>;
+    fn set_flow_watermarks(&mut self, low: usize, high: usize) -> Result<()> {
+        let _ = (low, high);
+        Err(unsupported(
+            ErrorKind::InvalidInput,
+            "Setting flow control watermarks",
+        ))
+    }
 
-    /// Gets the number of bytes available to be read from the input buffer.
+    /// Sets whether the sender is paused once the input buffer fills up.
+    ///
+    /// This only changes the receiving half of the flow control set with `set_flow_control()`,
+    /// so `FlowControl::Hardware` or `Software` continues to pause this port's output when the
+    /// other end asks for it. The mapping is:
+    ///
+    /// * `DropIncoming` clears `IXOFF` on POSIX, and on Windows disables `fInX` and sets
+    ///   `fRtsControl` to `RTS_CONTROL_ENABLE` in place of `RTS_CONTROL_HANDSHAKE`.
+    /// * `AssertFlowControl` sets `IXOFF` with software flow control on POSIX, where hardware
+    ///   flow control always lowers RTS as the buffer fills. On Windows it enables `fInX` or sets
+    ///   `fRtsControl` to `RTS_CONTROL_HANDSHAKE`, which pause the sender at the thresholds set
+    ///   with `set_flow_watermarks()`.
+    ///
+    /// The default depends on the platform and the flow control mode, and is restored after
+    /// every change of the flow control mode:
+    ///
+    /// * With `FlowControl::Software`, ports pause the sender as with `AssertFlowControl`.
+    /// * With `FlowControl::Hardware`, POSIX ports pause the sender as with
+    ///   `AssertFlowControl`. Windows ports behave as with `DropIncoming`, since
+    ///   `set_flow_control()` sets `fRtsControl` to `RTS_CONTROL_ENABLE`, which keeps RTS
+    ///   asserted however full the buffer gets, and only `AssertFlowControl` hands RTS to the
+    ///   driver.
+    ///
+    /// `fAbortOnError` is always disabled on Windows, so an overrun never aborts pending I/O
+    /// under either policy.
     ///
     /// # Errors
     ///
     /// This function may return the following errors:
     ///
+    /// * `InvalidInput` for `AssertFlowControl` without flow control, or for `DropIncoming` with
+    ///   hardware flow control on POSIX, where RTS/CTS can only be enabled for both directions.
+    ///   The provided implementation always returns this error.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn bytes_to_read(&self) -> Result<u32>;
+    fn set_overflow_policy(&mut self, policy: OverflowPolicy) -> Result<()> {
+        let _ = policy;
+        Err(unsupported(
+            ErrorKind::InvalidInput,
+            "Setting the overflow policy",
+        ))
+    }
 
-    /// Get the number of bytes written to the output buffer, awaiting transmission.
+    /// Sets whether XON and XOFF are passed through to the application.
+    ///
+    /// With `passthrough`, the driver stops acting on received XON/XOFF bytes and stops sending
+    /// them itself, so they appear in the data returned by `read()` like any other byte. This is
+    /// for protocol stacks that implement software flow control themselves. It clears `IXON` and
+    /// `IXOFF` on POSIX, and `fOutX` and `fInX` of the `DCB` on Windows. Without `passthrough`,
+    /// these are set again and the driver handles XON/XOFF.
+    ///
+    /// This is the part of `FlowControl::Software` that concerns the XON/XOFF bytes, so
+    /// `flow_control()` reports `Software` without passthrough and `None` with it, and
+    /// `set_flow_control()` replaces this setting. Passthrough is already in effect with
+    /// `FlowControl::None`.
     ///
     /// # Errors
     ///
     /// This function may return the following errors:
     ///
+    /// * `InvalidInput` for disabling passthrough while hardware flow control is enabled, which
+    ///   can't be combined with software flow control. The provided implementation always
+    ///   returns this error.
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn bytes_to_write(&self) -> Result<u32>;
+    fn set_flow_control_passthrough(&mut self, passthrough: bool) -> Result<()> {
+        let _ = passthrough;
+        Err(unsupported(ErrorKind::InvalidInput, "XON/XOFF passthrough"))
+    }
 
-    /// Discards all bytes from the serial driver's input buffer and/or output buffer.
+    /// Saves the flow control configuration, to be restored with `restore_flow_control()`.
+    ///
+    /// Unlike `flow_control()`, this keeps the raw driver state: `IXON`, `IXOFF` and `CRTSCTS`
+    /// on POSIX, and `fOutxCtsFlow`, `fRtsControl`, `fOutX` and `fInX` of the `DCB` on Windows.
+    /// An overflow policy or XON/XOFF passthrough, and an RTS control mode other than
+    /// handshaking on Windows, therefore survive a round trip through another mode. The provided
+    /// implementation only saves the mode reported by `flow_control()`.
     ///
     /// # Errors
     ///
@@ -493,211 +1690,4256 @@ pub trait SerialPort: Send + io::Read + io::Write {
     ///
     /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
-    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()>;
-
-    // Misc methods
+    fn save_flow_control(&self) -> Result<SavedFlowControl> {
+        Ok(SavedFlowControl {
+            flow_control: self.flow_control()?,
+            raw: None,
+        })
+    }
 
-    /// Attempts to clone the `SerialPort`. This allow you to write and read simultaneously from the
-    /// same serial connection. Please note that if you want a real asynchronous serial port you
-    /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
-    /// [tokio-serial](https://crates.io/crates/tokio-serial).
+    /// Restores a flow control configuration saved with `save_flow_control()`.
     ///
-    /// Also, you must be very carefull when changing the settings of a cloned `SerialPort` : since
-    /// the settings are cached on a per object basis, trying to modify them from two different
-    /// objects can cause some nasty behavior.
+    /// Only the flow control state is written back, other settings changed since it was saved
+    /// are kept. Configurations saved by the provided `save_flow_control()` are restored with
+    /// `set_flow_control()`, which the provided implementation does for all of them.
     ///
     /// # Errors
     ///
-    /// This function returns an error if the serial port couldn't be cloned.
-    fn try_clone(&self) -> Result<Box<dyn SerialPort>>;
-
-    /// Start transmitting a break
-    fn set_break(&self) -> Result<()>;
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn restore_flow_control(&mut self, saved: &SavedFlowControl) -> Result<()> {
+        self.set_flow_control(saved.flow_control)
+    }
 
-    /// Stop transmitting a break
-    fn clear_break(&self) -> Result<()>;
-}
+    // Functions for setting non-data control signal pins
 
-impl<T: SerialPort> SerialPort for &mut T {
-    fn name(&self) -> Option<String> {
-        (**self).name()
+    /// Reads the state of all control signals at once.
+    ///
+    /// Besides the input signals CTS, DSR, RI and CD, this includes the levels of the output
+    /// signals RTS and DTR. Windows can't read back the output signals, so there they are the
+    /// levels last written through this handle, or otherwise those implied by the RTS and DTR
+    /// control modes of the port.
+    ///
+    /// The provided implementation fails with `Unknown`. Ports that implement it should do so
+    /// together with `set_modem_lines_masked()`, as the methods for the individual signals are
+    /// based on these two.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn modem_lines(&self) -> Result<ModemLines> {
+        Err(unsupported(
+            ErrorKind::Unknown,
+            "Reading the control signals",
+        ))
     }
 
-    fn baud_rate(&self) -> Result<u32> {
-        (**self).baud_rate()
+    /// Asserts the output signals in `set` and clears those in `clear`.
+    ///
+    /// Signals in neither mask keep their state. Only `ModemLines::RTS` and `ModemLines::DTR` can
+    /// be changed. On POSIX the signals in `set` are asserted with one call into the driver
+    /// (`TIOCMBIS`) and then those in `clear` are cleared with another (`TIOCMBIC`), so if both
+    /// masks are given, the signals in `set` change slightly before those in `clear`. Changes
+    /// made to the lines by other handles in between aren't undone. On Windows each signal is
+    /// changed on its own, one after the other.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if a mask contains an input signal, if a signal is in both masks, or on
+    ///   Windows if a signal is controlled by the driver (see `COMPort::set_rts_control_mode()`).
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    /// * `Unknown` for the provided implementation, which doesn't change any signal.
+    fn set_modem_lines_masked(&mut self, set: ModemLines, clear: ModemLines) -> Result<()> {
+        check_modem_line_masks(set, clear)?;
+        Err(unsupported(
+            ErrorKind::Unknown,
+            "Setting the control signals",
+        ))
     }
 
-    fn data_bits(&self) -> Result<DataBits> {
-        (**self).data_bits()
+    /// Sets the state of the RTS (Request To Send) control signal.
+    ///
+    /// Setting a value of `true` asserts the RTS control signal. `false` clears the signal.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the RTS control signal could not be set to the desired
+    /// state on the underlying hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        if level {
+            self.set_modem_lines_masked(ModemLines::RTS, ModemLines::empty())
+        } else {
+            self.set_modem_lines_masked(ModemLines::empty(), ModemLines::RTS)
+        }
     }
 
-    fn flow_control(&self) -> Result<FlowControl> {
-        (**self).flow_control()
+    /// Writes to the Data Terminal Ready pin
+    ///
+    /// Setting a value of `true` asserts the DTR control signal. `false` clears the signal.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the DTR control signal could not be set to the desired
+    /// state on the underlying hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        if level {
+            self.set_modem_lines_masked(ModemLines::DTR, ModemLines::empty())
+        } else {
+            self.set_modem_lines_masked(ModemLines::empty(), ModemLines::DTR)
+        }
     }
 
-    fn parity(&self) -> Result<Parity> {
-        (**self).parity()
+    /// Sets the levels DTR and RTS are left at when the port is closed.
+    ///
+    /// Drivers usually deassert both lines on close, which resets or powers down devices that
+    /// depend on them. The levels given here are applied when the port is dropped or reopened,
+    /// right before it's closed, and `None` leaves a line to the driver. On POSIX the lines are
+    /// written and `HUPCL` is cleared, so closing the port doesn't drop them again. `HUPCL` stays
+    /// cleared for later opens of the device, and once it's cleared a line given as `None` keeps
+    /// its current level as well. On Windows the DTR and RTS control of the DCB is set to match,
+    /// which doesn't keep every driver from deasserting the lines on close.
+    ///
+    /// This is stored per handle: clones made with `try_clone()` start without it, and it's
+    /// kept by `reopen()`. Errors while applying it can't be reported from `Drop` and are
+    /// ignored. Setting it currently always succeeds for the ports of this crate. The provided
+    /// implementation only accepts `None` for both lines and fails with `InvalidInput` otherwise.
+    fn set_close_behavior(&mut self, dtr: Option<bool>, rts: Option<bool>) -> Result<()> {
+        if dtr.is_none() && rts.is_none() {
+            Ok(())
+        } else {
+            Err(unsupported(
+                ErrorKind::InvalidInput,
+                "Setting the close behavior",
+            ))
+        }
     }
 
-    fn stop_bits(&self) -> Result<StopBits> {
-        (**self).stop_bits()
+    // Functions for reading additional pins
+
+    /// Reads the state of the CTS (Clear To Send) control signal.
+    ///
+    /// This function returns a boolean that indicates whether the CTS control signal is asserted.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the state of the CTS control signal could not be read
+    /// from the underlying hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        Ok(self.modem_lines()?.contains(ModemLines::CTS))
+    }
+
+    /// Reads the state of the Data Set Ready control signal.
+    ///
+    /// This function returns a boolean that indicates whether the DSR control signal is asserted.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the state of the DSR control signal could not be read
+    /// from the underlying hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        Ok(self.modem_lines()?.contains(ModemLines::DSR))
+    }
+
+    /// Reads the state of the Ring Indicator control signal.
+    ///
+    /// This function returns a boolean that indicates whether the RI control signal is asserted.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the state of the RI control signal could not be read from
+    /// the underlying hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        Ok(self.modem_lines()?.contains(ModemLines::RI))
+    }
+
+    /// Reads the state of the Carrier Detect control signal.
+    ///
+    /// This function returns a boolean that indicates whether the CD control signal is asserted.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the state of the CD control signal could not be read from
+    /// the underlying hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        Ok(self.modem_lines()?.contains(ModemLines::CD))
+    }
+
+    /// Blocks until the state of CTS, DSR, RI or CD changes and returns the new state.
+    ///
+    /// With a `timeout` of `None` this waits forever. On Linux without a timeout, and on
+    /// Windows, this waits for a change notification from the driver. Otherwise the signals are
+    /// polled every few milliseconds, so very short pulses may be missed. The provided
+    /// implementation polls `read_modem_status()` as well.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `Io(TimedOut)` if no signal changed within `timeout`.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error, including drivers that don't support reading the
+    ///   control signals.
+    fn wait_for_modem_change(&mut self, timeout: Option<Duration>) -> Result<ModemStatus> {
+        let initial = self.read_modem_status()?;
+        poll_modem_change(self, initial, timeout)
+    }
+
+    /// Waits for a USB device to re-enumerate and reopens the port on it.
+    ///
+    /// This is meant for devices that reset themselves, such as after a firmware update: call it
+    /// right after issuing the reset command. It first waits for the device to go away (see
+    /// `awaits_reenumeration()`), then for a USB port matching `identity` to reappear, which may
+    /// have a different name than before. That port is opened with the settings this port had
+    /// before the reset and replaces the handle of `self`.
+    ///
+    /// The settings are read when this is called. If the device already disconnected by that
+    /// time they can't be read anymore, and the port is reopened at 9600 baud with the default
+    /// settings instead, keeping only the timeout and line ending.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `Io(TimedOut)` if the device didn't disconnect and reappear within `timeout`.
+    /// * `NoDevice` if the reappeared device couldn't be opened before the timeout.
+    /// * `Io` for any other type of I/O error while opening it.
+    /// * `InvalidInput` if the port can't be reopened, which is always the case for the
+    ///   provided implementation.
+    fn wait_reenumerate(&mut self, identity: &UsbIdentity, timeout: Duration) -> Result<()> {
+        let _ = (identity, timeout);
+        Err(unsupported(ErrorKind::InvalidInput, "Reopening"))
+    }
+
+    /// Closes and reopens the port, e.g. to recover from a transient `NoDevice` error.
+    ///
+    /// The port is reopened with the path and builder options it was opened with, and the
+    /// settings last applied through this handle: baud rate, data bits, parity, stop bits, flow
+    /// control, timeout and line ending. DTR and RTS are restored to the levels last written
+    /// through this handle. Changes made through other handles aren't carried over, as the
+    /// driver may no longer be able to report them once the device is gone. Ports that weren't
+    /// opened from a builder, such as the slave of `TTYPort::pair()`, are reopened with their
+    /// current settings.
+    ///
+    /// The old handle is closed first, after applying the levels set with `set_close_behavior()`,
+    /// so if reopening fails, the port remains closed and all other operations fail until
+    /// `reopen()` succeeds.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if the port wasn't opened from a path, e.g. ports created from a raw
+    ///   file descriptor or handle, or can't be reopened at all, as with the provided
+    ///   implementation.
+    /// * `NoDevice` if the device isn't present (yet).
+    /// * `Io` for any other type of I/O error.
+    fn reopen(&mut self) -> Result<()> {
+        Err(unsupported(ErrorKind::InvalidInput, "Reopening"))
+    }
+
+    /// Gets the number of bytes available to be read from the input buffer.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn bytes_to_read(&self) -> Result<u32>;
+
+    /// Waits until data is available to be read, without consuming it.
+    ///
+    /// Returns `true` as soon as at least one byte can be read, or `false` if none arrived within
+    /// `timeout`. A `timeout` of zero checks without waiting. This is implemented with `poll()`
+    /// on POSIX and `WaitCommEvent()` for `EV_RXCHAR` on Windows.
+    ///
+    /// # Errors
+    ///
+    /// Running out of time isn't an error. This function may return the following errors:
+    ///
+    /// * `InvalidInput` if the port can't wait for data, which is always the case for the
+    ///   provided implementation.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn has_data(&self, timeout: Duration) -> Result<bool> {
+        let _ = timeout;
+        Err(unsupported(ErrorKind::InvalidInput, "Waiting for data"))
+    }
+
+    /// Returns the largest number of bytes `bytes_to_read()` has reported.
+    ///
+    /// This shows how full the input buffer gets under load, which helps deciding whether to
+    /// read more often or to increase buffer sizes. It only reflects the queries made with
+    /// `bytes_to_read()` on this handle, so the actual peak may be higher in between them. The
+    /// provided implementation doesn't track it and always returns zero.
+    fn rx_watermark(&self) -> u32 {
+        0
+    }
+
+    /// Resets the value returned by `rx_watermark()` to zero.
+    fn reset_rx_watermark(&mut self) {}
+
+    /// Get the number of bytes written to the output buffer, awaiting transmission.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn bytes_to_write(&self) -> Result<u32>;
+
+    /// Returns the size of the driver's input buffer in bytes.
+    ///
+    /// See `tx_buffer_capacity()`.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `Unknown` if the driver doesn't report its buffer size. This is always the case on
+    ///   POSIX, and for the provided implementation.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn rx_buffer_capacity(&self) -> Result<u32> {
+        Err(unsupported(
+            ErrorKind::Unknown,
+            "Reporting the input buffer size",
+        ))
+    }
+
+    /// Returns the size of the driver's output buffer in bytes.
+    ///
+    /// Writes only block once the output buffer is full, so as long as the data written at once
+    /// doesn't exceed this minus `bytes_to_write()`, a write returns without waiting for data to
+    /// be transmitted.
+    ///
+    /// This is the current queue size reported by Windows, as set with `SetupComm`. The tty
+    /// layers of Linux and BSD don't expose their buffer sizes, so it's not available there.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `Unknown` if the driver doesn't report its buffer size. This is always the case on
+    ///   POSIX, and for the provided implementation.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn tx_buffer_capacity(&self) -> Result<u32> {
+        Err(unsupported(
+            ErrorKind::Unknown,
+            "Reporting the output buffer size",
+        ))
+    }
+
+    /// Gets the number of bytes in the input and output buffers, and their sizes if known.
+    ///
+    /// On Windows both fill levels come from a single `ClearCommError()` call, so they're a
+    /// consistent snapshot, and the buffer sizes are those of `rx_buffer_capacity()` and
+    /// `tx_buffer_capacity()`. On POSIX the fill levels are read with two consecutive ioctls,
+    /// and the buffer sizes are never known. The provided implementation works like the POSIX
+    /// one, and takes the buffer sizes from `rx_buffer_capacity()` and `tx_buffer_capacity()`.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn queue_status(&self) -> Result<QueueStatus> {
+        Ok(QueueStatus {
+            bytes_to_read: self.bytes_to_read()?,
+            bytes_to_write: self.bytes_to_write()?,
+            rx_capacity: self.rx_buffer_capacity().ok(),
+            tx_capacity: self.tx_buffer_capacity().ok(),
+        })
+    }
+
+    /// Checks whether writing `bytes` bytes would return without waiting for transmission.
+    ///
+    /// The write fits if the free space of the output buffer, `tx_buffer_capacity()` minus
+    /// `bytes_to_write()`, is at least `bytes`. Under hardware flow control this is `false`
+    /// whenever CTS is deasserted, since nothing is transmitted until the other end asserts it
+    /// again. A pause requested with XOFF under software flow control isn't visible to the
+    /// driver interface and isn't accounted for.
+    ///
+    /// The buffer levels come from a single `queue_status()` call, but they can change right
+    /// after, so the result is only a hint for a following write.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `Unknown` if the driver doesn't report its output buffer size. This is always the case
+    ///   on POSIX.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn can_write(&self, bytes: usize) -> Result<bool> {
+        let status = self.queue_status()?;
+        let flow_control = self.flow_control()?;
+        // Only read the lines when they matter, not every port reports them
+        let lines = if status.tx_capacity.is_some() && flow_control == FlowControl::Hardware {
+            Some(self.modem_lines()?)
+        } else {
+            None
+        };
+        write_fits(&status, flow_control, lines, bytes)
+    }
+
+    /// Reports which settings the port supports.
+    ///
+    /// On Windows this is what the driver reports with `GetCommProperties()`, narrowed down by
+    /// what's known about the port's chipset. There's no such interface on POSIX, so the result
+    /// only reflects the known limits of the chipset and the platform, and lists every setting
+    /// otherwise. Settings that aren't listed are certain not to work, but the ones listed may
+    /// still be rejected when they're applied. The provided implementation does the same as on
+    /// POSIX, based on `port_type()`.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn capabilities(&self) -> Result<PortCapabilities> {
+        Ok(chipset_capabilities(&self.port_type()))
+    }
+
+    /// Reports which optional operations the port supports.
+    ///
+    /// This lets callers branch on what the port can do instead of trying an operation and
+    /// handling the error, which matters for operations that virtual ports silently accept, like
+    /// sending a break on a pseudo terminal. Each flag is found by probing the port, so an
+    /// operation that's reported may still fail, for example when the device is disconnected.
+    ///
+    /// The provided implementation probes the operations that can be detected through the other
+    /// methods of this trait, which covers every flag except `CUSTOM_DIVISOR` and
+    /// `RS485_RTS_TOGGLE`.
+    fn supported_operations(&self) -> SupportedOps {
+        common_supported_operations(self)
+    }
+
+    /// Discards all bytes from the serial driver's input buffer and/or output buffer.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()>;
+
+    /// Gets the byte and line error counters accumulated since the port was opened, or since the
+    /// last call to `reset_counters()`.
+    ///
+    /// The driver doesn't zero its counters when the device is opened, so the counts it reports at
+    /// open time are stored as the initial baseline and subtracted from later readings. The
+    /// driver's counters are shared between all handles to the same device, but the baseline is
+    /// kept on a per object basis.
+    ///
+    /// This is only supported on Linux, where the counters are read with the
+    /// `TIOCGICOUNT` ioctl. Not all drivers implement it; USB adapters in particular often don't.
+    ///
+    /// # Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` if the driver doesn't maintain counters, or for any other type of I/O error.
+    /// * `Unknown` if counters aren't supported on this platform or port. The provided
+    ///   implementation always returns this error.
+    fn counters(&self) -> Result<LineCounters> {
+        Err(unsupported(ErrorKind::Unknown, "Line counters"))
+    }
+
+    /// Resets the counters returned by `counters()` to zero.
+    ///
+    /// The driver's counters can't be cleared, so this stores the current counts as a baseline
+    /// which is subtracted from later readings.
+    ///
+    /// # Errors
+    ///
+    /// See `counters()`.
+    fn reset_counters(&mut self) -> Result<()> {
+        Err(unsupported(ErrorKind::Unknown, "Line counters"))
+    }
+
+    // Misc methods
+
+    /// Attempts to clone the `SerialPort`. This allow you to write and read simultaneously from the
+    /// same serial connection. Please note that if you want a real asynchronous serial port you
+    /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
+    /// [tokio-serial](https://crates.io/crates/tokio-serial).
+    ///
+    /// Also, you must be very carefull when changing the settings of a cloned `SerialPort` : while
+    /// most settings are read from and written to the driver directly, the timeout is cached on a
+    /// per object basis, and modifying settings from two different objects can cause some nasty
+    /// behavior.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the serial port couldn't be cloned.
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>>;
+
+    /// Start transmitting a break
+    fn set_break(&self) -> Result<()>;
+
+    /// Stop transmitting a break
+    fn clear_break(&self) -> Result<()>;
+
+    // Helper methods
+
+    /// Probes whether the given baud rate is actually used by the port.
+    ///
+    /// Some drivers accept any baud rate in `set_baud_rate()` but silently run at another one.
+    /// This sets the requested baud rate, reads back the rate reported by the driver, and then
+    /// restores the original baud rate. The rate is considered supported if the driver reports a
+    /// rate within 2% of the requested one, which is about as much of a mismatch as UARTs can
+    /// tolerate between both ends of a link.
+    ///
+    /// Note that on macOS the actual baud rate can't be read back, so `true` is returned whenever
+    /// the baud rate was accepted.
+    ///
+    /// ## Errors
+    ///
+    /// A baud rate rejected by `set_baud_rate()` is reported as `Ok(false)`. This function returns
+    /// an error if the current baud rate could not be read or restored:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn is_baud_rate_supported(&mut self, baud_rate: u32) -> Result<bool> {
+        let original = self.baud_rate()?;
+        let supported = match self.set_baud_rate(baud_rate) {
+            Ok(()) => self
+                .baud_rate()
+                .map(|actual| baud_rate_matches(baud_rate, actual, BAUD_RATE_TOLERANCE_PERCENT)),
+            Err(e) if e.kind() == ErrorKind::NoDevice => Err(e),
+            Err(_) => Ok(false),
+        };
+        self.set_baud_rate(original)?;
+        supported
+    }
+
+    /// Returns whether `FlowControl::Hardware` is known to work on this port.
+    ///
+    /// Not all USB bridges have RTS and CTS, and drivers for some of them accept hardware flow
+    /// control without implementing it, so writes may stall or data may be lost without any
+    /// error. This is decided from the port type by a table of known chipsets: FTDI, Silicon
+    /// Labs CP210x and Prolific bridges as well as PCI UARTs support it, while the WCH CH340
+    /// doesn't. Nothing is sent and the control signals aren't touched.
+    ///
+    /// Whether a supporting chip actually has its RTS and CTS pins wired up depends on the board
+    /// or cable, which can't be determined from the host.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns `Unknown` if the chipset isn't known, including ports whose type is
+    /// `SerialPortType::Unknown`, such as pseudo terminals.
+    fn supports_hardware_flow_control(&self) -> Result<bool> {
+        hardware_flow_control_support(&self.port_type()).ok_or_else(|| {
+            Error::new(
+                ErrorKind::Unknown,
+                "Hardware flow control support of the device is unknown",
+            )
+        })
+    }
+
+    /// Guesses the flow control used by the device on the other end of the line.
+    ///
+    /// This sends `probe`, which should be something the device is known to answer, and waits up
+    /// to `timeout` for a reply:
+    ///
+    /// * `Hardware` is tried first if CTS is asserted, and is kept if CTS stays asserted for the
+    ///   whole exchange and a reply was received.
+    /// * Otherwise the probe is sent without flow control. If the reply contains XON or XOFF bytes
+    ///   the device is assumed to use `Software` flow control, otherwise `None`.
+    ///
+    /// This is only a heuristic, meant to help bringing up unknown devices. The original flow
+    /// control and timeout are restored afterward, and any data received is discarded.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the device never answered the probe:
+    ///
+    /// * `Io(TimedOut)` if no reply was received with any flow control mode.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn detect_flow_control(&mut self, probe: &[u8], timeout: Duration) -> Result<FlowControl> {
+        let original_flow_control = self.flow_control()?;
+        let original_timeout = self.timeout();
+        self.set_timeout(timeout)?;
+        let detected = probe_flow_control(self, probe);
+        let restored = self
+            .set_flow_control(original_flow_control)
+            .and_then(|_| self.set_timeout(original_timeout));
+        let detected = detected?;
+        restored?;
+        Ok(detected)
+    }
+
+    /// Sends `request` and reads the reply into `response`.
+    ///
+    /// Any input still pending in the receive buffer is discarded first, so that stale data isn't
+    /// mistaken for the reply. The request is then written and flushed, and bytes are read until
+    /// `response` is full or `timeout` has elapsed, whichever comes first. The port's configured
+    /// timeout is restored afterward.
+    ///
+    /// Returns the number of bytes read, which is less than `response.len()` if the timeout
+    /// elapsed first.
+    ///
+    /// ## Errors
+    ///
+    /// Running out of time isn't an error. This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn transaction(
+        &mut self,
+        request: &[u8],
+        response: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize> {
+        let original_timeout = self.timeout();
+        let result = transact(self, request, response, timeout);
+        self.set_timeout(original_timeout)?;
+        result
+    }
+
+    /// Makes reads wait up to `first` for data, then collect bytes until the line goes quiet.
+    ///
+    /// This suits devices that send their data in bursts: a single `read()` waits up to `first`
+    /// for the first byte, then keeps receiving until no byte arrived for `inter_byte`, so it
+    /// returns a whole burst at once (or as much of it as fits into `buf`). If nothing arrives
+    /// within `first`, the read fails with `Io(TimedOut)` as usual.
+    ///
+    /// This sets the port timeout to `first` and calls `set_read_mode(255, inter_byte)`, which
+    /// maps onto the platforms as follows:
+    ///
+    /// * On POSIX the first byte is awaited with `poll()` for `first`. The inter-byte timeout is
+    ///   `VTIME`, which the driver restarts after every byte, with a `VMIN` of 255, so it's
+    ///   rounded up to a multiple of 100ms, and a burst isn't bounded by `first`.
+    /// * On Windows the inter-byte timeout is `ReadIntervalTimeout` and `first` is
+    ///   `ReadTotalTimeoutConstant`. Windows only has a total timeout, so a read also returns
+    ///   once `first` has elapsed since it started, in the middle of a burst if need be.
+    ///
+    /// The settings stay in effect for all future reads, until they're changed with
+    /// `set_timeout()` and `set_read_mode()`.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if `inter_byte` is zero or longer than supported (25.5s on POSIX).
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn set_burst_read_timeout(&mut self, first: Duration, inter_byte: Duration) -> Result<()> {
+        if inter_byte == Duration::from_secs(0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Inter-byte timeout must not be zero",
+            ));
+        }
+        self.set_read_mode(u8::MAX, inter_byte)?;
+        self.set_timeout(first)
+    }
+
+    /// Sets the timeout to the time it takes to receive `n_bytes` plus `slack`.
+    ///
+    /// The transmission time is computed from the current baud rate, data bits, parity and stop
+    /// bits, counting a start bit for every character. It assumes the characters arrive back to
+    /// back, so `slack` should cover the time the other end takes to respond as well as gaps
+    /// between characters, e.g. from USB latency or flow control.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if the current baud rate is zero or the timeout doesn't fit a
+    ///   `Duration`.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn set_timeout_for_bytes(&mut self, n_bytes: usize, slack: Duration) -> Result<()> {
+        let bits = frame_bits(self.data_bits()?, self.parity()?, self.stop_bits()?);
+        let timeout = transmission_time(n_bytes, bits, self.baud_rate()?)?
+            .checked_add(slack)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Timeout is too long"))?;
+        self.set_timeout(timeout)
+    }
+
+    /// Reads until the byte sequence `needle` has been received.
+    ///
+    /// Returns all bytes read, up to and including the end of the first occurrence of `needle`.
+    /// Bytes are read one at a time so that nothing past the match is consumed; any data
+    /// following `needle` is left in the receive buffer for the next read. Occurrences of
+    /// `needle` overlapping with a previous partial match (e.g. `"aab"` in `"aaab"`) are found
+    /// as well. The port's configured timeout is restored afterward.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `Io(TimedOut)` if `needle` wasn't received within `timeout`. The bytes read so far are
+    ///   discarded.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn wait_for_sequence(&mut self, needle: &[u8], timeout: Duration) -> Result<Vec<u8>> {
+        let original_timeout = self.timeout();
+        let result = read_until_sequence(self, needle, timeout);
+        self.set_timeout(original_timeout)?;
+        result
+    }
+
+    /// Reads until any of the byte sequences in `needles` has been received.
+    ///
+    /// Returns the index of the matching needle and all bytes read, up to and including the end
+    /// of the match. This is meant for replies with several possible endings, such as the
+    /// `"OK\r\n"` and `"ERROR\r\n"` of AT commands. Like `wait_for_sequence()`, bytes are read
+    /// one at a time so that nothing past the match is consumed, and the port's configured
+    /// timeout is restored afterward.
+    ///
+    /// The needle that is completed first wins. When a needle is a prefix of another, e.g.
+    /// `"OK"` and `"OK\r\n"`, this is the shorter one, and the rest of the longer one is left in
+    /// the receive buffer. When several needles are completed by the same byte because one ends
+    /// with another, e.g. `"\r\n"` and `"OK\r\n"`, the longest one wins.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if `needles` is empty or contains an empty needle.
+    /// * `Io(TimedOut)` if none of `needles` was received within `timeout`. The bytes read so far
+    ///   are discarded.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_until_any(&mut self, needles: &[&[u8]], timeout: Duration) -> Result<(usize, Vec<u8>)> {
+        if needles.is_empty() || needles.iter().any(|needle| needle.is_empty()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Needles must not be empty",
+            ));
+        }
+        let original_timeout = self.timeout();
+        let result = read_until_any_sequence(self, needles, timeout);
+        self.set_timeout(original_timeout)?;
+        result
+    }
+
+    /// Measures the round-trip latency of the link.
+    ///
+    /// For each of the `samples` iterations, both buffers are cleared, `probe` is written and the
+    /// time until `expect` has been received is recorded (see `wait_for_sequence()`). A sample
+    /// whose reply doesn't arrive within `timeout` is counted in `LatencyStats::timeouts` rather
+    /// than failing the measurement. The port's configured timeout is restored afterward.
+    ///
+    /// ## Errors
+    ///
+    /// Missing replies aren't an error. This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn measure_latency(
+        &mut self,
+        probe: &[u8],
+        expect: &[u8],
+        samples: usize,
+        timeout: Duration,
+    ) -> Result<LatencyStats> {
+        let original_timeout = self.timeout();
+        let result = sample_latency(self, probe, expect, samples, timeout);
+        self.set_timeout(original_timeout)?;
+        result
+    }
+
+    /// Measures the throughput of a link that echoes all data, such as a loopback plug.
+    ///
+    /// Both buffers are cleared, and `total_bytes` of a counting pattern are written in writes of
+    /// `chunk` bytes. In between writes, the data that has been echoed so far is read with
+    /// `bytes_to_read()`, so the input buffer doesn't overflow. The write time ends once
+    /// `flush()` reports that all output has been transmitted, after which the rest of the echo
+    /// is awaited for up to `timeout`. Bytes that don't arrive in time are reported as missing
+    /// rather than failing the measurement.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if `chunk` is zero.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn measure_throughput(
+        &mut self,
+        total_bytes: usize,
+        chunk: usize,
+        timeout: Duration,
+    ) -> Result<ThroughputStats> {
+        if chunk == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Chunk size must not be zero",
+            ));
+        }
+        sample_throughput(self, total_bytes, chunk, timeout)
+    }
+
+    /// Runs `f` with the port temporarily switched to `baud_rate`.
+    ///
+    /// Pending output is drained with `flush()` before switching, so bytes that are still being
+    /// transmitted aren't sent at the wrong rate, and again before the original baud rate is
+    /// restored. The original baud rate is restored even if `f` returns an error, which is then
+    /// returned in place of any error from restoring it.
+    ///
+    /// This isn't available on `dyn SerialPort` trait objects, but on `Box<dyn SerialPort>`,
+    /// such as the ports returned by `open()`, since `Box` implements `SerialPort` as well.
+    ///
+    /// ## Errors
+    ///
+    /// Besides the errors returned by `f`, this function may return the following errors:
+    ///
+    /// * `InvalidInput` if `baud_rate` isn't supported.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn with_temporary_baud<R, F>(&mut self, baud_rate: u32, f: F) -> Result<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        let original_baud_rate = self.baud_rate()?;
+        self.flush()?;
+        self.set_baud_rate(baud_rate)?;
+        let result = f(self);
+        let restored = self
+            .flush()
+            .map_err(Error::from)
+            .and_then(|_| self.set_baud_rate(original_baud_rate));
+        let result = result?;
+        restored?;
+        Ok(result)
+    }
+
+    /// Runs `f` with the port temporarily switched to the flow control mode `temp`.
+    ///
+    /// This sends a frame that must not be paused halfway, e.g. by an XOFF, with flow control
+    /// disabled. Pending output is drained with `flush()` before the original mode is restored,
+    /// so the data written by `f` is transmitted under `temp`. The original configuration is
+    /// saved with `save_flow_control()` and restored with `restore_flow_control()`, so an
+    /// overflow policy or passthrough setting is kept as well. It's restored even if `f` returns
+    /// an error, which is then returned in place of any error from restoring it.
+    ///
+    /// This isn't available on `dyn SerialPort` trait objects, but on `Box<dyn SerialPort>`,
+    /// such as the ports returned by `open()`, since `Box` implements `SerialPort` as well.
+    ///
+    /// ## Errors
+    ///
+    /// Besides the errors returned by `f`, this function may return the following errors:
+    ///
+    /// * `InvalidInput` if `temp` isn't supported.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn with_flow_control<R, F>(&mut self, temp: FlowControl, f: F) -> Result<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        let saved = self.save_flow_control()?;
+        self.set_flow_control(temp)?;
+        let result = f(self);
+        let restored = self
+            .flush()
+            .map_err(Error::from)
+            .and_then(|_| self.restore_flow_control(&saved));
+        let result = result?;
+        restored?;
+        Ok(result)
+    }
+
+    /// Transmits a break lasting `bits` bit times at the current baud rate.
+    ///
+    /// Protocols such as LIN specify the length of a break in bit times (13 for a LIN break)
+    /// rather than as a wall-clock duration. Pending output is drained with `flush()` first, then
+    /// the break is held with `set_break()` and `clear_break()` for the computed duration.
+    ///
+    /// The duration is timed with `std::thread::sleep()`, so the break is never shorter than
+    /// requested but may be longer by the scheduler granularity of the OS, typically up to a
+    /// millisecond and sometimes more on a loaded system. At high baud rates this can be many
+    /// bit times. LIN only specifies a minimum break length, so this is usually acceptable.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if `bits` or the current baud rate is zero.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn send_break_bits(&mut self, bits: u32) -> Result<()> {
+        let baud_rate = self.baud_rate()?;
+        let duration = break_duration(bits, baud_rate)?;
+        self.flush()?;
+        self.set_break()?;
+        std::thread::sleep(duration);
+        self.clear_break()
+    }
+
+    /// Sends a wake-up stimulus to the device.
+    ///
+    /// Some devices keep their UART asleep to save power and lose the first bytes sent to them,
+    /// or need a particular signal on the line to wake up. Which `pattern` works, and how long
+    /// the device then takes before it's ready, is specific to the device and described in its
+    /// documentation. Pending output is drained with `flush()` first, and this returns once the
+    /// stimulus has been transmitted. Anything the device sends in response is left in the input
+    /// buffer, so it may have to be cleared with `clear()` afterwards.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error, e.g. for `DtrToggle` on ports without modem
+    ///   control lines.
+    fn wake(&mut self, pattern: WakePattern) -> Result<()> {
+        self.flush()?;
+        match pattern {
+            WakePattern::NulBytes(count) => {
+                write_all_cancellable(self, &vec![0; count])?;
+                self.flush()?;
+            }
+            WakePattern::Break(duration) => {
+                self.set_break()?;
+                std::thread::sleep(duration);
+                self.clear_break()?;
+            }
+            WakePattern::DtrToggle(duration) => {
+                self.write_data_terminal_ready(false)?;
+                std::thread::sleep(duration);
+                self.write_data_terminal_ready(true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reconfigures the port from a settings string such as `"115200,8,N,1"`.
+    ///
+    /// The string consists of the baud rate, the number of data bits (5-8), the parity (`N`,
+    /// `O` or `E`) and the number of stop bits (1 or 2), separated by commas. The flow control
+    /// mode and the timeout are left unchanged.
+    ///
+    /// The settings are applied all or nothing: a malformed string doesn't change anything, and
+    /// if the driver rejects one of the settings, those already applied are rolled back.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if `spec` is malformed, or the driver doesn't support its settings.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn configure(&mut self, spec: &str) -> Result<()> {
+        let (baud_rate, data_bits, parity, stop_bits) = parse_settings_spec(spec)?;
+        let original = self.settings()?;
+        let applied = self
+            .set_baud_rate(baud_rate)
+            .and_then(|_| self.set_data_bits(data_bits))
+            .and_then(|_| self.set_parity(parity))
+            .and_then(|_| self.set_stop_bits(stop_bits));
+        if applied.is_err() {
+            // Best effort, the error that caused the rollback is the one worth reporting.
+            let _ = self
+                .set_baud_rate(original.baud_rate)
+                .and_then(|_| self.set_data_bits(original.data_bits))
+                .and_then(|_| self.set_parity(original.parity))
+                .and_then(|_| self.set_stop_bits(original.stop_bits));
+        }
+        applied
+    }
+
+    /// Waits until the line is idle in both directions.
+    ///
+    /// Pending output is drained with `flush()` first. Then this waits until no byte has been
+    /// received for `quiet`, by watching `bytes_to_read()`. Received bytes aren't consumed, so
+    /// they're still available to read afterward. This is useful before closing the port or
+    /// changing its settings, to make sure the other end is done talking.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `Io(TimedOut)` if the line didn't become quiet within `timeout`, which includes the
+    ///   time spent draining the output.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn flush_and_wait_quiescent(&mut self, quiet: Duration, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        self.flush()?;
+
+        let mut received = self.bytes_to_read()?;
+        let mut quiet_since = Instant::now();
+        loop {
+            let now = Instant::now();
+            if now.saturating_duration_since(quiet_since) >= quiet {
+                return Ok(());
+            }
+            if now >= deadline {
+                return Err(Error::new(
+                    ErrorKind::Io(io::ErrorKind::TimedOut),
+                    "Line didn't become quiet before the timeout",
+                ));
+            }
+            std::thread::sleep(QUIESCENCE_POLL_INTERVAL.min(quiet));
+            let now_received = self.bytes_to_read()?;
+            // Fewer bytes means someone else read them, more means new data arrived
+            if now_received != received {
+                received = now_received;
+                quiet_since = Instant::now();
+            }
+        }
+    }
+
+    /// Reads a single frame of a protocol whose header contains the payload length.
+    ///
+    /// First `header_len` bytes are read, and the payload length is taken from the field of
+    /// `length_size` bytes at `length_offset` within the header, in `endian` byte order. Then
+    /// that many payload bytes are read. The returned frame contains both the header and the
+    /// payload.
+    ///
+    /// Frames longer than [`MAX_FRAME_LEN`] are rejected, see `read_length_prefixed_max()`.
+    ///
+    /// `timeout` is the time allowed for the whole frame, however many reads it takes. The
+    /// port's configured timeout is restored afterward.
+    ///
+    /// [`MAX_FRAME_LEN`]: constant.MAX_FRAME_LEN.html
+    ///
+    /// ## Errors
+    ///
+    /// See `read_length_prefixed_max()`.
+    fn read_length_prefixed(
+        &mut self,
+        header_len: usize,
+        length_offset: usize,
+        length_size: LengthFieldSize,
+        endian: Endian,
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        self.read_length_prefixed_max(
+            header_len,
+            length_offset,
+            length_size,
+            endian,
+            MAX_FRAME_LEN,
+            timeout,
+        )
+    }
+
+    /// Reads a single frame like `read_length_prefixed()`, accepting at most `max_len` bytes.
+    ///
+    /// Frames longer than `max_len` bytes, header included, are rejected before anything is
+    /// allocated for the payload, so a corrupted length field can't exhaust memory. The payload
+    /// of a rejected frame is left unread.
+    ///
+    /// `timeout` is the time allowed for the whole frame, however many reads it takes. The
+    /// port's configured timeout is restored afterward.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if the length field doesn't fit within the header, or the header is
+    ///   longer than `max_len`.
+    /// * `Io(InvalidData)` if the length field announces a frame longer than `max_len`.
+    /// * `Io(TimedOut)` if the frame wasn't received completely within `timeout`. The bytes read
+    ///   so far are discarded.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_length_prefixed_max(
+        &mut self,
+        header_len: usize,
+        length_offset: usize,
+        length_size: LengthFieldSize,
+        endian: Endian,
+        max_len: usize,
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        if length_offset + length_size.len() > header_len {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Length field extends past the end of the header",
+            ));
+        }
+        if header_len > max_len {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Header is longer than the maximum frame length",
+            ));
+        }
+        let original_timeout = self.timeout();
+        let result = read_frame(
+            self,
+            header_len,
+            length_offset,
+            length_size,
+            endian,
+            max_len,
+            Instant::now() + timeout,
+        );
+        self.set_timeout(original_timeout)?;
+        result
+    }
+
+    /// Writes `text` with its line breaks translated according to `line_ending()`.
+    ///
+    /// Each `\n` in `text`, along with a `\r` directly in front of it, is replaced with the
+    /// configured line ending. With `LineEnding::None`, `text` is written unchanged.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn write_str(&mut self, text: &str) -> Result<()> {
+        let translated = translate_line_endings(text, self.line_ending());
+        write_all_cancellable(self, translated.as_bytes())?;
+        Ok(())
+    }
+
+    /// Writes `line` like `write_str()`, followed by the configured line ending.
+    ///
+    /// With `LineEnding::None` the line is terminated with `\n`.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        let mut translated = translate_line_endings(line, self.line_ending()).into_owned();
+        translated.push_str(self.line_ending().as_str());
+        write_all_cancellable(self, translated.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the number at the end of the port name, e.g. 7 for `COM7` or 0 for `/dev/ttyUSB0`.
+    ///
+    /// This is a compact identifier for UIs and logs. It's derived from the name alone, on a
+    /// best-effort basis: it's `None` for ports without a name or whose name doesn't end in a
+    /// number, and ports opened through a different name of the same device, such as a link in
+    /// `/dev/serial/by-id/`, get a different or no index. Ports of different drivers may share
+    /// an index, e.g. `/dev/ttyS0` and `/dev/ttyUSB0`.
+    fn port_index(&self) -> Option<u32> {
+        self.name().as_ref().and_then(|name| port_index(name))
+    }
+
+    /// Returns whether the device behind this port has gone away.
+    ///
+    /// This is detected by the port settings no longer being readable from the handle, which
+    /// drivers refuse once the device was disconnected. A USB device doing so after a reset is
+    /// expected to re-enumerate shortly, see `wait_reenumerate()`.
+    fn awaits_reenumeration(&self) -> bool {
+        self.settings().is_err()
+    }
+
+    /// Reads the state of all input control signals at once.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_modem_status(&mut self) -> Result<ModemStatus> {
+        let lines = self.modem_lines()?;
+        Ok(ModemStatus {
+            clear_to_send: lines.contains(ModemLines::CTS),
+            data_set_ready: lines.contains(ModemLines::DSR),
+            ring_indicator: lines.contains(ModemLines::RI),
+            carrier_detect: lines.contains(ModemLines::CD),
+        })
+    }
+
+    /// Returns an iterator over changes of the input control signals.
+    ///
+    /// The iterator uses its own clone of the port (see `try_clone()`) and waits for each change
+    /// with `wait_for_modem_change(timeout)`. See [`SignalEventStream`] for details.
+    ///
+    /// [`SignalEventStream`]: struct.SignalEventStream.html
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the port couldn't be cloned or its control signals
+    /// couldn't be read.
+    fn signal_events(&self, timeout: Option<Duration>) -> Result<SignalEventStream> {
+        let mut port = self.try_clone()?;
+        let status = port.read_modem_status()?;
+        Ok(SignalEventStream {
+            port,
+            timeout,
+            status,
+        })
+    }
+
+    /// Calls `on_data` with received data from a background thread.
+    ///
+    /// The thread uses its own clone of the port (see `try_clone()`) and waits for data with
+    /// `has_data()`, so it doesn't change the timeout of either port. Each chunk of data is
+    /// passed to `on_data` as soon as it has been read. Reading stops once `stop()` is called on
+    /// the returned [`ReaderHandle`] or it's dropped, and when reading fails, in which case
+    /// `stop()` returns the error.
+    ///
+    /// This gives a push-style interface without an async runtime. Reading from this port at the
+    /// same time will steal data from the callback.
+    ///
+    /// This isn't available on `dyn SerialPort` trait objects, but on `Box<dyn SerialPort>`,
+    /// such as the ports returned by `open()`, since `Box` implements `SerialPort` as well.
+    ///
+    /// [`ReaderHandle`]: struct.ReaderHandle.html
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the port couldn't be cloned.
+    fn spawn_reader<F>(&self, on_data: F) -> Result<ReaderHandle>
+    where
+        Self: Sized,
+        F: FnMut(&[u8]) + Send + 'static,
+    {
+        let port = self.try_clone()?;
+        Ok(spawn_reader_thread(move |stop| {
+            read_into_callback(port, stop, on_data)
+        }))
+    }
+
+    /// Sends received data to a bounded channel from a background thread.
+    ///
+    /// Like `spawn_reader()`, the thread uses its own clone of the port. Received data is
+    /// collected into buffers of `chunk` bytes, and a buffer is sent early when no more data
+    /// arrives within the port's timeout. While the channel is full the thread stops reading, so
+    /// a slow consumer applies backpressure: data backs up in the driver, which holds off the
+    /// other end if flow control is enabled, and is lost otherwise once the driver's buffer
+    /// overflows. Reading stops once `stop()` is called on the returned [`ReaderHandle`] or it's
+    /// dropped, when the receiver is dropped, and when reading fails, in which case `stop()`
+    /// returns the error.
+    ///
+    /// This isn't available on `dyn SerialPort` trait objects, but on `Box<dyn SerialPort>`,
+    /// such as the ports returned by `open()`, since `Box` implements `SerialPort` as well.
+    ///
+    /// [`ReaderHandle`]: struct.ReaderHandle.html
+    ///
+    /// ## Errors
+    ///
+    /// This function returns `InvalidInput` if `chunk` is zero, or an error if the port couldn't
+    /// be cloned.
+    fn read_to_channel(
+        &self,
+        tx: std::sync::mpsc::SyncSender<Vec<u8>>,
+        chunk: usize,
+    ) -> Result<ReaderHandle>
+    where
+        Self: Sized,
+    {
+        if chunk == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Chunk size must not be zero",
+            ));
+        }
+        let port = self.try_clone()?;
+        Ok(spawn_reader_thread(move |stop| {
+            read_into_channel(port, stop, tx, chunk)
+        }))
+    }
+
+    /// Reports the state of hardware flow control.
+    ///
+    /// With `FlowControl::Hardware`, the driver only transmits while the other end asserts CTS,
+    /// and asserts RTS itself while it's ready to receive. If the cable doesn't carry the RTS/CTS
+    /// lines, CTS is never asserted and writes hang forever. This reads the modem status to tell
+    /// whether that's currently the case, and which side is holding up the transfer.
+    ///
+    /// RTS is read back with `TIOCMGET` on POSIX. Windows can't read the output lines back, so
+    /// there RTS is only reported while it isn't driven by the handshake. Both lines are `None`
+    /// for drivers that don't report the modem status, like pseudo terminals, and writes are
+    /// then never reported as blocked.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn hardware_flow_diagnostic(&mut self) -> Result<HwFlowStatus> {
+        let flow_control = self.flow_control()?;
+        let lines = match self.modem_lines() {
+            Ok(lines) => Some(lines),
+            Err(e) if e.kind() == ErrorKind::NoDevice => return Err(e),
+            Err(_) => None,
+        };
+        let rts_readable = !(cfg!(windows) && flow_control == FlowControl::Hardware);
+        Ok(hw_flow_status(flow_control, lines, rts_readable))
+    }
+
+    /// Reads into `buf`, writing `keepalive_bytes` every `keepalive` interval until data arrives.
+    ///
+    /// This is meant for devices that only answer when prompted periodically. The first
+    /// keepalive is sent once `keepalive` has elapsed without any data received, and no more are
+    /// sent once `read()` returned data. Gives up once `total_timeout` has elapsed. The port's
+    /// configured timeout is restored afterward.
+    ///
+    /// ## Errors
+    ///
+    /// This function may return the following errors:
+    ///
+    /// * `InvalidInput` if `keepalive` is zero.
+    /// * `Io(TimedOut)` if no data was received within `total_timeout`.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn read_with_keepalive(
+        &mut self,
+        buf: &mut [u8],
+        keepalive: Duration,
+        keepalive_bytes: &[u8],
+        total_timeout: Duration,
+    ) -> Result<usize> {
+        if keepalive == Duration::from_secs(0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Keepalive interval must not be zero",
+            ));
+        }
+        let original_timeout = self.timeout();
+        let result = read_keepalive(self, buf, keepalive, keepalive_bytes, total_timeout);
+        self.set_timeout(original_timeout)?;
+        result
+    }
+}
+
+impl<T: SerialPort> SerialPort for &mut T {
+    fn name(&self) -> Option<String> {
+        (**self).name()
+    }
+
+    fn port_type(&self) -> SerialPortType {
+        (**self).port_type()
+    }
+
+    fn is_virtual(&self) -> bool {
+        (**self).is_virtual()
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        (**self).baud_rate()
+    }
+
+    fn baud_rate_info(&self) -> Result<BaudRateInfo> {
+        (**self).baud_rate_info()
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        (**self).data_bits()
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        (**self).flow_control()
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        (**self).parity()
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        (**self).stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        (**self).timeout()
+    }
+
+    fn timeout_resolution(&self) -> Duration {
+        (**self).timeout_resolution()
+    }
+
+    fn line_ending(&self) -> LineEnding {
+        (**self).line_ending()
+    }
+
+    fn settings(&self) -> Result<SerialPortBuilder> {
+        (**self).settings()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        (**self).set_baud_rate(baud_rate)
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        (**self).set_data_bits(data_bits)
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        (**self).set_flow_control(flow_control)
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        (**self).set_parity(parity)
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        (**self).set_stop_bits(stop_bits)
+    }
+
+    fn swap_settings(&mut self, new: &SerialPortBuilder) -> Result<SerialPortBuilder> {
+        (**self).swap_settings(new)
+    }
+
+    fn parity_check(&self) -> Result<bool> {
+        (**self).parity_check()
+    }
+
+    fn set_parity_check(&mut self, enabled: bool) -> Result<()> {
+        (**self).set_parity_check(enabled)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        (**self).set_timeout(timeout)
+    }
+
+    fn set_line_ending(&mut self, line_ending: LineEnding) {
+        (**self).set_line_ending(line_ending)
+    }
+
+    fn set_read_mode(&mut self, min_bytes: u8, inter_byte_timeout: Duration) -> Result<()> {
+        (**self).set_read_mode(min_bytes, inter_byte_timeout)
+    }
+
+    fn inter_byte_write_delay(&self) -> Duration {
+        (**self).inter_byte_write_delay()
+    }
+
+    fn set_inter_byte_write_delay(&mut self, delay: Duration) -> Result<()> {
+        (**self).set_inter_byte_write_delay(delay)
+    }
+
+    fn write_unbuffered(&self) -> bool {
+        (**self).write_unbuffered()
+    }
+
+    fn set_write_unbuffered(&mut self, unbuffered: bool) -> Result<()> {
+        (**self).set_write_unbuffered(unbuffered)
+    }
+
+    fn max_read_size(&self) -> Option<usize> {
+        (**self).max_read_size()
+    }
+
+    fn set_max_read_size(&mut self, max: Option<usize>) -> Result<()> {
+        (**self).set_max_read_size(max)
+    }
+
+    fn close_on_exec(&self) -> Result<bool> {
+        (**self).close_on_exec()
+    }
+
+    fn set_close_on_exec(&mut self, cloexec: bool) -> Result<()> {
+        (**self).set_close_on_exec(cloexec)
+    }
+
+    fn set_binary_mode(&mut self, binary: bool) -> Result<()> {
+        (**self).set_binary_mode(binary)
+    }
+
+    fn set_flow_watermarks(&mut self, low: usize, high: usize) -> Result<()> {
+        (**self).set_flow_watermarks(low, high)
+    }
+
+    fn set_overflow_policy(&mut self, policy: OverflowPolicy) -> Result<()> {
+        (**self).set_overflow_policy(policy)
+    }
+
+    fn set_flow_control_passthrough(&mut self, passthrough: bool) -> Result<()> {
+        (**self).set_flow_control_passthrough(passthrough)
+    }
+
+    fn save_flow_control(&self) -> Result<SavedFlowControl> {
+        (**self).save_flow_control()
+    }
+
+    fn restore_flow_control(&mut self, saved: &SavedFlowControl) -> Result<()> {
+        (**self).restore_flow_control(saved)
+    }
+
+    fn modem_lines(&self) -> Result<ModemLines> {
+        (**self).modem_lines()
+    }
+
+    fn set_modem_lines_masked(&mut self, set: ModemLines, clear: ModemLines) -> Result<()> {
+        (**self).set_modem_lines_masked(set, clear)
+    }
+
+    fn set_close_behavior(&mut self, dtr: Option<bool>, rts: Option<bool>) -> Result<()> {
+        (**self).set_close_behavior(dtr, rts)
+    }
+
+    fn wait_for_modem_change(&mut self, timeout: Option<Duration>) -> Result<ModemStatus> {
+        (**self).wait_for_modem_change(timeout)
+    }
+
+    fn wait_reenumerate(&mut self, identity: &UsbIdentity, timeout: Duration) -> Result<()> {
+        (**self).wait_reenumerate(identity, timeout)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        (**self).reopen()
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        (**self).bytes_to_read()
+    }
+
+    fn has_data(&self, timeout: Duration) -> Result<bool> {
+        (**self).has_data(timeout)
+    }
+
+    fn rx_watermark(&self) -> u32 {
+        (**self).rx_watermark()
+    }
+
+    fn reset_rx_watermark(&mut self) {
+        (**self).reset_rx_watermark()
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        (**self).bytes_to_write()
+    }
+
+    fn rx_buffer_capacity(&self) -> Result<u32> {
+        (**self).rx_buffer_capacity()
+    }
+
+    fn tx_buffer_capacity(&self) -> Result<u32> {
+        (**self).tx_buffer_capacity()
+    }
+
+    fn queue_status(&self) -> Result<QueueStatus> {
+        (**self).queue_status()
+    }
+
+    fn capabilities(&self) -> Result<PortCapabilities> {
+        (**self).capabilities()
+    }
+
+    fn supported_operations(&self) -> SupportedOps {
+        (**self).supported_operations()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        (**self).clear(buffer_to_clear)
+    }
+
+    fn counters(&self) -> Result<LineCounters> {
+        (**self).counters()
+    }
+
+    fn reset_counters(&mut self) -> Result<()> {
+        (**self).reset_counters()
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        (**self).try_clone()
+    }
+
+    fn set_break(&self) -> Result<()> {
+        (**self).set_break()
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        (**self).clear_break()
+    }
+}
+
+impl<T: SerialPort + ?Sized> SerialPort for Box<T> {
+    fn name(&self) -> Option<String> {
+        (**self).name()
+    }
+
+    fn port_type(&self) -> SerialPortType {
+        (**self).port_type()
+    }
+
+    fn is_virtual(&self) -> bool {
+        (**self).is_virtual()
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        (**self).baud_rate()
+    }
+
+    fn baud_rate_info(&self) -> Result<BaudRateInfo> {
+        (**self).baud_rate_info()
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        (**self).data_bits()
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        (**self).flow_control()
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        (**self).parity()
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        (**self).stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        (**self).timeout()
+    }
+
+    fn timeout_resolution(&self) -> Duration {
+        (**self).timeout_resolution()
+    }
+
+    fn line_ending(&self) -> LineEnding {
+        (**self).line_ending()
+    }
+
+    fn settings(&self) -> Result<SerialPortBuilder> {
+        (**self).settings()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        (**self).set_baud_rate(baud_rate)
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        (**self).set_data_bits(data_bits)
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        (**self).set_flow_control(flow_control)
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        (**self).set_parity(parity)
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        (**self).set_stop_bits(stop_bits)
+    }
+
+    fn swap_settings(&mut self, new: &SerialPortBuilder) -> Result<SerialPortBuilder> {
+        (**self).swap_settings(new)
+    }
+
+    fn parity_check(&self) -> Result<bool> {
+        (**self).parity_check()
+    }
+
+    fn set_parity_check(&mut self, enabled: bool) -> Result<()> {
+        (**self).set_parity_check(enabled)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        (**self).set_timeout(timeout)
+    }
+
+    fn set_line_ending(&mut self, line_ending: LineEnding) {
+        (**self).set_line_ending(line_ending)
+    }
+
+    fn set_read_mode(&mut self, min_bytes: u8, inter_byte_timeout: Duration) -> Result<()> {
+        (**self).set_read_mode(min_bytes, inter_byte_timeout)
+    }
+
+    fn inter_byte_write_delay(&self) -> Duration {
+        (**self).inter_byte_write_delay()
+    }
+
+    fn set_inter_byte_write_delay(&mut self, delay: Duration) -> Result<()> {
+        (**self).set_inter_byte_write_delay(delay)
+    }
+
+    fn write_unbuffered(&self) -> bool {
+        (**self).write_unbuffered()
+    }
+
+    fn set_write_unbuffered(&mut self, unbuffered: bool) -> Result<()> {
+        (**self).set_write_unbuffered(unbuffered)
+    }
+
+    fn max_read_size(&self) -> Option<usize> {
+        (**self).max_read_size()
+    }
+
+    fn set_max_read_size(&mut self, max: Option<usize>) -> Result<()> {
+        (**self).set_max_read_size(max)
+    }
+
+    fn close_on_exec(&self) -> Result<bool> {
+        (**self).close_on_exec()
+    }
+
+    fn set_close_on_exec(&mut self, cloexec: bool) -> Result<()> {
+        (**self).set_close_on_exec(cloexec)
+    }
+
+    fn set_binary_mode(&mut self, binary: bool) -> Result<()> {
+        (**self).set_binary_mode(binary)
+    }
+
+    fn set_flow_watermarks(&mut self, low: usize, high: usize) -> Result<()> {
+        (**self).set_flow_watermarks(low, high)
+    }
+
+    fn set_overflow_policy(&mut self, policy: OverflowPolicy) -> Result<()> {
+        (**self).set_overflow_policy(policy)
+    }
+
+    fn set_flow_control_passthrough(&mut self, passthrough: bool) -> Result<()> {
+        (**self).set_flow_control_passthrough(passthrough)
+    }
+
+    fn save_flow_control(&self) -> Result<SavedFlowControl> {
+        (**self).save_flow_control()
+    }
+
+    fn restore_flow_control(&mut self, saved: &SavedFlowControl) -> Result<()> {
+        (**self).restore_flow_control(saved)
+    }
+
+    fn modem_lines(&self) -> Result<ModemLines> {
+        (**self).modem_lines()
+    }
+
+    fn set_modem_lines_masked(&mut self, set: ModemLines, clear: ModemLines) -> Result<()> {
+        (**self).set_modem_lines_masked(set, clear)
+    }
+
+    fn set_close_behavior(&mut self, dtr: Option<bool>, rts: Option<bool>) -> Result<()> {
+        (**self).set_close_behavior(dtr, rts)
+    }
+
+    fn wait_for_modem_change(&mut self, timeout: Option<Duration>) -> Result<ModemStatus> {
+        (**self).wait_for_modem_change(timeout)
+    }
+
+    fn wait_reenumerate(&mut self, identity: &UsbIdentity, timeout: Duration) -> Result<()> {
+        (**self).wait_reenumerate(identity, timeout)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        (**self).reopen()
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        (**self).bytes_to_read()
+    }
+
+    fn has_data(&self, timeout: Duration) -> Result<bool> {
+        (**self).has_data(timeout)
+    }
+
+    fn rx_watermark(&self) -> u32 {
+        (**self).rx_watermark()
+    }
+
+    fn reset_rx_watermark(&mut self) {
+        (**self).reset_rx_watermark()
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        (**self).bytes_to_write()
+    }
+
+    fn rx_buffer_capacity(&self) -> Result<u32> {
+        (**self).rx_buffer_capacity()
+    }
+
+    fn tx_buffer_capacity(&self) -> Result<u32> {
+        (**self).tx_buffer_capacity()
+    }
+
+    fn queue_status(&self) -> Result<QueueStatus> {
+        (**self).queue_status()
+    }
+
+    fn capabilities(&self) -> Result<PortCapabilities> {
+        (**self).capabilities()
+    }
+
+    fn supported_operations(&self) -> SupportedOps {
+        (**self).supported_operations()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        (**self).clear(buffer_to_clear)
+    }
+
+    fn counters(&self) -> Result<LineCounters> {
+        (**self).counters()
+    }
+
+    fn reset_counters(&mut self) -> Result<()> {
+        (**self).reset_counters()
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        (**self).try_clone()
+    }
+
+    fn set_break(&self) -> Result<()> {
+        (**self).set_break()
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        (**self).clear_break()
+    }
+}
+
+impl<'a> fmt::Debug for dyn SerialPort + 'a {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.describe())
+    }
+}
+
+/// What a port needs to remember to reopen itself, see `SerialPort::reopen()`
+#[derive(Debug, Clone, Default)]
+struct ReopenState {
+    /// The builder the port was opened with, updated by the setters
+    settings: Option<SerialPortBuilder>,
+    /// The DTR level last written
+    dtr: Option<bool>,
+    /// The RTS level last written
+    rts: Option<bool>,
+}
+
+impl ReopenState {
+    fn new(builder: &SerialPortBuilder) -> Self {
+        ReopenState {
+            settings: Some(builder.clone()),
+            ..ReopenState::default()
+        }
+    }
+
+    /// Records the output signals that were asserted and cleared.
+    fn record_lines(&mut self, set: ModemLines, clear: ModemLines) {
+        if set.contains(ModemLines::RTS) {
+            self.rts = Some(true);
+        } else if clear.contains(ModemLines::RTS) {
+            self.rts = Some(false);
+        }
+        if set.contains(ModemLines::DTR) {
+            self.dtr = Some(true);
+        } else if clear.contains(ModemLines::DTR) {
+            self.dtr = Some(false);
+        }
+    }
+
+    /// Records the settings applied by `SerialPort::swap_settings()`.
+    fn record_settings(&mut self, new: &SerialPortBuilder) {
+        self.update(|s| {
+            s.baud_rate = new.baud_rate;
+            s.data_bits = new.data_bits;
+            s.flow_control = new.flow_control;
+            s.parity = new.parity;
+            s.stop_bits = new.stop_bits;
+            s.timeout = new.timeout;
+            s.line_ending = new.line_ending;
+        });
+    }
+
+    /// Records a setting that was applied to the port.
+    fn update<F: FnOnce(&mut SerialPortBuilder)>(&mut self, f: F) {
+        if let Some(settings) = self.settings.as_mut() {
+            f(settings);
+        }
+    }
+}
+
+/// Parses the number at the end of the last component of a port name.
+fn port_index(name: &str) -> Option<u32> {
+    let name = name
+        .rsplit(|c| c == '/' || c == '\\')
+        .next()
+        .unwrap_or(name);
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    name[name.len() - digits..].parse().ok()
+}
+
+/// Checks the masks passed to `SerialPort::set_modem_lines_masked()`.
+fn check_modem_line_masks(set: ModemLines, clear: ModemLines) -> Result<()> {
+    if !((set | clear) - (ModemLines::RTS | ModemLines::DTR)).is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Only RTS and DTR can be set",
+        ));
+    }
+    if set.intersects(clear) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "A control signal can't be both set and cleared",
+        ));
+    }
+    Ok(())
+}
+
+/// Deviation between a requested and an actual baud rate that is still considered a match, in
+/// percent of the requested baud rate.
+const BAUD_RATE_TOLERANCE_PERCENT: u32 = 2;
+
+/// Checks whether `actual` deviates from `requested` by at most `tolerance_percent` percent.
+fn baud_rate_matches(requested: u32, actual: u32, tolerance_percent: u32) -> bool {
+    let difference = if requested > actual {
+        requested - actual
+    } else {
+        actual - requested
+    };
+
+    u64::from(difference) * 100 <= u64::from(requested) * u64::from(tolerance_percent)
+}
+
+/// Builds the error `open_verified()` and `safe_reconfigure()` return for the settings that
+/// didn't match.
+fn settings_mismatch(differences: Vec<String>) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("Settings were not applied: {}", differences.join(", ")),
+    )
+}
+
+/// Implements the loop of `SerialPortBuilder::open_with_settings_retry()`.
+///
+/// `open` is called up to `attempts` times while it reports differing settings. Its errors end
+/// the loop right away, whatever their kind.
+fn retry_settings_mismatch<T, F>(attempts: u32, mut open: F) -> Result<T>
+where
+    F: FnMut() -> Result<std::result::Result<T, Vec<String>>>,
+{
+    let mut attempt = 1;
+    loop {
+        match open()? {
+            Ok(port) => return Ok(port),
+            Err(differences) if attempt >= attempts => return Err(settings_mismatch(differences)),
+            Err(_) => attempt += 1,
+        }
+    }
+}
+
+/// Returns how long `bits` bit times last at `baud_rate`, rounded up to whole nanoseconds.
+fn break_duration(bits: u32, baud_rate: u32) -> Result<Duration> {
+    if bits == 0 || baud_rate == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Break length and baud rate must be non-zero",
+        ));
+    }
+    transmission_time(1, bits, baud_rate)
+}
+
+/// Returns the number of bits on the line for each character, including the start bit.
+fn frame_bits(data_bits: DataBits, parity: Parity, stop_bits: StopBits) -> u32 {
+    let data = u32::from(data_bits.bits());
+    let parity = match parity {
+        Parity::None => 0,
+        Parity::Odd | Parity::Even => 1,
+    };
+    let stop = match stop_bits {
+        StopBits::One => 1,
+        StopBits::Two => 2,
+    };
+    1 + data + parity + stop
+}
+
+/// Formats the summary returned by `SerialPort::describe()`.
+fn describe_port(name: Option<String>, settings: Result<SerialPortBuilder>) -> String {
+    let name = name.unwrap_or_else(|| String::from("<unnamed>"));
+    let settings = match settings {
+        Ok(settings) => settings,
+        Err(e) => return format!("{}: settings unavailable ({})", name, e),
+    };
+    let data_bits = settings.data_bits.bits();
+    let parity = match settings.parity {
+        Parity::None => 'N',
+        Parity::Odd => 'O',
+        Parity::Even => 'E',
+    };
+    let stop_bits = match settings.stop_bits {
+        StopBits::One => 1,
+        StopBits::Two => 2,
+    };
+    let flow_control = match settings.flow_control {
+        FlowControl::None => "no",
+        FlowControl::Software => "software",
+        FlowControl::Hardware => "hardware",
+    };
+    format!(
+        "{}: {} baud {}{}{}, {} flow control, timeout {:?}",
+        name, settings.baud_rate, data_bits, parity, stop_bits, flow_control, settings.timeout
+    )
+}
+
+/// Returns how long receiving `n_bytes` characters of `frame_bits` bits takes at `baud_rate`,
+/// rounded up to whole nanoseconds.
+fn transmission_time(n_bytes: usize, frame_bits: u32, baud_rate: u32) -> Result<Duration> {
+    if baud_rate == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Baud rate must be non-zero",
+        ));
+    }
+    let bits = n_bytes as u128 * u128::from(frame_bits);
+    let nanos = (bits * 1_000_000_000 + u128::from(baud_rate) - 1) / u128::from(baud_rate);
+    let secs = nanos / 1_000_000_000;
+    if secs > u128::from(u64::MAX) {
+        return Err(Error::new(ErrorKind::InvalidInput, "Timeout is too long"));
+    }
+    Ok(Duration::new(secs as u64, (nanos % 1_000_000_000) as u32))
+}
+
+/// Byte sent to resume transmission with software flow control
+const XON: u8 = 0x11;
+/// Byte sent to pause transmission with software flow control
+const XOFF: u8 = 0x13;
+
+/// Interval at which control signals are polled when waiting for them to change
+#[cfg_attr(windows, allow(dead_code))]
+const MODEM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Interval at which the input queue is polled when waiting for the line to become quiet
+const QUIESCENCE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Interval at which the port list is polled when waiting for a device to re-enumerate
+const REENUMERATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns the character sizes supported by the port's chipset, if it's known to not support all
+/// of them.
+fn supported_data_bits(port_type: &SerialPortType) -> Option<&'static [DataBits]> {
+    match port_type {
+        // FTDI chips only support 7 and 8 data bits
+        SerialPortType::UsbPort(info) if info.vid == 0x0403 => {
+            Some(&[DataBits::Seven, DataBits::Eight])
+        }
+        _ => None,
+    }
+}
+
+/// Returns the capabilities of a port as far as they're known from its chipset alone.
+fn chipset_capabilities(port_type: &SerialPortType) -> PortCapabilities {
+    let data_bits = supported_data_bits(port_type).unwrap_or(&[
+        DataBits::Five,
+        DataBits::Six,
+        DataBits::Seven,
+        DataBits::Eight,
+    ]);
+    let mut flow_control = vec![FlowControl::None, FlowControl::Software];
+    if hardware_flow_control_support(port_type) != Some(false) {
+        flow_control.push(FlowControl::Hardware);
+    }
+    PortCapabilities {
+        baud_rates: None,
+        arbitrary_baud_rates: None,
+        max_baud_rate: None,
+        data_bits: data_bits.to_vec(),
+        parity: vec![Parity::None, Parity::Odd, Parity::Even],
+        stop_bits: vec![StopBits::One, StopBits::Two],
+        flow_control,
+    }
+}
+
+/// Returns whether the port's chipset supports RTS/CTS flow control, if it's known.
+fn hardware_flow_control_support(port_type: &SerialPortType) -> Option<bool> {
+    match port_type {
+        SerialPortType::UsbPort(info) => match (info.vid, info.pid) {
+            // FTDI, Silicon Labs CP210x and Prolific PL2303
+            (0x0403, _) | (0x10c4, _) | (0x067b, _) => Some(true),
+            // WCH CH340, whose drivers ignore hardware flow control
+            (0x1a86, 0x7523) => Some(false),
+            _ => None,
+        },
+        SerialPortType::PciPort => Some(true),
+        _ => None,
+    }
+}
+
+/// Returns the error of the provided implementations of optional `SerialPort` methods.
+fn unsupported(kind: ErrorKind, operation: &str) -> Error {
+    Error::new(kind, format!("{} is not supported by this port", operation))
+}
+
+/// Checks a cap passed to `SerialPort::set_max_read_size()`.
+fn check_max_read_size(max: Option<usize>) -> Result<()> {
+    if max == Some(0) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Maximum read size must be greater than zero",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the arguments passed to `SerialPort::set_read_mode()`.
+///
+/// Without an inter-byte timeout, `VMIN` greater than 1 blocks until that many bytes arrived, so
+/// this is rejected on every platform even though Windows ignores `min_bytes`.
+fn check_read_mode(min_bytes: u8, inter_byte_timeout: Duration) -> Result<()> {
+    if min_bytes > 1 && inter_byte_timeout == Duration::from_secs(0) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Waiting for more than one byte requires an inter-byte timeout",
+        ));
+    }
+    Ok(())
+}
+
+/// Probes the operations of `port` that are available the same way on every platform.
+fn common_supported_operations<P: SerialPort + ?Sized>(port: &P) -> SupportedOps {
+    let mut ops = SupportedOps::empty();
+    if let Ok(capabilities) = port.capabilities() {
+        if capabilities.arbitrary_baud_rates == Some(true) {
+            ops |= SupportedOps::ARBITRARY_BAUD_RATES;
+        }
+        if capabilities.flow_control.contains(&FlowControl::Hardware) {
+            ops |= SupportedOps::HARDWARE_FLOW_CONTROL;
+        }
+    }
+    // Pseudo terminals and virtual null-modem drivers accept breaks but never put one on a line
+    if !port.is_virtual() {
+        ops |= SupportedOps::BREAK;
+    }
+    if port.modem_lines().is_ok() {
+        ops |= SupportedOps::MODEM_LINES;
+    }
+    if port.rx_buffer_capacity().is_ok() && port.tx_buffer_capacity().is_ok() {
+        ops |= SupportedOps::BUFFER_CAPACITY;
+    }
+    ops
+}
+
+/// Checks `data_bits` against the character sizes supported by the port's chipset.
+#[cfg_attr(not(any(unix, windows)), allow(dead_code))]
+fn check_data_bits_supported(port_type: &SerialPortType, data_bits: DataBits) -> Result<()> {
+    match supported_data_bits(port_type) {
+        Some(supported) if !supported.contains(&data_bits) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Device doesn't support this number of data bits",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the port type `available_ports()` reports for the port at `path`.
+///
+/// Only the device at `path` is looked up, without enumerating or probing the other ports. A
+/// port whose type can't be looked up, such as a pseudo terminal, still opens, with a type of
+/// `SerialPortType::Unknown`.
+#[cfg(any(unix, windows))]
+fn lookup_port_type(path: &str) -> SerialPortType {
+    #[cfg(unix)]
+    let port_type = posix::port_type_of(path);
+    #[cfg(windows)]
+    let port_type = windows::port_type_of(path);
+    port_type.unwrap_or(SerialPortType::Unknown)
+}
+
+/// Implements `SerialPort::wait_reenumerate()`, returning the port opened with `open`.
+fn reenumerate<P, F>(port: &P, identity: &UsbIdentity, timeout: Duration, mut open: F) -> Result<P>
+where
+    P: SerialPort,
+    F: FnMut(&SerialPortBuilder) -> Result<P>,
+{
+    let deadline = Instant::now() + timeout;
+    let settings = port.settings().unwrap_or_else(|_| {
+        new("", 9600)
+            .timeout(port.timeout())
+            .line_ending(port.line_ending())
+    });
+    let timed_out = || {
+        Error::new(
+            ErrorKind::Io(io::ErrorKind::TimedOut),
+            "Device didn't re-enumerate before the timeout",
+        )
+    };
+
+    while !port.awaits_reenumeration() {
+        if Instant::now() >= deadline {
+            return Err(timed_out());
+        }
+        std::thread::sleep(REENUMERATION_POLL_INTERVAL);
+    }
+
+    loop {
+        let reappeared = available_ports()?.into_iter().find(|p| match p.port_type {
+            SerialPortType::UsbPort(ref info) => identity.matches(info),
+            _ => false,
+        });
+        // The device node may show up before it can be opened, e.g. while its permissions are
+        // still being set up, so keep trying until the deadline.
+        let error = match reappeared {
+            Some(info) => match open(&settings.clone().path(info.port_name)) {
+                Ok(reopened) => return Ok(reopened),
+                Err(e) => e,
+            },
+            None => timed_out(),
+        };
+        if Instant::now() >= deadline {
+            return Err(error);
+        }
+        std::thread::sleep(REENUMERATION_POLL_INTERVAL);
+    }
+}
+
+/// Polls the control signals until they differ from `initial` or `timeout` elapses.
+fn poll_modem_change<P: SerialPort + ?Sized>(
+    port: &mut P,
+    initial: ModemStatus,
+    timeout: Option<Duration>,
+) -> Result<ModemStatus> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        let status = port.read_modem_status()?;
+        if status != initial {
+            return Ok(status);
+        }
+        if let Some(deadline) = deadline {
+            if Instant::now() >= deadline {
+                return Err(Error::new(
+                    ErrorKind::Io(io::ErrorKind::TimedOut),
+                    "No control signal changed before the timeout",
+                ));
+            }
+        }
+        std::thread::sleep(MODEM_POLL_INTERVAL);
+    }
+}
+
+/// Implements `SerialPort::detect_flow_control()` once the timeout has been set.
+fn probe_flow_control<P: SerialPort + ?Sized>(port: &mut P, probe: &[u8]) -> Result<FlowControl> {
+    if matches!(port.read_clear_to_send(), Ok(true)) {
+        port.set_flow_control(FlowControl::Hardware)?;
+        let reply = probe_reply(port, probe)?;
+        if reply.is_some() && port.read_clear_to_send()? {
+            return Ok(FlowControl::Hardware);
+        }
+    }
+
+    port.set_flow_control(FlowControl::None)?;
+    match probe_reply(port, probe)? {
+        Some(reply) if reply.iter().any(|&b| b == XON || b == XOFF) => Ok(FlowControl::Software),
+        Some(_) => Ok(FlowControl::None),
+        None => Err(Error::new(
+            ErrorKind::Io(io::ErrorKind::TimedOut),
+            "No reply to the flow control probe",
+        )),
+    }
+}
+
+/// Sends `probe` and returns the first chunk of the reply, or `None` if the read timed out.
+fn probe_reply<P: SerialPort + ?Sized>(port: &mut P, probe: &[u8]) -> Result<Option<Vec<u8>>> {
+    port.clear(ClearBuffer::Input)?;
+    write_all_cancellable(port, probe)?;
+    port.flush()?;
+    let mut buf = [0u8; 64];
+    match port.read(&mut buf) {
+        Ok(n) => Ok(Some(buf[..n].to_vec())),
+        Err(ref e) if e.kind() == io::ErrorKind::TimedOut => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Checks whether a read or write that failed with `e` should be retried right away.
+///
+/// That's the case for `Interrupted` on POSIX, where it means a signal arrived. On Windows it
+/// means the I/O was cancelled from another thread, which has to end the operation.
+fn is_retryable(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::Interrupted && !cfg!(windows)
+}
+
+/// Writes all of `buf` like `write_all()`, but stops when the write is cancelled on Windows.
+fn write_all_cancellable<P: SerialPort + ?Sized>(port: &mut P, mut buf: &[u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        match port.write(buf) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+            }
+            Ok(n) => buf = &buf[n..],
+            Err(ref e) if is_retryable(e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Implements `SerialPort::transaction()`, leaving the port timeout modified.
+fn transact<P: SerialPort + ?Sized>(
+    port: &mut P,
+    request: &[u8],
+    response: &mut [u8],
+    timeout: Duration,
+) -> Result<usize> {
+    port.clear(ClearBuffer::Input)?;
+    write_all_cancellable(port, request)?;
+    port.flush()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut len = 0;
+    while len < response.len() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::from_secs(0) {
+            break;
+        }
+        port.set_timeout(remaining)?;
+        match port.read(&mut response[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => break,
+            Err(ref e) if is_retryable(e) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(len)
+}
+
+/// Implements `SerialPort::read_length_prefixed()`, leaving the port timeout modified.
+fn read_frame<P: SerialPort + ?Sized>(
+    port: &mut P,
+    header_len: usize,
+    length_offset: usize,
+    length_size: LengthFieldSize,
+    endian: Endian,
+    max_len: usize,
+    deadline: Instant,
+) -> Result<Vec<u8>> {
+    let mut frame = vec![0; header_len];
+    read_exact_before(port, &mut frame, deadline)?;
+
+    let field = &frame[length_offset..length_offset + length_size.len()];
+    let payload_len = match endian {
+        Endian::Big => field.iter().fold(0, |len, &b| len << 8 | usize::from(b)),
+        Endian::Little => field
+            .iter()
+            .rev()
+            .fold(0, |len, &b| len << 8 | usize::from(b)),
+    };
+
+    let frame_len = header_len
+        .checked_add(payload_len)
+        .filter(|&len| len <= max_len)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::Io(io::ErrorKind::InvalidData),
+                format!(
+                    "Payload length {} exceeds the maximum frame length of {} bytes",
+                    payload_len, max_len
+                ),
+            )
+        })?;
+    frame.resize(frame_len, 0);
+    read_exact_before(port, &mut frame[header_len..], deadline)?;
+    Ok(frame)
+}
+
+/// Fills `buf` like `read_exact()`, failing with `TimedOut` once `deadline` has passed.
+fn read_exact_before<P: SerialPort + ?Sized>(
+    port: &mut P,
+    buf: &mut [u8],
+    deadline: Instant,
+) -> Result<()> {
+    let mut len = 0;
+    while len < buf.len() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::from_secs(0) {
+            return Err(Error::new(
+                ErrorKind::Io(io::ErrorKind::TimedOut),
+                "Frame not received before the timeout",
+            ));
+        }
+        port.set_timeout(remaining)?;
+        match port.read(&mut buf[len..]) {
+            Ok(n) => len += n,
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(ref e) if is_retryable(e) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Implements `SerialPort::wait_for_sequence()`, leaving the port timeout modified.
+fn read_until_sequence<P: SerialPort + ?Sized>(
+    port: &mut P,
+    needle: &[u8],
+    timeout: Duration,
+) -> Result<Vec<u8>> {
+    read_until_any_sequence(port, &[needle], timeout).map(|(_, received)| received)
+}
+
+/// Implements `SerialPort::can_write()` for the buffer levels in `status`.
+///
+/// The modem `lines` are only looked at under hardware flow control, where they must be passed.
+fn write_fits(
+    status: &QueueStatus,
+    flow_control: FlowControl,
+    lines: Option<ModemLines>,
+    bytes: usize,
+) -> Result<bool> {
+    let capacity = status.tx_capacity.ok_or_else(|| {
+        Error::new(
+            ErrorKind::Unknown,
+            "The output buffer size is not reported for this port",
+        )
+    })?;
+    if flow_control == FlowControl::Hardware
+        && !lines.map_or(false, |lines| lines.contains(ModemLines::CTS))
+    {
+        return Ok(false);
     }
+    let free = capacity.saturating_sub(status.bytes_to_write);
+    Ok(bytes <= free as usize)
+}
 
-    fn timeout(&self) -> Duration {
-        (**self).timeout()
+/// Implements `SerialPort::hardware_flow_diagnostic()` for the modem `lines`, if known.
+fn hw_flow_status(
+    flow_control: FlowControl,
+    lines: Option<ModemLines>,
+    rts_readable: bool,
+) -> HwFlowStatus {
+    let clear_to_send = lines.map(|lines| lines.contains(ModemLines::CTS));
+    HwFlowStatus {
+        flow_control,
+        clear_to_send,
+        rts: lines
+            .filter(|_| rts_readable)
+            .map(|lines| lines.contains(ModemLines::RTS)),
+        write_blocked: flow_control == FlowControl::Hardware && clear_to_send == Some(false),
     }
+}
 
-    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
-        (**self).set_baud_rate(baud_rate)
+/// Returns the index of the longest of `needles` that `received` ends with.
+fn matching_needle(received: &[u8], needles: &[&[u8]]) -> Option<usize> {
+    needles
+        .iter()
+        .enumerate()
+        .filter(|(_, needle)| received.ends_with(needle))
+        .max_by_key(|(_, needle)| needle.len())
+        .map(|(index, _)| index)
+}
+
+/// Implements `SerialPort::read_until_any()`, leaving the port timeout modified.
+fn read_until_any_sequence<P: SerialPort + ?Sized>(
+    port: &mut P,
+    needles: &[&[u8]],
+    timeout: Duration,
+) -> Result<(usize, Vec<u8>)> {
+    let deadline = Instant::now() + timeout;
+    let mut received = Vec::new();
+    loop {
+        if let Some(index) = matching_needle(&received, needles) {
+            return Ok((index, received));
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::from_secs(0) {
+            return Err(Error::new(
+                ErrorKind::Io(io::ErrorKind::TimedOut),
+                "Sequence not received before the timeout",
+            ));
+        }
+        port.set_timeout(remaining)?;
+        let mut byte = [0u8];
+        match port.read(&mut byte) {
+            Ok(0) => {}
+            Ok(_) => received.push(byte[0]),
+            Err(ref e) if is_retryable(e) => {}
+            Err(e) => return Err(e.into()),
+        }
     }
+}
 
-    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
-        (**self).set_data_bits(data_bits)
+/// Parses a `"115200,8,N,1"` settings string, see `SerialPort::configure()`.
+fn parse_settings_spec(spec: &str) -> Result<(u32, DataBits, Parity, StopBits)> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid settings \"{}\", expected e.g. \"115200,8,N,1\"",
+                spec
+            ),
+        )
+    };
+    let fields: Vec<&str> = spec.split(',').map(str::trim).collect();
+    if fields.len() != 4 {
+        return Err(invalid());
     }
+    let baud_rate = fields[0].parse().map_err(|_| invalid())?;
+    let data_bits = match fields[1] {
+        "5" => DataBits::Five,
+        "6" => DataBits::Six,
+        "7" => DataBits::Seven,
+        "8" => DataBits::Eight,
+        _ => return Err(invalid()),
+    };
+    let parity = match fields[2] {
+        "N" | "n" => Parity::None,
+        "O" | "o" => Parity::Odd,
+        "E" | "e" => Parity::Even,
+        _ => return Err(invalid()),
+    };
+    let stop_bits = match fields[3] {
+        "1" => StopBits::One,
+        "2" => StopBits::Two,
+        _ => return Err(invalid()),
+    };
+    Ok((baud_rate, data_bits, parity, stop_bits))
+}
 
-    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
-        (**self).set_flow_control(flow_control)
+/// Replaces the line breaks in `text` with `line_ending`, see `SerialPort::write_str()`.
+fn translate_line_endings(text: &str, line_ending: LineEnding) -> std::borrow::Cow<'_, str> {
+    if line_ending == LineEnding::None || !text.contains('\n') {
+        return text.into();
+    }
+    let mut translated = String::with_capacity(text.len() + text.len() / 8);
+    let mut lines = text.split('\n').peekable();
+    while let Some(line) = lines.next() {
+        if lines.peek().is_some() {
+            translated.push_str(line.strip_suffix('\r').unwrap_or(line));
+            translated.push_str(line_ending.as_str());
+        } else {
+            translated.push_str(line);
+        }
+    }
+    translated.into()
+}
+
+/// Implements `SerialPort::measure_latency()`, leaving the port timeout modified.
+fn sample_latency<P: SerialPort + ?Sized>(
+    port: &mut P,
+    probe: &[u8],
+    expect: &[u8],
+    samples: usize,
+    timeout: Duration,
+) -> Result<LatencyStats> {
+    let mut stats = LatencyStats::default();
+    let mut total = Duration::from_secs(0);
+    for _ in 0..samples {
+        port.clear(ClearBuffer::All)?;
+        let start = Instant::now();
+        write_all_cancellable(port, probe)?;
+        port.flush()?;
+        match read_until_sequence(port, expect, timeout) {
+            Ok(_) => {
+                let elapsed = start.elapsed();
+                stats.replies += 1;
+                total += elapsed;
+                stats.min = Some(stats.min.map_or(elapsed, |min| min.min(elapsed)));
+                stats.max = Some(stats.max.map_or(elapsed, |max| max.max(elapsed)));
+            }
+            Err(ref e) if e.kind() == ErrorKind::Io(io::ErrorKind::TimedOut) => stats.timeouts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+    if stats.replies > 0 {
+        stats.mean = Some(total / stats.replies as u32);
+    }
+    Ok(stats)
+}
+
+/// Implements `SerialPort::measure_throughput()`.
+fn sample_throughput<P: SerialPort + ?Sized>(
+    port: &mut P,
+    total_bytes: usize,
+    chunk: usize,
+    timeout: Duration,
+) -> Result<ThroughputStats> {
+    let pattern: Vec<u8> = (0..total_bytes).map(|i| i as u8).collect();
+    let mut received = Vec::with_capacity(total_bytes);
+    let mut buf = vec![0u8; chunk.max(256)];
+    let mut read_available = |port: &mut P, received: &mut Vec<u8>| -> Result<()> {
+        let available = port.bytes_to_read()? as usize;
+        let wanted = available.min(total_bytes - received.len()).min(buf.len());
+        if wanted > 0 {
+            let n = port.read(&mut buf[..wanted])?;
+            received.extend_from_slice(&buf[..n]);
+        }
+        Ok(())
+    };
+
+    port.clear(ClearBuffer::All)?;
+    let start = Instant::now();
+    let mut read_time = Duration::from_secs(0);
+    for data in pattern.chunks(chunk) {
+        write_all_cancellable(port, data)?;
+        let before = received.len();
+        read_available(port, &mut received)?;
+        if received.len() > before {
+            read_time = start.elapsed();
+        }
+    }
+    port.flush()?;
+    let write_time = start.elapsed();
+
+    let deadline = Instant::now() + timeout;
+    while received.len() < total_bytes {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if !port.has_data(remaining)? {
+            break;
+        }
+        let before = received.len();
+        read_available(port, &mut received)?;
+        if received.len() > before {
+            read_time = start.elapsed();
+        }
+    }
+
+    Ok(ThroughputStats {
+        bytes_written: total_bytes,
+        bytes_read: received.len(),
+        corrupted: received
+            .iter()
+            .zip(pattern.iter())
+            .filter(|(a, b)| a != b)
+            .count(),
+        write_time,
+        read_time,
+    })
+}
+
+/// Writes `buf` one byte at a time with `delay` in between, for
+/// `SerialPort::set_inter_byte_write_delay()`.
+///
+/// `write_byte` must wait for the byte to be transmitted. If writing fails after some bytes were
+/// sent, the number of bytes sent is returned instead of the error, as `io::Write` requires.
+fn write_paced<F>(buf: &[u8], delay: Duration, mut write_byte: F) -> io::Result<usize>
+where
+    F: FnMut(&[u8]) -> io::Result<usize>,
+{
+    for (i, byte) in buf.iter().enumerate() {
+        if i > 0 {
+            std::thread::sleep(delay);
+        }
+        match write_byte(std::slice::from_ref(byte)) {
+            Ok(0) => return Ok(i),
+            Ok(_) => {}
+            Err(e) if i == 0 => return Err(e),
+            Err(_) => return Ok(i),
+        }
+    }
+    Ok(buf.len())
+}
+
+/// Runs `read` on a new thread, returning the handle that sets its `stop` flag.
+fn spawn_reader_thread<F>(read: F) -> ReaderHandle
+where
+    F: FnOnce(&AtomicBool) -> Result<()> + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        let finished = finished.clone();
+        std::thread::spawn(move || {
+            let result = read(&stop);
+            finished.store(true, Ordering::SeqCst);
+            result
+        })
+    };
+    ReaderHandle {
+        stop,
+        finished,
+        thread: Some(thread),
+    }
+}
+
+/// Returns how long a reader thread waits for data before checking whether to stop.
+///
+/// This is the port's timeout, unless that's zero, which would make the thread spin.
+fn reader_wait(timeout: Duration) -> Duration {
+    if timeout == Duration::from_secs(0) {
+        REASONABLE_TIMEOUT
+    } else {
+        timeout
+    }
+}
+
+/// Implements `SerialPort::read_to_channel()`.
+fn read_into_channel(
+    mut port: Box<dyn SerialPort>,
+    stop: &AtomicBool,
+    tx: std::sync::mpsc::SyncSender<Vec<u8>>,
+    chunk: usize,
+) -> Result<()> {
+    use std::sync::mpsc::TrySendError;
+
+    let gap = port.timeout();
+    let idle = reader_wait(gap);
+    let mut buf = vec![0u8; chunk];
+    let mut filled = 0;
+    while !stop.load(Ordering::SeqCst) {
+        let wait = if filled == 0 { idle } else { gap };
+        if port.has_data(wait)? {
+            match port.read(&mut buf[filled..]) {
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+                Err(e) => return Err(e.into()),
+            }
+            if filled < chunk {
+                continue;
+            }
+        } else if filled == 0 {
+            continue;
+        }
+
+        let mut data = buf[..filled].to_vec();
+        filled = 0;
+        loop {
+            match tx.try_send(data) {
+                Ok(()) => break,
+                Err(TrySendError::Full(unsent)) => {
+                    if stop.load(Ordering::SeqCst) {
+                        return Ok(());
+                    }
+                    data = unsent;
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(TrySendError::Disconnected(_)) => return Ok(()),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Implements the thread of `SerialPort::spawn_reader()`, reading until `stop` is set.
+fn read_into_callback<F: FnMut(&[u8])>(
+    mut port: Box<dyn SerialPort>,
+    stop: &AtomicBool,
+    mut on_data: F,
+) -> Result<()> {
+    let wait = reader_wait(port.timeout());
+    let mut buf = [0u8; 1024];
+    while !stop.load(Ordering::SeqCst) {
+        if !port.has_data(wait)? {
+            continue;
+        }
+        match port.read(&mut buf) {
+            Ok(0) => {}
+            Ok(n) => on_data(&buf[..n]),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Implements `SerialPort::read_with_keepalive()`, leaving the port timeout modified.
+fn read_keepalive<P: SerialPort + ?Sized>(
+    port: &mut P,
+    buf: &mut [u8],
+    keepalive: Duration,
+    keepalive_bytes: &[u8],
+    total_timeout: Duration,
+) -> Result<usize> {
+    let deadline = Instant::now() + total_timeout;
+    let mut next_keepalive = Instant::now() + keepalive;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(Error::new(
+                ErrorKind::Io(io::ErrorKind::TimedOut),
+                "No data received before the timeout",
+            ));
+        }
+        if now >= next_keepalive {
+            write_all_cancellable(port, keepalive_bytes)?;
+            port.flush()?;
+            next_keepalive += keepalive;
+            continue;
+        }
+        port.set_timeout(std::cmp::min(deadline, next_keepalive) - now)?;
+        match port.read(buf) {
+            Ok(n) => return Ok(n),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(ref e) if is_retryable(e) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Contains all possible USB information about a `SerialPort`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsbPortInfo {
+    /// Vendor ID
+    pub vid: u16,
+    /// Product ID
+    pub pid: u16,
+    /// Serial number (arbitrary string)
+    pub serial_number: Option<String>,
+    /// Manufacturer (arbitrary string)
+    pub manufacturer: Option<String>,
+    /// Product name (arbitrary string)
+    pub product: Option<String>,
+    /// Interface (id number for multiplexed devices)
+    pub interface: Option<u8>,
+}
+
+/// Identifies a USB serial device across re-enumerations
+///
+/// Unlike the port name, which may change when a device resets, these properties stay the same.
+/// `None` fields match any value. See
+/// [`SerialPort::wait_reenumerate`](trait.SerialPort.html#method.wait_reenumerate).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsbIdentity {
+    /// Vendor ID
+    pub vid: u16,
+    /// Product ID
+    pub pid: u16,
+    /// Serial number (arbitrary string)
+    pub serial_number: Option<String>,
+    /// Interface (id number for multiplexed devices)
+    pub interface: Option<u8>,
+}
+
+impl UsbIdentity {
+    /// Returns whether the USB port described by `info` has this identity
+    pub fn matches(&self, info: &UsbPortInfo) -> bool {
+        self.vid == info.vid
+            && self.pid == info.pid
+            && (self.serial_number.is_none() || self.serial_number == info.serial_number)
+            && (self.interface.is_none() || self.interface == info.interface)
+    }
+}
+
+impl From<&UsbPortInfo> for UsbIdentity {
+    fn from(info: &UsbPortInfo) -> Self {
+        UsbIdentity {
+            vid: info.vid,
+            pid: info.pid,
+            serial_number: info.serial_number.clone(),
+            interface: info.interface,
+        }
+    }
+}
+
+/// The serial ports exposed by a single USB device
+///
+/// Returned by [`available_ports_grouped`](fn.available_ports_grouped.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsbDeviceGroup {
+    /// Vendor ID
+    pub vid: u16,
+    /// Product ID
+    pub pid: u16,
+    /// Serial number (arbitrary string)
+    pub serial_number: Option<String>,
+    /// Manufacturer (arbitrary string)
+    pub manufacturer: Option<String>,
+    /// Product name (arbitrary string)
+    pub product: Option<String>,
+    /// The ports of the device, ordered by interface number
+    pub ports: Vec<SerialPortInfo>,
+}
+
+/// The physical type of a `SerialPort`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SerialPortType {
+    /// The serial port is connected via USB
+    UsbPort(UsbPortInfo),
+    /// The serial port is connected via PCI (permanent port)
+    PciPort,
+    /// The serial port is connected via Bluetooth
+    BluetoothPort,
+    /// It can't be determined how the serial port is connected
+    Unknown,
+}
+
+/// A device-independent implementation of serial port information
+///
+/// Infos are ordered by port name first, so sorted lists and sets of them are in name order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SerialPortInfo {
+    /// The short name of the serial port
+    pub port_name: String,
+    /// The hardware device type that exposes this port
+    pub port_type: SerialPortType,
+    /// The name of the driver handling this port, if known
+    ///
+    /// On Linux this is the kernel module bound to the device (e.g. `ftdi_sio` or `cdc_acm`), on
+    /// Windows the name of the device's service (e.g. `FTDIBUS` or `usbser`). This isn't
+    /// reported on other platforms.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub driver_name: Option<String>,
+    /// A path to the port that stays the same across reboots and replugging, if there is one
+    ///
+    /// On Linux this is the matching symlink in `/dev/serial/by-id/` (or `/dev/serial/by-path/`
+    /// for devices without a serial number) created by udev, which can be opened like
+    /// `port_name`. This isn't reported on other platforms.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub by_id_path: Option<String>,
+}
+
+/// A class of serial devices, see `available_ports_by_subsystem()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortSubsystem {
+    /// USB devices implementing the CDC ACM class, like most microcontroller boards
+    ///
+    /// These are the `ttyACM*` ports of the `cdc_acm` driver on Linux, the ports of the `usbser`
+    /// driver on Windows, and the `usbmodem` ports on macOS.
+    CdcAcm,
+    /// Other USB devices, typically USB to serial bridges from FTDI, Silicon Labs, WCH or
+    /// Prolific, like the `ttyUSB*` ports on Linux
+    UsbBridge,
+    /// UARTs built into the machine or attached via PCI, like the `ttyS*` ports on Linux
+    Onboard,
+    /// Serial ports over Bluetooth, like the `rfcomm*` ports on Linux
+    Bluetooth,
+}
+
+impl SerialPortInfo {
+    /// Opens the port described by this info with the settings from `builder`
+    ///
+    /// The path set on `builder` is replaced with `port_name`, so enumerated ports can be opened
+    /// without building the device path by hand.
+    ///
+    /// ```no_run
+    /// # fn main() -> serialport::Result<()> {
+    /// let settings = serialport::new("", 115_200);
+    /// for info in serialport::available_ports()? {
+    ///     let port = info.open(settings.clone())?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// See `SerialPortBuilder::open()`.
+    pub fn open(&self, builder: SerialPortBuilder) -> Result<Box<dyn SerialPort>> {
+        builder.path(&self.port_name).open()
+    }
+}
+
+/// The timeout used by builders created with [`new`]
+///
+/// A zero timeout makes reads return immediately if no data is available. This is kept for
+/// backwards compatibility; most applications want a longer timeout such as
+/// [`REASONABLE_TIMEOUT`].
+///
+/// [`new`]: fn.new.html
+/// [`REASONABLE_TIMEOUT`]: constant.REASONABLE_TIMEOUT.html
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(0);
+
+/// A timeout that is long enough to not be affected by the poll granularity of the OS
+///
+/// This is used by [`SerialPortBuilder::with_reasonable_defaults`] and as the initial timeout of
+/// ports which aren't opened through a builder, such as `TTYPort::pair()`.
+///
+/// [`SerialPortBuilder::with_reasonable_defaults`]: struct.SerialPortBuilder.html#method.with_reasonable_defaults
+pub const REASONABLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The longest frame [`SerialPort::read_length_prefixed`] accepts, header included
+///
+/// This fits every frame with a length field of one or two bytes, while a corrupted four byte
+/// length field can't make it allocate gigabytes. `read_length_prefixed_max()` takes a different
+/// limit.
+///
+/// [`SerialPort::read_length_prefixed`]: trait.SerialPort.html#method.read_length_prefixed
+pub const MAX_FRAME_LEN: usize = 1024 * 1024;
+
+/// Construct a builder of `SerialPort` objects
+///
+/// `SerialPort` objects are built using the Builder pattern through the `new` function. The
+/// resultant `SerialPortBuilder` object can be copied, reconfigured, and saved making working with
+/// multiple serial ports a little easier.
+///
+/// To open a new serial port:
+/// ```no_run
+/// serialport::new("/dev/ttyUSB0", 9600).open().expect("Failed to open port");
+/// ```
+///
+/// The builder starts with 8 data bits, no flow control, no parity, one stop bit and a timeout
+/// of [`DEFAULT_TIMEOUT`].
+///
+/// [`DEFAULT_TIMEOUT`]: constant.DEFAULT_TIMEOUT.html
+pub fn new<'a>(path: impl Into<std::borrow::Cow<'a, str>>, baud_rate: u32) -> SerialPortBuilder {
+    SerialPortBuilder {
+        path: path.into().into_owned(),
+        baud_rate,
+        baud_rate_tolerance: BAUD_RATE_TOLERANCE_PERCENT,
+        data_bits: DataBits::Eight,
+        flow_control: FlowControl::None,
+        parity: Parity::None,
+        stop_bits: StopBits::One,
+        timeout: DEFAULT_TIMEOUT,
+        settle_delay: None,
+        line_ending: LineEnding::None,
+        #[cfg(windows)]
+        shared: false,
+        #[cfg(unix)]
+        controlling_tty: false,
+        #[cfg(unix)]
+        raw: true,
+        #[cfg(unix)]
+        append: false,
+        #[cfg(unix)]
+        exclusive_open: false,
+        #[cfg(unix)]
+        follow_symlinks: true,
+    }
+}
+
+/// Opens the port at `path` and applies `raw` verbatim
+///
+/// **This is an advanced, platform-specific escape hatch**, see `RawPortConfig`. None of the
+/// settings a `SerialPortBuilder` applies are used, so the port ends up configured exactly like
+/// another tool left it, e.g. with a `termios` or `DCB` captured from it. This is the same as
+/// `TTYPort::open_with_raw_config()` on POSIX and `COMPort::open_with_raw_config()` on Windows.
+///
+/// ## Errors
+///
+/// See `TTYPort::open_with_raw_config()` and `COMPort::open_with_raw_config()`.
+#[cfg(unix)]
+pub fn open_with_raw_config(path: &str, raw: RawPortConfig) -> Result<TTYPort> {
+    posix::TTYPort::open_with_raw_config(path, &raw)
+}
+
+/// Opens the port at `path` and applies `raw` verbatim
+///
+/// **This is an advanced, platform-specific escape hatch**, see `RawPortConfig`. None of the
+/// settings a `SerialPortBuilder` applies are used, so the port ends up configured exactly like
+/// another tool left it, e.g. with a `DCB` and `COMMTIMEOUTS` captured from it. This is the same
+/// as `TTYPort::open_with_raw_config()` on POSIX and `COMPort::open_with_raw_config()` on
+/// Windows.
+///
+/// ## Errors
+///
+/// See `TTYPort::open_with_raw_config()` and `COMPort::open_with_raw_config()`.
+#[cfg(windows)]
+pub fn open_with_raw_config(path: &str, raw: RawPortConfig) -> Result<COMPort> {
+    windows::COMPort::open_with_raw_config(path, &raw)
+}
+
+/// Returns the serial ports whose name matches a wildcard `pattern`
+///
+/// The pattern syntax is that of shell wildcards: `*` matches any number of characters, `?`
+/// matches a single character, and `[...]` matches a single character out of a set such as
+/// `[0-9]` or `[abc]`. A set starting with `!`, e.g. `[!0-9]`, matches any character not in it.
+/// Matching is case-sensitive.
+///
+/// If `pattern` contains no `/`, it's matched against the last component of the port name, so
+/// `"ttyUSB*"` matches `/dev/ttyUSB0`. Otherwise it's matched against the whole name. On
+/// Windows, names such as `COM3` have a single component, so `"COM[3-9]"` selects `COM3` to
+/// `COM9`.
+///
+/// ## Errors
+///
+/// This function returns the same errors as `available_ports()`, as well as `InvalidInput` if
+/// `pattern` contains an unterminated `[`.
+pub fn available_ports_matching_name(pattern: &str) -> Result<Vec<SerialPortInfo>> {
+    let glob = glob::Glob::new(pattern)?;
+    let match_whole_name = pattern.contains('/');
+    let mut ports = available_ports()?;
+    ports.retain(|p| {
+        let name = if match_whole_name {
+            &p.port_name[..]
+        } else {
+            p.port_name.rsplit('/').next().unwrap_or(&p.port_name)
+        };
+        glob.matches(name)
+    });
+    Ok(ports)
+}
+
+/// Returns the USB serial ports whose product or manufacturer name contains `substr`
+///
+/// Users often know their device by its name, such as "Arduino" or "STLink", rather than by
+/// its vendor and product IDs. The match is case-insensitive, so `"arduino"` matches a product
+/// named `Arduino Uno`. Only USB ports are matched, and only if the device reports a product or
+/// manufacturer name, which some devices and drivers don't.
+///
+/// ## Errors
+///
+/// This function returns the same errors as `available_usb_ports()`.
+pub fn available_ports_matching_product(substr: &str) -> Result<Vec<SerialPortInfo>> {
+    let substr = substr.to_lowercase();
+    let mut ports = available_usb_ports()?;
+    ports.retain(|p| match p.port_type {
+        SerialPortType::UsbPort(ref info) => product_matches(info, &substr),
+        _ => false,
+    });
+    Ok(ports)
+}
+
+/// Returns the serial ports of the class `subsystem`
+///
+/// This gives tools a way to target e.g. CDC ACM devices without matching port names, which
+/// differ across platforms and distributions. The class is derived from the port type, the
+/// driver name and, where those don't tell, the port name, so it's only precise where the
+/// driver name is reported (see `SerialPortInfo::driver_name`). On Linux, ports of a hardware
+/// driver that aren't on USB, PCI or Bluetooth are taken to be `Onboard`, which covers the
+/// UARTs of SoCs; on Windows only the ports of the in-box `Serial` driver are.
+///
+/// ## Errors
+///
+/// This function returns the same errors as `available_ports()`.
+pub fn available_ports_by_subsystem(subsystem: PortSubsystem) -> Result<Vec<SerialPortInfo>> {
+    let mut ports = available_ports()?;
+    ports.retain(|p| port_subsystem(p) == Some(subsystem));
+    Ok(ports)
+}
+
+/// Classifies `info` for `available_ports_by_subsystem()`.
+fn port_subsystem(info: &SerialPortInfo) -> Option<PortSubsystem> {
+    let name = info.port_name.rsplit('/').next().unwrap_or(&info.port_name);
+    let driver = info.driver_name.as_ref().map(|d| d.to_lowercase());
+    let driver = driver.as_deref();
+    match info.port_type {
+        SerialPortType::UsbPort(_) => {
+            if driver == Some("cdc_acm")
+                || driver == Some("usbser")
+                || name.starts_with("ttyACM")
+                || name.contains("usbmodem")
+            {
+                Some(PortSubsystem::CdcAcm)
+            } else {
+                Some(PortSubsystem::UsbBridge)
+            }
+        }
+        SerialPortType::PciPort => Some(PortSubsystem::Onboard),
+        SerialPortType::BluetoothPort => Some(PortSubsystem::Bluetooth),
+        SerialPortType::Unknown => {
+            if name.starts_with("rfcomm") {
+                Some(PortSubsystem::Bluetooth)
+            } else if driver == Some("serial") || (cfg!(target_os = "linux") && driver.is_some()) {
+                Some(PortSubsystem::Onboard)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Checks whether the product or manufacturer name of `info` contains the lowercase `substr`.
+fn product_matches(info: &UsbPortInfo, substr: &str) -> bool {
+    [&info.product, &info.manufacturer]
+        .iter()
+        .filter_map(|name| name.as_ref())
+        .any(|name| name.to_lowercase().contains(substr))
+}
+
+/// Opens the port of a USB device's interface `interface` with the settings from `builder`
+///
+/// Devices with several serial interfaces, such as multi-port adapters or boards exposing a
+/// console and a data port, enumerate their ports in no particular order. This selects the
+/// port by its USB identity instead: the vendor ID, product ID, interface number and, if given,
+/// the serial number. If several connected devices match, e.g. identical devices when no serial
+/// number is given, the one with the lowest port name is opened.
+///
+/// The path set on `builder` is replaced with the name of the matching port.
+///
+/// ## Errors
+///
+/// This function returns the same errors as `available_usb_ports()` and
+/// `SerialPortBuilder::open()`, as well as `NoDevice` if no matching port is present.
+pub fn open_usb_interface(
+    vid: u16,
+    pid: u16,
+    interface: u8,
+    serial: Option<&str>,
+    builder: &SerialPortBuilder,
+) -> Result<Box<dyn SerialPort>> {
+    let identity = UsbIdentity {
+        vid,
+        pid,
+        serial_number: serial.map(String::from),
+        interface: Some(interface),
+    };
+    available_usb_ports()?
+        .into_iter()
+        .filter(|port| match port.port_type {
+            SerialPortType::UsbPort(ref info) => identity.matches(info),
+            _ => false,
+        })
+        .min_by(|a, b| a.port_name.cmp(&b.port_name))
+        .ok_or_else(|| Error::new(ErrorKind::NoDevice, "No matching USB interface is present"))?
+        .open(builder.clone())
+}
+
+/// Opens each serial port and returns the first one for which `probe` returns `true`
+///
+/// This is the usual "which port is my device on" scan: ports are enumerated as with
+/// `available_ports_iter()` and opened one after another with the settings from `builder`,
+/// then `probe` is run on the open port, e.g. to send a command and check the reply. The first
+/// port accepted by `probe` is returned still open. Every other port is closed before the next
+/// one is opened.
+///
+/// Ports that can't be examined or opened, e.g. because another process holds them, are
+/// skipped, as are ports for which `probe` fails, so a timeout waiting for a reply simply
+/// rejects the port. Keep in mind that probing sends data to every port on the system.
+///
+/// The path set on `builder` is replaced with the name of each port. Returns `None` if no port
+/// was accepted.
+///
+/// ```no_run
+/// # use std::io::{Read, Write};
+/// # fn main() -> serialport::Result<()> {
+/// let settings = serialport::new("", 115_200);
+/// let port = serialport::find_port(&settings, |port| {
+///     port.write_all(b"ID?\n")?;
+///     let mut reply = [0; 6];
+///     port.read_exact(&mut reply)?;
+///     Ok(&reply == b"ACME1\n")
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Errors
+///
+/// This function returns the same errors as `available_ports_iter()`.
+pub fn find_port<F>(builder: &SerialPortBuilder, probe: F) -> Result<Option<Box<dyn SerialPort>>>
+where
+    F: Fn(&mut dyn SerialPort) -> Result<bool>,
+{
+    for info in available_ports_iter()?.filter_map(|info| info.ok()) {
+        let mut port = match info.open(builder.clone()) {
+            Ok(port) => port,
+            Err(_) => continue,
+        };
+        if let Ok(true) = probe(&mut *port) {
+            return Ok(Some(port));
+        }
     }
+    Ok(None)
+}
 
-    fn set_parity(&mut self, parity: Parity) -> Result<()> {
-        (**self).set_parity(parity)
+/// Waits until a serial port accepted by `matcher` is present and returns its info
+///
+/// This lets a program start before its device is plugged in. The ports are enumerated as with
+/// `available_ports()` every 50ms, and the first port for which `matcher` returns `true` is
+/// returned, right away if it's already present. A `timeout` of `None` waits forever.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # fn main() -> serialport::Result<()> {
+/// let info = serialport::wait_for_port(
+///     |info| match info.port_type {
+///         serialport::SerialPortType::UsbPort(ref usb) => usb.vid == 0x2341,
+///         _ => false,
+///     },
+///     Some(Duration::from_secs(30)),
+/// )?;
+/// let port = info.open(serialport::new("", 115_200))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Errors
+///
+/// * `NoDevice` if no matching port appeared within `timeout`.
+/// * Any error returned by `available_ports()`.
+pub fn wait_for_port<F>(matcher: F, timeout: Option<Duration>) -> Result<SerialPortInfo>
+where
+    F: Fn(&SerialPortInfo) -> bool,
+{
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        if let Some(info) = available_ports()?.into_iter().find(|info| matcher(info)) {
+            return Ok(info);
+        }
+        match deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                return Err(Error::new(
+                    ErrorKind::NoDevice,
+                    "No matching port appeared before the timeout",
+                ))
+            }
+            _ => std::thread::sleep(REENUMERATION_POLL_INTERVAL),
+        }
     }
+}
 
-    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
-        (**self).set_stop_bits(stop_bits)
-    }
+/// Opens a port with the settings from `builder`, runs `f` on it and closes it again
+///
+/// This scopes the port to a one-shot task. The port is closed before this returns, whether `f`
+/// succeeds or fails, and also when `f` panics, as the port is dropped while unwinding. The
+/// result of `f` is returned as it is.
+///
+/// ```no_run
+/// # use std::io::Write;
+/// # fn main() -> serialport::Result<()> {
+/// serialport::with_port(&serialport::new("/dev/ttyUSB0", 115_200), |port| {
+///     port.write_all(b"RESET\n")?;
+///     Ok(())
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Errors
+///
+/// This function returns the errors of `SerialPortBuilder::open()` and of `f`.
+pub fn with_port<R, F>(builder: &SerialPortBuilder, f: F) -> Result<R>
+where
+    F: FnOnce(&mut dyn SerialPort) -> Result<R>,
+{
+    let mut port = builder.clone().open()?;
+    let result = f(&mut *port);
+    drop(port);
+    result
+}
 
-    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
-        (**self).set_timeout(timeout)
+/// Opens a group of serial ports, either all of them or none
+///
+/// The ports are opened in order with the settings and paths from `builders`, and returned in
+/// the same order. If any of them fails to open, the ports opened so far are closed before the
+/// error is returned, so a multi-device setup is never left with only some of its ports open.
+///
+/// ## Errors
+///
+/// This function returns the first error returned by `SerialPortBuilder::open()`, with the path
+/// of the port that failed prepended to its description.
+pub fn open_all(builders: &[SerialPortBuilder]) -> Result<Vec<Box<dyn SerialPort>>> {
+    let mut ports = Vec::with_capacity(builders.len());
+    for builder in builders {
+        match builder.clone().open() {
+            Ok(port) => ports.push(port),
+            Err(mut e) => {
+                // Close what was opened, most recent first, before reporting the failure
+                while let Some(port) = ports.pop() {
+                    drop(port);
+                }
+                e.description = format!("{}: {}", builder.path, e.description);
+                return Err(e);
+            }
+        }
     }
+    Ok(ports)
+}
 
-    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
-        (**self).write_request_to_send(level)
-    }
+/// Returns the USB serial ports on system, grouped by the device they belong to
+///
+/// Multi-port adapters and composite devices expose one port per interface. This collects the
+/// ports sharing a vendor ID, product ID and serial number into one `UsbDeviceGroup`, so they can
+/// be presented as a single device. Groups are returned in enumeration order.
+///
+/// Identical devices without a serial number can't be told apart, so their ports end up in the
+/// same group.
+///
+/// It is not guaranteed that these ports exist or are available even if they're
+/// returned by this function.
+pub fn available_ports_grouped() -> Result<Vec<UsbDeviceGroup>> {
+    Ok(group_usb_ports(available_usb_ports()?))
+}
 
-    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
-        (**self).write_data_terminal_ready(level)
+fn group_usb_ports(ports: Vec<SerialPortInfo>) -> Vec<UsbDeviceGroup> {
+    let mut groups: Vec<UsbDeviceGroup> = Vec::new();
+    for port in ports {
+        let info = match port.port_type {
+            SerialPortType::UsbPort(ref info) => info.clone(),
+            _ => continue,
+        };
+        match groups.iter_mut().find(|g| {
+            g.vid == info.vid && g.pid == info.pid && g.serial_number == info.serial_number
+        }) {
+            Some(group) => group.ports.push(port),
+            None => groups.push(UsbDeviceGroup {
+                vid: info.vid,
+                pid: info.pid,
+                serial_number: info.serial_number,
+                manufacturer: info.manufacturer,
+                product: info.product,
+                ports: vec![port],
+            }),
+        }
     }
-
-    fn read_clear_to_send(&mut self) -> Result<bool> {
-        (**self).read_clear_to_send()
+    for group in &mut groups {
+        group.ports.sort_by_key(|p| match p.port_type {
+            SerialPortType::UsbPort(ref info) => info.interface,
+            _ => None,
+        });
     }
+    groups
+}
 
-    fn read_data_set_ready(&mut self) -> Result<bool> {
-        (**self).read_data_set_ready()
-    }
+/// Returns a list of all serial ports on system
+///
+/// It is not guaranteed that these ports exist or are available even if they're
+/// returned by this function.
+pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+    #[cfg(unix)]
+    return crate::posix::available_ports();
 
-    fn read_ring_indicator(&mut self) -> Result<bool> {
-        (**self).read_ring_indicator()
-    }
+    #[cfg(windows)]
+    return crate::windows::available_ports();
 
-    fn read_carrier_detect(&mut self) -> Result<bool> {
-        (**self).read_carrier_detect()
-    }
+    #[cfg(not(any(unix, windows)))]
+    Err(Error::new(
+        ErrorKind::Unknown,
+        "available_ports() not implemented for platform",
+    ))
+}
 
-    fn bytes_to_read(&self) -> Result<u32> {
-        (**self).bytes_to_read()
-    }
+/// Returns the serial ports on system as a set, ordered by port name
+///
+/// This is `available_ports()` collected into a set, for callers that detect hotplug by
+/// comparing enumerations themselves:
+///
+/// ```no_run
+/// # fn main() -> serialport::Result<()> {
+/// let old = serialport::port_set()?;
+/// // ...
+/// let new = serialport::port_set()?;
+/// for port in new.difference(&old) {
+///     println!("Added {}", port.port_name);
+/// }
+/// for port in old.difference(&new) {
+///     println!("Removed {}", port.port_name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A port whose properties changed, e.g. because a different device was plugged into the same
+/// port, shows up as both removed and added.
+///
+/// ## Errors
+///
+/// This function returns the same errors as `available_ports()`.
+pub fn port_set() -> Result<BTreeSet<SerialPortInfo>> {
+    Ok(available_ports()?.into_iter().collect())
+}
 
-    fn bytes_to_write(&self) -> Result<u32> {
-        (**self).bytes_to_write()
-    }
+/// Returns a list of the USB serial ports on system
+///
+/// This is the subset of `available_ports()` with a port type of `SerialPortType::UsbPort`. On
+/// Linux only USB devices are requested from udev, which avoids probing the other ports.
+///
+/// It is not guaranteed that these ports exist or are available even if they're
+/// returned by this function.
+pub fn available_usb_ports() -> Result<Vec<SerialPortInfo>> {
+    #[cfg(unix)]
+    return crate::posix::available_usb_ports();
 
-    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
-        (**self).clear(buffer_to_clear)
-    }
+    #[cfg(windows)]
+    return crate::windows::available_usb_ports();
 
-    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
-        (**self).try_clone()
-    }
+    #[cfg(not(any(unix, windows)))]
+    Err(Error::new(
+        ErrorKind::Unknown,
+        "available_usb_ports() not implemented for platform",
+    ))
+}
 
-    fn set_break(&self) -> Result<()> {
-        (**self).set_break()
-    }
+/// Returns the serial port to use when the user didn't name one
+///
+/// This is the USB serial port if exactly one is connected, as is common when working with a
+/// single development board or adapter. With no USB serial port, or several of them, there is
+/// no obvious choice and `None` is returned so that the caller can ask the user instead. On
+/// macOS, the dial-in device `/dev/tty.*` and callout device `/dev/cu.*` of a port count as one,
+/// and the callout device is returned.
+pub fn default_port() -> Result<Option<SerialPortInfo>> {
+    available_usb_ports().map(single_port)
+}
 
-    fn clear_break(&self) -> Result<()> {
-        (**self).clear_break()
+fn single_port(ports: Vec<SerialPortInfo>) -> Option<SerialPortInfo> {
+    let names: Vec<String> = ports.iter().map(|p| p.port_name.clone()).collect();
+    let mut ports: Vec<SerialPortInfo> = ports
+        .into_iter()
+        .filter(|p| match p.port_name.strip_prefix("/dev/tty.") {
+            Some(suffix) => !names.contains(&format!("/dev/cu.{}", suffix)),
+            None => true,
+        })
+        .collect();
+    if ports.len() == 1 {
+        ports.pop()
+    } else {
+        None
     }
 }
 
-/// Contains all possible USB information about a `SerialPort`
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct UsbPortInfo {
-    /// Vendor ID
-    pub vid: u16,
-    /// Product ID
-    pub pid: u16,
-    /// Serial number (arbitrary string)
-    pub serial_number: Option<String>,
-    /// Manufacturer (arbitrary string)
-    pub manufacturer: Option<String>,
-    /// Product name (arbitrary string)
-    pub product: Option<String>,
-    /// Interface (id number for multiplexed devices)
-    pub interface: Option<u8>,
-}
+/// Returns a list of all serial ports on system, ignoring any errors
+///
+/// Unlike `available_ports()`, this never fails. Ports which can't be examined are skipped, and
+/// if the platform's enumeration fails entirely the result is simply missing its ports. Where a
+/// second source of port names is available, its ports are added as well: on Linux with udev,
+/// the ports listed in `/sys/class/tty/`, and on Windows, the ports recorded in the
+/// `HARDWARE\DEVICEMAP\SERIALCOMM` registry key. Ports found by both are only listed once,
+/// with the more detailed information from `available_ports()`.
+///
+/// It is not guaranteed that these ports exist or are available even if they're
+/// returned by this function.
+pub fn available_ports_best_effort() -> Vec<SerialPortInfo> {
+    let mut ports: Vec<SerialPortInfo> = match available_ports_iter() {
+        Ok(iter) => iter.filter_map(|port| port.ok()).collect(),
+        Err(_) => Vec::new(),
+    };
 
-/// The physical type of a `SerialPort`
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub enum SerialPortType {
-    /// The serial port is connected via USB
-    UsbPort(UsbPortInfo),
-    /// The serial port is connected via PCI (permanent port)
-    PciPort,
-    /// The serial port is connected via Bluetooth
-    BluetoothPort,
-    /// It can't be determined how the serial port is connected
-    Unknown,
+    #[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
+    let fallback = crate::posix::sysfs_ports();
+    #[cfg(windows)]
+    let fallback = crate::windows::serialcomm_ports();
+    #[cfg(not(any(
+        all(target_os = "linux", not(target_env = "musl"), feature = "libudev"),
+        windows
+    )))]
+    let fallback = Vec::new();
+    merge_ports(&mut ports, fallback);
+
+    ports
 }
 
-/// A device-independent implementation of serial port information
-#[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct SerialPortInfo {
-    /// The short name of the serial port
-    pub port_name: String,
-    /// The hardware device type that exposes this port
-    pub port_type: SerialPortType,
+/// Appends the ports from `extra` whose names aren't in `ports` yet
+fn merge_ports(ports: &mut Vec<SerialPortInfo>, extra: Vec<SerialPortInfo>) {
+    for port in extra {
+        if !ports.iter().any(|p| p.port_name == port.port_name) {
+            ports.push(port);
+        }
+    }
 }
 
-/// Construct a builder of `SerialPort` objects
+/// Returns a list of all serial ports on system along with their input control signals
 ///
-/// `SerialPort` objects are built using the Builder pattern through the `new` function. The
-/// resultant `SerialPortBuilder` object can be copied, reconfigured, and saved making working with
-/// multiple serial ports a little easier.
+/// Each port is opened briefly to read CTS, DSR, RI and CD, e.g. to show which ports have a
+/// carrier. No settings are changed and nothing is written. On POSIX the port is opened
+/// read-only and non-blocking, although drivers commonly raise DTR and RTS whenever a port is
+/// opened. The signals are `None` for ports that couldn't be opened, e.g. because another
+/// process holds them, or whose signals couldn't be read. The output signals RTS and DTR are
+/// never included.
 ///
-/// To open a new serial port:
-/// ```no_run
-/// serialport::new("/dev/ttyUSB0", 9600).open().expect("Failed to open port");
-/// ```
-pub fn new<'a>(path: impl Into<std::borrow::Cow<'a, str>>, baud_rate: u32) -> SerialPortBuilder {
-    SerialPortBuilder {
-        path: path.into().into_owned(),
-        baud_rate,
-        data_bits: DataBits::Eight,
-        flow_control: FlowControl::None,
-        parity: Parity::None,
-        stop_bits: StopBits::One,
-        timeout: Duration::from_millis(0),
-    }
+/// It is not guaranteed that these ports exist or are available even if they're
+/// returned by this function.
+///
+/// ## Errors
+///
+/// This function returns the same errors as `available_ports()`.
+pub fn available_ports_with_signals() -> Result<Vec<(SerialPortInfo, Option<ModemLines>)>> {
+    Ok(available_ports()?
+        .into_iter()
+        .map(|info| {
+            #[cfg(unix)]
+            let signals = crate::posix::probe_modem_inputs(&info.port_name);
+            #[cfg(windows)]
+            let signals = crate::windows::probe_modem_inputs(&info.port_name);
+            #[cfg(not(any(unix, windows)))]
+            let signals = None;
+            (info, signals)
+        })
+        .collect())
 }
 
-/// Returns a list of all serial ports on system
+/// Returns an iterator over all serial ports on system
+///
+/// Unlike `available_ports()`, ports are examined one at a time as the iterator is advanced, so
+/// callers which only need the first matching port can stop early without probing every device.
+/// An error for one port doesn't prevent the remaining ports from being returned.
 ///
 /// It is not guaranteed that these ports exist or are available even if they're
 /// returned by this function.
-pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+pub fn available_ports_iter() -> Result<impl Iterator<Item = Result<SerialPortInfo>>> {
     #[cfg(unix)]
-    return crate::posix::available_ports();
+    return crate::posix::available_ports_iter();
 
     #[cfg(windows)]
-    return crate::windows::available_ports();
+    return crate::windows::available_ports_iter();
 
     #[cfg(not(any(unix, windows)))]
-    Err(Error::new(
+    Err::<std::iter::Empty<Result<SerialPortInfo>>, _>(Error::new(
         ErrorKind::Unknown,
-        "available_ports() not implemented for platform",
+        "available_ports_iter() not implemented for platform",
     ))
 }
+
+#[test]
+fn test_baud_rate_matches() {
+    assert!(baud_rate_matches(9600, 9600, 2));
+    assert!(baud_rate_matches(115_200, 117_000, 2));
+    assert!(baud_rate_matches(115_200, 113_500, 2));
+    assert!(!baud_rate_matches(115_200, 120_000, 2));
+    assert!(!baud_rate_matches(115_200, 9600, 2));
+    assert!(!baud_rate_matches(9600, 0, 2));
+    assert!(baud_rate_matches(0, 0, 2));
+    assert!(!baud_rate_matches(u32::MAX, u32::MAX - 1, 0));
+}
+
+#[test]
+fn test_break_duration() {
+    assert_eq!(
+        break_duration(13, 19_200).unwrap(),
+        Duration::from_nanos(677_084)
+    );
+    assert_eq!(break_duration(10, 1000).unwrap(), Duration::from_millis(10));
+    assert!(break_duration(u32::MAX, 1).is_ok());
+    assert_eq!(
+        break_duration(0, 9600).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        break_duration(13, 0).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_data_bits_bits() {
+    assert_eq!(DataBits::Five.bits(), 5);
+    assert_eq!(DataBits::Eight.bits(), 8);
+}
+
+#[test]
+fn test_transmission_time() {
+    assert_eq!(frame_bits(DataBits::Eight, Parity::None, StopBits::One), 10);
+    assert_eq!(frame_bits(DataBits::Seven, Parity::Even, StopBits::Two), 11);
+    assert_eq!(
+        transmission_time(96, 10, 9600).unwrap(),
+        Duration::from_millis(100)
+    );
+    assert_eq!(
+        transmission_time(1, 10, 115_200).unwrap(),
+        Duration::from_nanos(86_806)
+    );
+    assert_eq!(
+        transmission_time(0, 10, 9600).unwrap(),
+        Duration::from_secs(0)
+    );
+    assert!(transmission_time(usize::MAX, 12, 1).is_err());
+    assert_eq!(
+        transmission_time(1, 10, 0).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_chipset_capabilities() {
+    let usb = |vid, pid| {
+        SerialPortType::UsbPort(UsbPortInfo {
+            vid,
+            pid,
+            serial_number: None,
+            manufacturer: None,
+            product: None,
+            interface: None,
+        })
+    };
+
+    let capabilities = chipset_capabilities(&SerialPortType::Unknown);
+    assert_eq!(capabilities.data_bits.len(), 4);
+    assert_eq!(capabilities.stop_bits, vec![StopBits::One, StopBits::Two]);
+    assert!(capabilities.flow_control.contains(&FlowControl::Hardware));
+
+    let capabilities = chipset_capabilities(&usb(0x0403, 0x6001));
+    assert_eq!(
+        capabilities.data_bits,
+        vec![DataBits::Seven, DataBits::Eight]
+    );
+
+    let capabilities = chipset_capabilities(&usb(0x1a86, 0x7523));
+    assert_eq!(
+        capabilities.flow_control,
+        vec![FlowControl::None, FlowControl::Software]
+    );
+}
+
+#[test]
+fn test_check_data_bits_supported() {
+    let usb = |vid| {
+        SerialPortType::UsbPort(UsbPortInfo {
+            vid,
+            pid: 0x6001,
+            serial_number: None,
+            manufacturer: None,
+            product: None,
+            interface: None,
+        })
+    };
+    assert!(check_data_bits_supported(&usb(0x0403), DataBits::Seven).is_ok());
+    assert_eq!(
+        check_data_bits_supported(&usb(0x0403), DataBits::Five)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+    assert!(check_data_bits_supported(&usb(0x10c4), DataBits::Five).is_ok());
+    assert!(check_data_bits_supported(&SerialPortType::Unknown, DataBits::Six).is_ok());
+}
+
+#[test]
+fn test_hardware_flow_control_support() {
+    let usb = |vid, pid| {
+        SerialPortType::UsbPort(UsbPortInfo {
+            vid,
+            pid,
+            serial_number: None,
+            manufacturer: None,
+            product: None,
+            interface: None,
+        })
+    };
+    assert_eq!(
+        hardware_flow_control_support(&usb(0x0403, 0x6001)),
+        Some(true)
+    );
+    assert_eq!(
+        hardware_flow_control_support(&usb(0x1a86, 0x7523)),
+        Some(false)
+    );
+    assert_eq!(hardware_flow_control_support(&usb(0x1234, 0x5678)), None);
+    assert_eq!(
+        hardware_flow_control_support(&SerialPortType::PciPort),
+        Some(true)
+    );
+    assert_eq!(
+        hardware_flow_control_support(&SerialPortType::Unknown),
+        None
+    );
+}
+
+#[test]
+fn test_port_index() {
+    assert_eq!(port_index("COM7"), Some(7));
+    assert_eq!(port_index(r"\\.\COM12"), Some(12));
+    assert_eq!(port_index("/dev/ttyUSB0"), Some(0));
+    assert_eq!(port_index("/dev/ttyS3"), Some(3));
+    assert_eq!(port_index("/dev/tty.usbserial"), None);
+    assert_eq!(port_index("/dev/ttyUSB99999999999"), None);
+    assert_eq!(port_index(""), None);
+}
+
+#[test]
+fn test_builder_validate() {
+    assert!(new("/dev/ttyUSB0", 9600).validate().is_ok());
+    assert!(new("", 115_200).validate().is_ok());
+    assert_eq!(
+        new("/dev/ttyUSB0", 0).validate().unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+
+    let five_two = new("COM1", 9600)
+        .data_bits(DataBits::Five)
+        .stop_bits(StopBits::Two);
+    assert_eq!(five_two.validate().is_err(), cfg!(windows));
+}
+
+#[test]
+fn test_io_error_round_trip() {
+    let error = Error::new(ErrorKind::NoDevice, "Device disconnected");
+    let io_error = io::Error::from(error);
+    assert_eq!(io_error.kind(), io::ErrorKind::NotFound);
+    assert_eq!(io_error.to_string(), "Device disconnected");
+
+    let error = Error::from(io_error);
+    assert_eq!(error.kind(), ErrorKind::NoDevice);
+    assert_eq!(error.description, "Device disconnected");
+
+    let error = Error::from(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "Operation timed out",
+    ));
+    assert_eq!(error.kind(), ErrorKind::Io(io::ErrorKind::TimedOut));
+}
+
+#[test]
+fn test_matching_needle() {
+    let needles: &[&[u8]] = &[b"OK\r\n", b"ERROR\r\n", b"\r\n", b"OK"];
+    assert_eq!(matching_needle(b"", needles), None);
+    assert_eq!(matching_needle(b"AT\r", needles), None);
+    assert_eq!(matching_needle(b"AT\r\n", needles), Some(2));
+    assert_eq!(matching_needle(b"AT\r\nOK", needles), Some(3));
+    assert_eq!(matching_needle(b"AT\r\nOK\r\n", needles), Some(0));
+    assert_eq!(matching_needle(b"ERROR\r\n", needles), Some(1));
+}
+
+#[test]
+fn test_describe_port() {
+    let settings = crate::new("/dev/ttyUSB0", 115_200)
+        .parity(Parity::Even)
+        .stop_bits(StopBits::Two)
+        .flow_control(FlowControl::Hardware)
+        .timeout(Duration::from_millis(10));
+    assert_eq!(
+        describe_port(Some("/dev/ttyUSB0".into()), Ok(settings)),
+        "/dev/ttyUSB0: 115200 baud 8E2, hardware flow control, timeout 10ms"
+    );
+
+    let err = Error::new(ErrorKind::NoDevice, "Device disconnected");
+    assert_eq!(
+        describe_port(None, Err(err)),
+        "<unnamed>: settings unavailable (Device disconnected)"
+    );
+}
+
+#[test]
+fn test_serial_port_info_order() {
+    let info = |name: &str, port_type| SerialPortInfo {
+        port_name: name.into(),
+        port_type,
+        driver_name: None,
+        by_id_path: None,
+    };
+    let mut ports = vec![
+        info("/dev/ttyUSB1", SerialPortType::Unknown),
+        info("/dev/ttyS0", SerialPortType::PciPort),
+        info("/dev/ttyACM0", SerialPortType::Unknown),
+        info("/dev/ttyS0", SerialPortType::PciPort),
+    ];
+    let set: BTreeSet<_> = ports.iter().cloned().collect();
+    ports.sort();
+    ports.dedup();
+    assert_eq!(set.into_iter().collect::<Vec<_>>(), ports);
+    assert_eq!(ports[0].port_name, "/dev/ttyACM0");
+    assert_eq!(ports[2].port_name, "/dev/ttyUSB1");
+}
+
+#[test]
+fn test_product_matches() {
+    let info = UsbPortInfo {
+        vid: 0x2341,
+        pid: 0x0043,
+        serial_number: None,
+        manufacturer: Some("Arduino (www.arduino.cc)".into()),
+        product: Some("Arduino Uno".into()),
+        interface: None,
+    };
+    assert!(product_matches(&info, "uno"));
+    assert!(product_matches(&info, "www.arduino"));
+    assert!(!product_matches(&info, "stlink"));
+
+    let anonymous = UsbPortInfo {
+        manufacturer: None,
+        product: None,
+        ..info
+    };
+    assert!(!product_matches(&anonymous, ""));
+}
+
+#[test]
+fn test_port_subsystem() {
+    let usb = |name: &str, driver: Option<&str>| SerialPortInfo {
+        port_name: name.into(),
+        port_type: SerialPortType::UsbPort(UsbPortInfo {
+            vid: 0x0403,
+            pid: 0x6001,
+            serial_number: None,
+            manufacturer: None,
+            product: None,
+            interface: None,
+        }),
+        driver_name: driver.map(String::from),
+        by_id_path: None,
+    };
+    assert_eq!(
+        port_subsystem(&usb("/dev/ttyACM0", Some("cdc_acm"))),
+        Some(PortSubsystem::CdcAcm)
+    );
+    assert_eq!(
+        port_subsystem(&usb("COM3", Some("usbser"))),
+        Some(PortSubsystem::CdcAcm)
+    );
+    assert_eq!(
+        port_subsystem(&usb("/dev/cu.usbmodem14101", None)),
+        Some(PortSubsystem::CdcAcm)
+    );
+    assert_eq!(
+        port_subsystem(&usb("/dev/ttyUSB0", Some("ftdi_sio"))),
+        Some(PortSubsystem::UsbBridge)
+    );
+
+    let other = |name: &str, port_type, driver: Option<&str>| SerialPortInfo {
+        port_name: name.into(),
+        port_type,
+        driver_name: driver.map(String::from),
+        by_id_path: None,
+    };
+    assert_eq!(
+        port_subsystem(&other("/dev/ttyS4", SerialPortType::PciPort, None)),
+        Some(PortSubsystem::Onboard)
+    );
+    assert_eq!(
+        port_subsystem(&other("COM1", SerialPortType::Unknown, Some("Serial"))),
+        Some(PortSubsystem::Onboard)
+    );
+    assert_eq!(
+        port_subsystem(&other("/dev/rfcomm0", SerialPortType::Unknown, None)),
+        Some(PortSubsystem::Bluetooth)
+    );
+    assert_eq!(
+        port_subsystem(&other("COM7", SerialPortType::BluetoothPort, None)),
+        Some(PortSubsystem::Bluetooth)
+    );
+    assert_eq!(
+        port_subsystem(&other("COM9", SerialPortType::Unknown, None)),
+        None
+    );
+}
+
+#[test]
+fn test_write_fits() {
+    let status = QueueStatus {
+        bytes_to_read: 0,
+        bytes_to_write: 4000,
+        rx_capacity: Some(4096),
+        tx_capacity: Some(4096),
+    };
+    assert!(write_fits(&status, FlowControl::None, None, 96).unwrap());
+    assert!(!write_fits(&status, FlowControl::None, None, 97).unwrap());
+    assert!(write_fits(&status, FlowControl::Software, None, 0).unwrap());
+
+    // A driver may report more pending bytes than its buffer size while it drains
+    let overfull = QueueStatus {
+        bytes_to_write: 5000,
+        ..status
+    };
+    assert!(write_fits(&overfull, FlowControl::None, None, 0).unwrap());
+    assert!(!write_fits(&overfull, FlowControl::None, None, 1).unwrap());
+
+    // Hardware flow control holds everything back while CTS is deasserted
+    let cts = Some(ModemLines::CTS);
+    assert!(write_fits(&status, FlowControl::Hardware, cts, 96).unwrap());
+    assert!(!write_fits(&status, FlowControl::Hardware, cts, 97).unwrap());
+    let no_cts = Some(ModemLines::RTS | ModemLines::DSR);
+    assert!(!write_fits(&status, FlowControl::Hardware, no_cts, 1).unwrap());
+    assert!(write_fits(&status, FlowControl::None, no_cts, 1).unwrap());
+
+    let unknown = QueueStatus {
+        tx_capacity: None,
+        ..status
+    };
+    let err = write_fits(&unknown, FlowControl::Hardware, cts, 1).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unknown);
+}
+
+#[test]
+fn test_hw_flow_status() {
+    let status = hw_flow_status(FlowControl::Hardware, Some(ModemLines::RTS), true);
+    assert_eq!(status.clear_to_send, Some(false));
+    assert_eq!(status.rts, Some(true));
+    assert!(status.write_blocked);
+
+    let status = hw_flow_status(FlowControl::Hardware, Some(ModemLines::CTS), false);
+    assert_eq!(status.clear_to_send, Some(true));
+    assert_eq!(status.rts, None);
+    assert!(!status.write_blocked);
+
+    // Without hardware flow control a deasserted CTS doesn't hold anything back
+    let status = hw_flow_status(FlowControl::None, Some(ModemLines::empty()), true);
+    assert_eq!(status.rts, Some(false));
+    assert!(!status.write_blocked);
+
+    let status = hw_flow_status(FlowControl::Hardware, None, true);
+    assert_eq!(status.clear_to_send, None);
+    assert_eq!(status.rts, None);
+    assert!(!status.write_blocked);
+}
+
+#[test]
+fn test_retry_settings_mismatch() {
+    fn mismatch<T>() -> Result<std::result::Result<T, Vec<String>>> {
+        Ok(Err(vec!["parity Even (requested None)".to_string()]))
+    }
+
+    let mut calls = 0;
+    let result: Result<()> = retry_settings_mismatch(3, || {
+        calls += 1;
+        mismatch()
+    });
+    assert_eq!(calls, 3);
+    let err = result.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.description.starts_with("Settings were not applied"));
+
+    let mut calls = 0;
+    let result = retry_settings_mismatch(3, || {
+        calls += 1;
+        if calls == 1 {
+            mismatch()
+        } else {
+            Ok(Ok(calls))
+        }
+    });
+    assert_eq!(result.unwrap(), 2);
+
+    // Other errors aren't retried, even when they are `InvalidInput` as well
+    let mut calls = 0;
+    let result: Result<()> = retry_settings_mismatch(3, || {
+        calls += 1;
+        Err(Error::new(ErrorKind::InvalidInput, "Unsupported data bits"))
+    });
+    assert_eq!(calls, 1);
+    assert_eq!(result.unwrap_err().description, "Unsupported data bits");
+}
+
+#[test]
+fn test_check_modem_line_masks() {
+    assert!(check_modem_line_masks(ModemLines::RTS, ModemLines::DTR).is_ok());
+    assert!(check_modem_line_masks(ModemLines::empty(), ModemLines::empty()).is_ok());
+    assert_eq!(
+        check_modem_line_masks(ModemLines::CTS, ModemLines::empty())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        check_modem_line_masks(ModemLines::RTS | ModemLines::DTR, ModemLines::DTR)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_line_counters_since() {
+    let baseline = LineCounters {
+        rx: u32::MAX - 1,
+        tx: 10,
+        overrun: 3,
+        ..LineCounters::default()
+    };
+    let current = LineCounters {
+        rx: 2,
+        tx: 25,
+        overrun: 3,
+        parity: 1,
+        ..LineCounters::default()
+    };
+    let delta = current.since(&baseline);
+    assert_eq!(delta.rx, 4);
+    assert_eq!(delta.tx, 15);
+    assert_eq!(delta.overrun, 0);
+    assert_eq!(delta.parity, 1);
+}
+
+#[test]
+fn test_builder_baud_rate_tolerance() {
+    // 10000 baud is 4% off 9600
+    let actual = new("", 10_000);
+    assert_eq!(new("", 9600).differences(&actual).len(), 1);
+    assert!(new("", 9600)
+        .baud_rate_tolerance(5)
+        .differences(&actual)
+        .is_empty());
+}
+
+#[test]
+fn test_builder_default_timeouts() {
+    assert_eq!(new("", 9600).timeout, DEFAULT_TIMEOUT);
+    assert_eq!(
+        new("", 9600).with_reasonable_defaults().timeout,
+        REASONABLE_TIMEOUT
+    );
+}
+
+#[test]
+fn test_modem_status_changes() {
+    let before = ModemStatus {
+        clear_to_send: true,
+        carrier_detect: true,
+        ..ModemStatus::default()
+    };
+    let after = ModemStatus {
+        clear_to_send: true,
+        ring_indicator: true,
+        ..ModemStatus::default()
+    };
+    assert_eq!(
+        before.changes(&after),
+        ModemStatus {
+            ring_indicator: true,
+            carrier_detect: true,
+            ..ModemStatus::default()
+        }
+    );
+}
+
+#[test]
+fn test_translate_line_endings() {
+    let text = "a\nb\r\nc\rd\n";
+    assert_eq!(translate_line_endings(text, LineEnding::None), text);
+    assert_eq!(translate_line_endings(text, LineEnding::Lf), "a\nb\nc\rd\n");
+    assert_eq!(translate_line_endings(text, LineEnding::Cr), "a\rb\rc\rd\r");
+    assert_eq!(
+        translate_line_endings(text, LineEnding::CrLf),
+        "a\r\nb\r\nc\rd\r\n"
+    );
+    assert_eq!(translate_line_endings("abc", LineEnding::CrLf), "abc");
+}
+
+#[test]
+fn test_usb_identity_matches() {
+    let info = UsbPortInfo {
+        vid: 0x0403,
+        pid: 0x6001,
+        serial_number: Some("A1".to_string()),
+        manufacturer: None,
+        product: None,
+        interface: Some(1),
+    };
+    let identity = UsbIdentity::from(&info);
+    assert!(identity.matches(&info));
+    let any_serial = UsbIdentity {
+        serial_number: None,
+        interface: None,
+        ..identity.clone()
+    };
+    assert!(any_serial.matches(&info));
+    let other_serial = UsbIdentity {
+        serial_number: Some("B2".to_string()),
+        ..identity.clone()
+    };
+    assert!(!other_serial.matches(&info));
+    let other_pid = UsbIdentity {
+        pid: 0x6015,
+        ..identity
+    };
+    assert!(!other_pid.matches(&info));
+}
+
+#[test]
+fn test_merge_ports() {
+    let port = |name: &str, port_type| SerialPortInfo {
+        port_name: name.to_string(),
+        port_type,
+        driver_name: None,
+        by_id_path: None,
+    };
+    let mut ports = vec![port("/dev/ttyUSB0", SerialPortType::PciPort)];
+    merge_ports(
+        &mut ports,
+        vec![
+            port("/dev/ttyUSB0", SerialPortType::Unknown),
+            port("/dev/ttyACM0", SerialPortType::Unknown),
+        ],
+    );
+    assert_eq!(ports.len(), 2);
+    assert_eq!(ports[0].port_type, SerialPortType::PciPort);
+    assert_eq!(ports[1].port_name, "/dev/ttyACM0");
+}
+
+#[test]
+fn test_single_port() {
+    let port = |name: &str| SerialPortInfo {
+        port_name: name.to_string(),
+        port_type: SerialPortType::Unknown,
+        driver_name: None,
+        by_id_path: None,
+    };
+    assert_eq!(single_port(vec![]), None);
+    assert_eq!(
+        single_port(vec![port("/dev/ttyUSB0")]).map(|p| p.port_name),
+        Some("/dev/ttyUSB0".to_string())
+    );
+    assert_eq!(
+        single_port(vec![port("/dev/ttyUSB0"), port("/dev/ttyACM0")]),
+        None
+    );
+    assert_eq!(
+        single_port(vec![
+            port("/dev/tty.usbserial-1"),
+            port("/dev/cu.usbserial-1")
+        ])
+        .map(|p| p.port_name),
+        Some("/dev/cu.usbserial-1".to_string())
+    );
+    assert_eq!(
+        single_port(vec![
+            port("/dev/cu.usbserial-1"),
+            port("/dev/tty.usbserial-2")
+        ]),
+        None
+    );
+}
+
+#[test]
+fn test_group_usb_ports() {
+    let port = |name: &str, pid, serial: Option<&str>, interface| SerialPortInfo {
+        port_name: name.to_string(),
+        port_type: SerialPortType::UsbPort(UsbPortInfo {
+            vid: 0x0403,
+            pid,
+            serial_number: serial.map(String::from),
+            manufacturer: None,
+            product: None,
+            interface,
+        }),
+        driver_name: None,
+        by_id_path: None,
+    };
+    let onboard = SerialPortInfo {
+        port_type: SerialPortType::PciPort,
+        ..port("/dev/ttyS0", 0, None, None)
+    };
+    let groups = group_usb_ports(vec![
+        port("/dev/ttyUSB1", 0x6010, Some("A1"), Some(1)),
+        onboard,
+        port("/dev/ttyUSB2", 0x6001, Some("B2"), Some(0)),
+        port("/dev/ttyUSB0", 0x6010, Some("A1"), Some(0)),
+        port("/dev/ttyUSB3", 0x6010, Some("C3"), Some(0)),
+    ]);
+    let names: Vec<Vec<&str>> = groups
+        .iter()
+        .map(|g| g.ports.iter().map(|p| &p.port_name[..]).collect())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            vec!["/dev/ttyUSB0", "/dev/ttyUSB1"],
+            vec!["/dev/ttyUSB2"],
+            vec!["/dev/ttyUSB3"],
+        ]
+    );
+    assert_eq!(groups[0].serial_number.as_deref(), Some("A1"));
+    assert_eq!(groups[1].pid, 0x6001);
+}
+
+#[test]
+fn test_parse_settings_spec() {
+    assert_eq!(
+        parse_settings_spec("115200,8,N,1").unwrap(),
+        (115_200, DataBits::Eight, Parity::None, StopBits::One)
+    );
+    assert_eq!(
+        parse_settings_spec(" 9600, 7, e, 2 ").unwrap(),
+        (9600, DataBits::Seven, Parity::Even, StopBits::Two)
+    );
+    for spec in &[
+        "",
+        "9600",
+        "9600,8,N",
+        "9600,8,N,1,0",
+        "fast,8,N,1",
+        "9600,9,N,1",
+    ] {
+        let err = parse_settings_spec(spec).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+    assert!(parse_settings_spec("9600,8,M,1").is_err());
+    assert!(parse_settings_spec("9600,8,N,1.5").is_err());
+}
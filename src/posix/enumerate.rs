@@ -232,11 +232,48 @@ fn port_type(service: io_object_t) -> SerialPortType {
     }
 }
 
+#[cfg(target_os = "linux")]
+/// Finds a symlink in `/dev/serial/by-id/` or, failing that, `/dev/serial/by-path/` that points at
+/// the device node of `port_name`. These links are created by udev and, unlike the `ttyUSBn`
+/// numbering, stay the same across reboots and replugging.
+fn by_id_path(port_name: &str) -> Option<String> {
+    let node = std::path::Path::new(port_name).file_name()?;
+    ["/dev/serial/by-id", "/dev/serial/by-path"]
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .find(|entry| {
+            entry
+                .path()
+                .read_link()
+                .map_or(false, |target| target.file_name() == Some(node))
+        })
+        .map(|entry| entry.path().to_string_lossy().into_owned())
+}
+
 cfg_if! {
     if #[cfg(any(target_os = "ios", target_os = "macos"))] {
         /// Scans the system for serial ports and returns a list of them.
         /// The `SerialPortInfo` struct contains the name of the port which can be used for opening it.
         pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+            scan_ports(None)
+        }
+
+        /// Returns the type `available_ports()` reports for the port at `path`.
+        ///
+        /// IOKit is still searched for all serial services, but only the one for `path` is
+        /// inspected.
+        pub(crate) fn port_type_of(path: &str) -> Result<SerialPortType> {
+            let path = std::fs::canonicalize(path)
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_else(|_| path.to_string());
+            Ok(scan_ports(Some(&path))?
+                .pop()
+                .map_or(SerialPortType::Unknown, |info| info.port_type))
+        }
+
+        /// Lists the serial ports found by IOKit, or only the one named `wanted`.
+        fn scan_ports(wanted: Option<&str>) -> Result<Vec<SerialPortInfo>> {
             use mach2::kern_return::KERN_SUCCESS;
             use mach2::port::{mach_port_t, MACH_PORT_NULL};
 
@@ -340,10 +377,14 @@ cfg_if! {
                                     kCFStringEncodingUTF8,
                                 );
                                 let path = CStr::from_ptr(buf.as_ptr()).to_string_lossy();
-                                vec.push(SerialPortInfo {
-                                    port_name: path.to_string(),
-                                    port_type: port_type(modem_service),
-                                });
+                                if wanted.map_or(true, |wanted| wanted == path) {
+                                    vec.push(SerialPortInfo {
+                                        port_name: path.to_string(),
+                                        port_type: port_type(modem_service),
+                                        driver_name: None,
+                                        by_id_path: None,
+                                    });
+                                }
                             } else {
                                 return Err(Error::new(
                                     ErrorKind::Unknown,
@@ -361,99 +402,215 @@ cfg_if! {
             }
             Ok(vec)
         }
+
+        /// Scans the system for serial ports and returns an iterator over them.
+        ///
+        /// IOKit enumeration is performed up front on macOS, so the whole list of ports is
+        /// retrieved before the first one is returned.
+        pub fn available_ports_iter() -> Result<impl Iterator<Item = Result<SerialPortInfo>>> {
+            Ok(available_ports()?.into_iter().map(Ok))
+        }
     } else if #[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))] {
         /// Scans the system for serial ports and returns a list of them.
         /// The `SerialPortInfo` struct contains the name of the port
         /// which can be used for opening it.
         pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
-            let mut vec = Vec::new();
+            available_ports_iter()?.collect()
+        }
+
+        /// Scans the system for serial ports and returns an iterator over them.
+        ///
+        /// The list of tty devices is retrieved from udev up front, but each device is only
+        /// inspected (and possibly probed by opening it) once the iterator reaches it.
+        pub fn available_ports_iter() -> Result<impl Iterator<Item = Result<SerialPortInfo>>> {
+            let devices = tty_devices(false)?;
+            Ok(devices.into_iter().filter_map(|d| port_info(&d).map(Ok)))
+        }
+
+        /// Scans the system for USB serial ports and returns a list of them.
+        ///
+        /// Only USB devices are requested from udev, so other ports are never probed.
+        pub fn available_usb_ports() -> Result<Vec<SerialPortInfo>> {
+            let devices = tty_devices(true)?;
+            Ok(devices
+                .iter()
+                .filter_map(port_info)
+                .filter(|info| matches!(info.port_type, SerialPortType::UsbPort(_)))
+                .collect())
+        }
+
+        /// Returns the type `available_ports()` reports for the tty device at `path`.
+        ///
+        /// Only this device is looked up in udev, and it's not probed.
+        pub(crate) fn port_type_of(path: &str) -> Result<SerialPortType> {
+            let devnode = std::fs::canonicalize(path)?;
+            let name = match devnode.file_name() {
+                Some(name) => name,
+                None => return Ok(SerialPortType::Unknown),
+            };
+            let context = libudev::Context::new()?;
+            let syspath = std::path::Path::new("/sys/class/tty/").join(name);
+            let device = libudev::Device::from_syspath(&context, &syspath)?;
+            // Device nodes outside of `/dev/`, like those of pseudo terminals in `/dev/pts/`, may
+            // share their name with an unrelated tty
+            if device.devnode() != Some(devnode.as_path()) {
+                return Ok(SerialPortType::Unknown);
+            }
+            port_type(&device)
+        }
+
+        /// Lists the serial ports found in `/sys/class/tty/`, without asking udev.
+        ///
+        /// This is a fallback for when udev is unavailable or fails on some devices, so it never
+        /// fails itself. Nothing is probed: the placeholder ports of the 8250 driver are skipped
+        /// and the port types are unknown.
+        pub(crate) fn sysfs_ports() -> Vec<SerialPortInfo> {
+            let entries = match std::fs::read_dir("/sys/class/tty/") {
+                Ok(entries) => entries,
+                Err(_) => return Vec::new(),
+            };
+            entries
+                .filter_map(|entry| {
+                    let entry = entry.ok()?;
+                    let driver = entry.path().join("device/driver").read_link().ok()?;
+                    let driver_name = driver.file_name()?.to_string_lossy().into_owned();
+                    if driver_name == "serial8250" {
+                        return None;
+                    }
+                    let port_name = std::path::Path::new("/dev")
+                        .join(entry.file_name())
+                        .to_string_lossy()
+                        .into_owned();
+                    Some(SerialPortInfo {
+                        by_id_path: by_id_path(&port_name),
+                        port_name,
+                        port_type: SerialPortType::Unknown,
+                        driver_name: Some(driver_name),
+                    })
+                })
+                .collect()
+        }
+
+        /// Retrieves the tty devices known to udev, optionally only those on a USB bus.
+        fn tty_devices(usb_only: bool) -> Result<Vec<libudev::Device>> {
+            let mut devices = Vec::new();
             if let Ok(context) = libudev::Context::new() {
                 let mut enumerator = libudev::Enumerator::new(&context)?;
                 enumerator.match_subsystem("tty")?;
-                let devices = enumerator.scan_devices()?;
-                for d in devices {
-                    if let Some(p) = d.parent() {
-                        if let Some(devnode) = d.devnode() {
-                            if let Some(path) = devnode.to_str() {
-                                if let Some(driver) = p.driver() {
-                                    if driver == "serial8250" && crate::new(path, 9600).open().is_err() {
-                                        continue;
-                                    }
-                                }
-                                // Stop bubbling up port_type errors here so problematic ports are just
-                                // skipped instead of causing no ports to be returned.
-                                if let Ok(pt) = port_type(&d) {
-                                    vec.push(SerialPortInfo {
-                                        port_name: String::from(path),
-                                        port_type: pt,
-                                    });
-                                }
-                            }
-                        }
-                    }
+                if usb_only {
+                    enumerator.match_property("ID_BUS", "usb")?;
                 }
+                devices.extend(enumerator.scan_devices()?);
             }
-            Ok(vec)
+            Ok(devices)
+        }
+
+        /// Returns the port information for a tty device, or `None` if it's not a serial port.
+        fn port_info(d: &libudev::Device) -> Option<SerialPortInfo> {
+            let p = d.parent()?;
+            let path = d.devnode()?.to_str()?;
+            if let Some(driver) = p.driver() {
+                if driver == "serial8250" && crate::new(path, 9600).open().is_err() {
+                    return None;
+                }
+            }
+            // Stop bubbling up port_type errors here so problematic ports are just
+            // skipped instead of causing no ports to be returned.
+            port_type(d).ok().map(|pt| SerialPortInfo {
+                port_name: String::from(path),
+                port_type: pt,
+                driver_name: p.driver().and_then(OsStr::to_str).map(String::from),
+                by_id_path: by_id_path(path),
+            })
         }
     } else if #[cfg(target_os = "linux")] {
-        use std::fs::File;
-        use std::io::Read;
+        use std::fs::{DirEntry, File};
+        use std::io::{self, Read};
         use std::path::Path;
 
         /// Enumerating serial ports on non-Linux POSIX platforms is disabled by disabled the "libudev"
         /// default feature.
         pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
-            let mut vec = Vec::new();
+            available_ports_iter()?.collect()
+        }
+
+        /// Scans `/sys/class/tty/` for serial ports and returns an iterator over them.
+        pub fn available_ports_iter() -> Result<impl Iterator<Item = Result<SerialPortInfo>>> {
             let sys_path = Path::new("/sys/class/tty/");
-            let mut s;
-            for path in sys_path.read_dir().expect("/sys/class/tty/ doesn't exist on this system") {
-                let raw_path = path?.path().clone();
-                let mut path = raw_path.clone();
-
-                path.push("device");
-                if !path.is_dir() {
-                    continue;
-                }
+            let entries = sys_path.read_dir()?;
+            Ok(entries.filter_map(|entry| port_info(entry).transpose()))
+        }
 
-                path.push("driver_override");
-                if path.is_file() {
-                    s = String::new();
-                    File::open(path)?.read_to_string(&mut s)?;
-                    if &s == "(null)\n" {
-                        continue;
-                    }
-                }
+        /// Returns the port information for an entry of `/sys/class/tty/`, or `None` if it's not
+        /// a serial port.
+        fn port_info(entry: io::Result<DirEntry>) -> Result<Option<SerialPortInfo>> {
+            let raw_path = entry?.path();
+            let mut path = raw_path.clone();
 
-                vec.push(SerialPortInfo {
-                    port_name: raw_path.to_string_lossy().to_string(),
-                    port_type: SerialPortType::Unknown,
-                });
+            path.push("device");
+            if !path.is_dir() {
+                return Ok(None);
             }
-            Ok(vec)
+
+            path.push("driver_override");
+            if path.is_file() {
+                let mut s = String::new();
+                File::open(path)?.read_to_string(&mut s)?;
+                if &s == "(null)\n" {
+                    return Ok(None);
+                }
+            }
+
+            // The driver is a symlink to the module's directory, e.g. `.../drivers/ftdi_sio`
+            let driver_name = raw_path
+                .join("device/driver")
+                .read_link()
+                .ok()
+                .and_then(|driver| driver.file_name().map(|name| name.to_string_lossy().into_owned()));
+
+            let port_name = raw_path.to_string_lossy().to_string();
+            Ok(Some(SerialPortInfo {
+                by_id_path: by_id_path(&port_name),
+                port_name,
+                port_type: SerialPortType::Unknown,
+                driver_name,
+            }))
         }
     } else if #[cfg(target_os = "freebsd")] {
+        use std::fs::DirEntry;
+        use std::io;
         use std::path::Path;
 
         /// Scans the system for serial ports and returns a list of them.
         /// The `SerialPortInfo` struct contains the name of the port
         /// which can be used for opening it.
         pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
-            let mut vec = Vec::new();
+            available_ports_iter()?.collect()
+        }
+
+        /// Scans `/dev/` for serial ports and returns an iterator over them.
+        pub fn available_ports_iter() -> Result<impl Iterator<Item = Result<SerialPortInfo>>> {
             let dev_path = Path::new("/dev/");
-            for path in dev_path.read_dir()? {
-                let path = path?;
-                let filename = path.file_name();
-                let filename_string = filename.to_string_lossy();
-                if filename_string.starts_with("cuaU") || filename_string.starts_with("cuau") || filename_string.starts_with("cuad") {
-                    if !filename_string.ends_with(".init") && !filename_string.ends_with(".lock") {
-                        vec.push(SerialPortInfo {
-                            port_name: path.path().to_string_lossy().to_string(),
-                            port_type: SerialPortType::Unknown,
-                        });
-                    }
+            Ok(dev_path.read_dir()?.filter_map(|entry| port_info(entry).transpose()))
+        }
+
+        /// Returns the port information for an entry of `/dev/`, or `None` if it's not a serial
+        /// port.
+        fn port_info(entry: io::Result<DirEntry>) -> Result<Option<SerialPortInfo>> {
+            let path = entry?;
+            let filename = path.file_name();
+            let filename_string = filename.to_string_lossy();
+            if filename_string.starts_with("cuaU") || filename_string.starts_with("cuau") || filename_string.starts_with("cuad") {
+                if !filename_string.ends_with(".init") && !filename_string.ends_with(".lock") {
+                    return Ok(Some(SerialPortInfo {
+                        port_name: path.path().to_string_lossy().to_string(),
+                        port_type: SerialPortType::Unknown,
+                        driver_name: None,
+                        by_id_path: None,
+                    }));
                 }
             }
-            Ok(vec)
+            Ok(None)
         }
     } else {
         /// Enumerating serial ports on this platform is not supported
@@ -463,5 +620,36 @@ cfg_if! {
                 "Not implemented for this OS",
             ))
         }
+
+        /// Enumerating serial ports on this platform is not supported
+        pub fn available_ports_iter() -> Result<std::iter::Empty<Result<SerialPortInfo>>> {
+            Err(Error::new(
+                ErrorKind::Unknown,
+                "Not implemented for this OS",
+            ))
+        }
     }
 }
+
+/// Returns the type `available_ports()` reports for the port at `path`.
+///
+/// Ports are never reported with a known type on this platform.
+#[cfg(not(any(
+    target_os = "ios",
+    all(target_os = "linux", not(target_env = "musl"), feature = "libudev"),
+    target_os = "macos"
+)))]
+pub(crate) fn port_type_of(_path: &str) -> Result<crate::SerialPortType> {
+    Ok(crate::SerialPortType::Unknown)
+}
+
+/// Scans the system for USB serial ports and returns a list of them.
+#[cfg(not(all(target_os = "linux", not(target_env = "musl"), feature = "libudev")))]
+pub fn available_usb_ports() -> Result<Vec<SerialPortInfo>> {
+    available_ports_iter()?
+        .filter(|port| match port {
+            Ok(info) => matches!(info.port_type, crate::SerialPortType::UsbPort(_)),
+            Err(_) => true,
+        })
+        .collect()
+}
@@ -3,12 +3,17 @@ use std::os::unix::io::RawFd;
 use bitflags::bitflags;
 use nix::libc;
 
+#[cfg(target_os = "linux")]
+use crate::LineCounters;
 use crate::Result;
 
 // These are wrapped in a module because they're `pub` by default
 mod raw {
     use nix::libc;
-    use nix::{ioctl_none_bad, ioctl_read, ioctl_read_bad, ioctl_write_ptr, ioctl_write_ptr_bad};
+    use nix::{
+        ioctl_none_bad, ioctl_read, ioctl_read_bad, ioctl_write_int_bad, ioctl_write_ptr,
+        ioctl_write_ptr_bad,
+    };
 
     ioctl_none_bad!(tiocexcl, libc::TIOCEXCL);
     ioctl_none_bad!(tiocnxcl, libc::TIOCNXCL);
@@ -44,6 +49,17 @@ mod raw {
     ))]
     ioctl_read!(tiocoutq, b't', 115, libc::c_int);
 
+    #[cfg(target_os = "linux")]
+    ioctl_read_bad!(tiocgicount, libc::TIOCGICOUNT, super::SerialICounter);
+
+    #[cfg(target_os = "linux")]
+    ioctl_read_bad!(tiocgserial, libc::TIOCGSERIAL, super::SerialStructRaw);
+    #[cfg(target_os = "linux")]
+    ioctl_write_ptr_bad!(tiocsserial, libc::TIOCSSERIAL, super::SerialStructRaw);
+
+    #[cfg(target_os = "linux")]
+    ioctl_write_int_bad!(tiocmiwait, libc::TIOCMIWAIT);
+
     ioctl_write_ptr_bad!(tiocmbic, libc::TIOCMBIC, libc::c_int);
     ioctl_write_ptr_bad!(tiocmbis, libc::TIOCMBIS, libc::c_int);
     ioctl_read!(
@@ -104,6 +120,63 @@ bitflags! {
     }
 }
 
+/// Mirror of the kernel's `struct serial_icounter_struct`, which `libc` doesn't provide
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Default)]
+struct SerialICounter {
+    cts: libc::c_int,
+    dsr: libc::c_int,
+    rng: libc::c_int,
+    dcd: libc::c_int,
+    rx: libc::c_int,
+    tx: libc::c_int,
+    frame: libc::c_int,
+    overrun: libc::c_int,
+    parity: libc::c_int,
+    brk: libc::c_int,
+    buf_overrun: libc::c_int,
+    reserved: [libc::c_int; 9],
+}
+
+/// Mirror of the kernel's `struct serial_struct`, which `libc` doesn't provide
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SerialStructRaw {
+    pub type_: libc::c_int,
+    pub line: libc::c_int,
+    pub port: libc::c_uint,
+    pub irq: libc::c_int,
+    pub flags: libc::c_int,
+    pub xmit_fifo_size: libc::c_int,
+    pub custom_divisor: libc::c_int,
+    pub baud_base: libc::c_int,
+    pub close_delay: libc::c_ushort,
+    pub io_type: libc::c_char,
+    pub reserved_char: [libc::c_char; 1],
+    pub hub6: libc::c_int,
+    pub closing_wait: libc::c_ushort,
+    pub closing_wait2: libc::c_ushort,
+    pub iomem_base: *mut libc::c_uchar,
+    pub iomem_reg_shift: libc::c_ushort,
+    pub port_high: libc::c_uint,
+    pub iomap_base: libc::c_ulong,
+}
+
+/// Use `custom_divisor` instead of the requested baud rate when it is set to 38400
+#[cfg(target_os = "linux")]
+pub const ASYNC_SPD_CUST: libc::c_int = 0x0030;
+/// All flags that alter the baud rate used for 38400
+#[cfg(target_os = "linux")]
+pub const ASYNC_SPD_MASK: libc::c_int = 0x1030;
+/// `closing_wait` value to wait for output to drain without a time limit
+#[cfg(target_os = "linux")]
+pub const ASYNC_CLOSING_WAIT_INF: libc::c_ushort = 0;
+/// `closing_wait` value to not wait for output to drain at all
+#[cfg(target_os = "linux")]
+pub const ASYNC_CLOSING_WAIT_NONE: libc::c_ushort = 65535;
+
 pub fn tiocexcl(fd: RawFd) -> Result<()> {
     unsafe { raw::tiocexcl(fd) }
         .map(|_| ())
@@ -149,6 +222,45 @@ pub fn tiocoutq(fd: RawFd) -> Result<u32> {
         .map_err(|e| e.into())
 }
 
+#[cfg(target_os = "linux")]
+pub fn tiocgicount(fd: RawFd) -> Result<LineCounters> {
+    let mut counter = SerialICounter::default();
+    unsafe { raw::tiocgicount(fd, &mut counter) }
+        .map(|_| LineCounters {
+            rx: counter.rx as u32,
+            tx: counter.tx as u32,
+            frame: counter.frame as u32,
+            overrun: counter.overrun as u32,
+            parity: counter.parity as u32,
+            brk: counter.brk as u32,
+            buf_overrun: counter.buf_overrun as u32,
+        })
+        .map_err(|e| e.into())
+}
+
+#[cfg(target_os = "linux")]
+pub fn tiocgserial(fd: RawFd) -> Result<SerialStructRaw> {
+    let mut serial = std::mem::MaybeUninit::zeroed();
+    match unsafe { raw::tiocgserial(fd, serial.as_mut_ptr()) } {
+        Ok(_) => unsafe { Ok(serial.assume_init()) },
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn tiocsserial(fd: RawFd, serial: &SerialStructRaw) -> Result<()> {
+    unsafe { raw::tiocsserial(fd, serial) }
+        .map(|_| ())
+        .map_err(|e| e.into())
+}
+
+#[cfg(target_os = "linux")]
+pub fn tiocmiwait(fd: RawFd, lines: SerialLines) -> Result<()> {
+    unsafe { raw::tiocmiwait(fd, lines.bits()) }
+        .map(|_| ())
+        .map_err(|e| e.into())
+}
+
 pub fn tiocmbic(fd: RawFd, status: SerialLines) -> Result<()> {
     let bits = status.bits() as libc::c_int;
     unsafe { raw::tiocmbic(fd, &bits) }
@@ -1,4 +1,5 @@
 pub use self::enumerate::*;
+pub(crate) use self::termios::{check_baud_rate, RawFlowControl};
 pub use self::tty::*;
 
 mod enumerate;
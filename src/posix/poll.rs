@@ -54,8 +54,15 @@ fn wait_fd(fd: RawFd, events: PollFlags, timeout: Duration) -> io::Result<()> {
     // Check the result of ppoll() by looking at the revents field
     match fd.revents() {
         Some(e) if e == events => return Ok(()),
-        // If there was a hangout or invalid request
-        Some(e) if e.contains(PollFlags::POLLHUP) || e.contains(PollFlags::POLLNVAL) => {
+        // A hangup means the device went away, e.g. a USB adapter was unplugged
+        Some(e) if e.contains(PollFlags::POLLHUP) => {
+            return Err(io::Error::from(crate::Error::new(
+                crate::ErrorKind::NoDevice,
+                "Device disconnected",
+            )));
+        }
+        // If there was an invalid request
+        Some(e) if e.contains(PollFlags::POLLNVAL) => {
             return Err(io::Error::new(io::ErrorKind::BrokenPipe, EPIPE.desc()));
         }
         Some(_) | None => (),
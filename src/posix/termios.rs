@@ -1,10 +1,11 @@
 // A set of helper functions for working with the `termios` and `termios2` structs
 use cfg_if::cfg_if;
 
-use crate::{DataBits, FlowControl, Parity, Result, StopBits};
+use crate::{DataBits, Error, ErrorKind, FlowControl, OverflowPolicy, Parity, Result, StopBits};
 use nix::libc;
 
 use std::os::unix::prelude::*;
+use std::time::Duration;
 
 cfg_if! {
     if #[cfg(any(
@@ -145,6 +146,228 @@ pub(crate) fn set_termios(fd: RawFd, termios: &Termios) -> Result<()> {
     crate::posix::ioctl::tcsets2(fd, termios)
 }
 
+pub(crate) fn get_parity(termios: &Termios) -> Parity {
+    if termios.c_cflag & libc::PARENB == libc::PARENB {
+        if termios.c_cflag & libc::PARODD == libc::PARODD {
+            Parity::Odd
+        } else {
+            Parity::Even
+        }
+    } else {
+        Parity::None
+    }
+}
+
+pub(crate) fn get_flow_control(termios: &Termios) -> FlowControl {
+    if termios.c_cflag & libc::CRTSCTS == libc::CRTSCTS {
+        FlowControl::Hardware
+    } else if termios.c_iflag & libc::IXON != 0 {
+        // `IXOFF` may be cleared by `OverflowPolicy::DropIncoming`
+        FlowControl::Software
+    } else {
+        FlowControl::None
+    }
+}
+
+pub(crate) fn get_data_bits(termios: &Termios) -> Result<DataBits> {
+    match termios.c_cflag & libc::CSIZE {
+        libc::CS8 => Ok(DataBits::Eight),
+        libc::CS7 => Ok(DataBits::Seven),
+        libc::CS6 => Ok(DataBits::Six),
+        libc::CS5 => Ok(DataBits::Five),
+        _ => Err(Error::new(
+            ErrorKind::Unknown,
+            "Invalid data bits setting encountered",
+        )),
+    }
+}
+
+pub(crate) fn get_stop_bits(termios: &Termios) -> StopBits {
+    if termios.c_cflag & libc::CSTOPB == libc::CSTOPB {
+        StopBits::Two
+    } else {
+        StopBits::One
+    }
+}
+
+#[cfg(any(
+    target_os = "android",
+    all(
+        target_os = "linux",
+        not(any(
+            target_env = "musl",
+            target_arch = "powerpc",
+            target_arch = "powerpc64"
+        ))
+    )
+))]
+pub(crate) fn get_baud_rate(termios: &Termios) -> u32 {
+    assert!(termios.c_ospeed == termios.c_ispeed);
+
+    termios.c_ospeed
+}
+
+// BSDs use the baud rate as the constant value so there's no translation necessary
+#[cfg(any(
+    target_os = "dragonflybsd",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub(crate) fn get_baud_rate(termios: &Termios) -> u32 {
+    let ospeed = unsafe { libc::cfgetospeed(termios) };
+    let ispeed = unsafe { libc::cfgetispeed(termios) };
+
+    assert!(ospeed == ispeed);
+
+    ospeed as u32
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_env = "musl",
+        target_arch = "powerpc",
+        target_arch = "powerpc64"
+    )
+))]
+pub(crate) fn get_baud_rate(termios: &Termios) -> u32 {
+    use self::libc::{
+        B1000000, B1152000, B1500000, B2000000, B2500000, B3000000, B3500000, B4000000, B460800,
+        B500000, B576000, B921600,
+    };
+    use self::libc::{
+        B110, B115200, B1200, B134, B150, B1800, B19200, B200, B230400, B2400, B300, B38400, B4800,
+        B50, B57600, B600, B75, B9600,
+    };
+
+    let ospeed = unsafe { libc::cfgetospeed(termios) };
+    let ispeed = unsafe { libc::cfgetispeed(termios) };
+
+    assert!(ospeed == ispeed);
+
+    match ospeed {
+        B50 => 50,
+        B75 => 75,
+        B110 => 110,
+        B134 => 134,
+        B150 => 150,
+        B200 => 200,
+        B300 => 300,
+        B600 => 600,
+        B1200 => 1200,
+        B1800 => 1800,
+        B2400 => 2400,
+        B4800 => 4800,
+        B9600 => 9600,
+        B19200 => 19_200,
+        B38400 => 38_400,
+        B57600 => 57_600,
+        B115200 => 115_200,
+        B230400 => 230_400,
+        B460800 => 460_800,
+        B500000 => 500_000,
+        B576000 => 576_000,
+        B921600 => 921_600,
+        B1000000 => 1_000_000,
+        B1152000 => 1_152_000,
+        B1500000 => 1_500_000,
+        B2000000 => 2_000_000,
+        B2500000 => 2_500_000,
+        B3000000 => 3_000_000,
+        B3500000 => 3_500_000,
+        B4000000 => 4_000_000,
+        _ => unreachable!(),
+    }
+}
+
+/// Sets `VMIN` and `VTIME`, rounding `inter_byte_timeout` up to tenths of a second
+pub(crate) fn set_read_mode(
+    termios: &mut Termios,
+    min_bytes: u8,
+    inter_byte_timeout: Duration,
+) -> Result<()> {
+    let millis = inter_byte_timeout.as_millis();
+    let deciseconds = (millis + 99) / 100;
+    if deciseconds > u128::from(u8::MAX) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Inter-byte timeout is longer than 25.5s",
+        ));
+    }
+    termios.c_cc[libc::VMIN] = min_bytes as libc::cc_t;
+    termios.c_cc[libc::VTIME] = deciseconds as libc::cc_t;
+    Ok(())
+}
+
+/// Clears all flags that alter the data, or restores the common CR/NL translations
+pub(crate) fn set_binary_mode(termios: &mut Termios, binary: bool) {
+    if binary {
+        termios.c_iflag &= !(libc::ISTRIP
+            | libc::IXON
+            | libc::IXOFF
+            | libc::INLCR
+            | libc::ICRNL
+            | libc::IGNCR
+            | libc::PARMRK);
+        termios.c_oflag &= !libc::OPOST;
+        termios.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ECHONL | libc::ISIG | libc::IEXTEN);
+        termios.c_cflag &= !libc::CSIZE;
+        termios.c_cflag |= libc::CS8;
+    } else {
+        termios.c_iflag |= libc::ICRNL;
+        termios.c_oflag |= libc::OPOST | libc::ONLCR;
+    }
+}
+
+pub(crate) fn get_ignore_hup(termios: &Termios) -> bool {
+    termios.c_cflag & libc::CLOCAL == libc::CLOCAL
+}
+
+pub(crate) fn set_ignore_hup(termios: &mut Termios, ignore: bool) {
+    if ignore {
+        termios.c_cflag |= libc::CLOCAL;
+    } else {
+        termios.c_cflag &= !libc::CLOCAL;
+    }
+}
+
+/// Controls whether closing the last descriptor of the device deasserts DTR and RTS
+pub(crate) fn set_hangup_on_close(termios: &mut Termios, hangup: bool) {
+    if hangup {
+        termios.c_cflag |= libc::HUPCL;
+    } else {
+        termios.c_cflag &= !libc::HUPCL;
+    }
+}
+
+pub(crate) fn get_parity_marking(termios: &Termios) -> bool {
+    termios.c_iflag & (libc::INPCK | libc::PARMRK) == libc::INPCK | libc::PARMRK
+}
+
+/// Marks received bytes with parity errors, instead of passing them on as they are
+pub(crate) fn set_parity_marking(termios: &mut Termios, enabled: bool) {
+    if enabled {
+        termios.c_iflag |= libc::INPCK | libc::PARMRK;
+        termios.c_iflag &= !(libc::IGNPAR | libc::ISTRIP);
+    } else {
+        termios.c_iflag &= !libc::PARMRK;
+    }
+}
+
+pub(crate) fn get_parity_check(termios: &Termios) -> bool {
+    termios.c_iflag & libc::INPCK == libc::INPCK
+}
+
+pub(crate) fn set_parity_check(termios: &mut Termios, enabled: bool) {
+    if enabled {
+        termios.c_iflag |= libc::INPCK;
+        termios.c_iflag &= !libc::IGNPAR;
+    } else {
+        termios.c_iflag &= !libc::INPCK;
+    }
+}
+
 pub(crate) fn set_parity(termios: &mut Termios, parity: Parity) {
     match parity {
         Parity::None => {
@@ -183,6 +406,68 @@ pub(crate) fn set_flow_control(termios: &mut Termios, flow_control: FlowControl)
     };
 }
 
+/// The flags `set_flow_control()` changes, as saved by `SerialPort::save_flow_control()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct RawFlowControl {
+    iflag: libc::tcflag_t,
+    cflag: libc::tcflag_t,
+}
+
+const FLOW_CONTROL_IFLAGS: libc::tcflag_t = libc::IXON | libc::IXOFF;
+
+pub(crate) fn get_raw_flow_control(termios: &Termios) -> RawFlowControl {
+    RawFlowControl {
+        iflag: termios.c_iflag & FLOW_CONTROL_IFLAGS,
+        cflag: termios.c_cflag & libc::CRTSCTS,
+    }
+}
+
+pub(crate) fn set_raw_flow_control(termios: &mut Termios, raw: RawFlowControl) {
+    termios.c_iflag = (termios.c_iflag & !FLOW_CONTROL_IFLAGS) | raw.iflag;
+    termios.c_cflag = (termios.c_cflag & !libc::CRTSCTS) | raw.cflag;
+}
+
+/// Enables or disables throttling the sender as the input buffer fills up
+pub(crate) fn set_overflow_policy(termios: &mut Termios, policy: OverflowPolicy) -> Result<()> {
+    let hardware = termios.c_cflag & libc::CRTSCTS != 0;
+    let software = termios.c_iflag & libc::IXON != 0;
+    match policy {
+        OverflowPolicy::DropIncoming if hardware => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "RTS/CTS flow control can't be disabled for input only",
+        )),
+        OverflowPolicy::DropIncoming => {
+            termios.c_iflag &= !libc::IXOFF;
+            Ok(())
+        }
+        OverflowPolicy::AssertFlowControl if !hardware && !software => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Flow control is disabled",
+        )),
+        OverflowPolicy::AssertFlowControl => {
+            if software {
+                termios.c_iflag |= libc::IXOFF;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Hands XON/XOFF over to the application or back to the driver
+pub(crate) fn set_flow_control_passthrough(termios: &mut Termios, passthrough: bool) -> Result<()> {
+    if passthrough {
+        termios.c_iflag &= !(libc::IXON | libc::IXOFF);
+    } else if termios.c_cflag & libc::CRTSCTS != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Software flow control can't be combined with RTS/CTS flow control",
+        ));
+    } else {
+        termios.c_iflag |= libc::IXON | libc::IXOFF;
+    }
+    Ok(())
+}
+
 pub(crate) fn set_data_bits(termios: &mut Termios, data_bits: DataBits) {
     let size = match data_bits {
         DataBits::Five => libc::CS5,
@@ -241,6 +526,25 @@ pub(crate) fn set_baud_rate(termios: &mut Termios, baud_rate: u32) {
     )
 ))]
 pub(crate) fn set_baud_rate(termios: &mut Termios, baud_rate: u32) {
+    // Non-standard rates can't be set here and are ignored, see `check_baud_rate()`
+    let speed = match standard_speed(baud_rate) {
+        Some(speed) => speed,
+        None => return,
+    };
+    let res = unsafe { libc::cfsetspeed(termios, speed) };
+    nix::errno::Errno::result(res).expect("cfsetspeed failed");
+}
+
+/// Returns the `speed_t` constant of a standard baud rate
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_env = "musl",
+        target_arch = "powerpc",
+        target_arch = "powerpc64"
+    )
+))]
+fn standard_speed(baud_rate: u32) -> Option<libc::speed_t> {
     use self::libc::{
         B1000000, B1152000, B1500000, B2000000, B2500000, B3000000, B3500000, B4000000, B460800,
         B500000, B576000, B921600,
@@ -281,8 +585,39 @@ pub(crate) fn set_baud_rate(termios: &mut Termios, baud_rate: u32) {
         3_000_000 => B3000000,
         3_500_000 => B3500000,
         4_000_000 => B4000000,
-        _ => return,
+        _ => return None,
     };
-    let res = unsafe { libc::cfsetspeed(termios, baud_rate) };
-    nix::errno::Errno::result(res).expect("cfsetspeed failed");
+    Some(baud_rate)
+}
+
+/// Checks that `baud_rate` can be set on this platform
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_env = "musl",
+        target_arch = "powerpc",
+        target_arch = "powerpc64"
+    )
+))]
+pub(crate) fn check_baud_rate(baud_rate: u32) -> Result<()> {
+    match standard_speed(baud_rate) {
+        Some(_) => Ok(()),
+        None => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Only standard baud rates are supported on this platform",
+        )),
+    }
+}
+
+/// Checks that `baud_rate` can be set on this platform
+#[cfg(not(all(
+    target_os = "linux",
+    any(
+        target_env = "musl",
+        target_arch = "powerpc",
+        target_arch = "powerpc64"
+    )
+)))]
+pub(crate) fn check_baud_rate(_baud_rate: u32) -> Result<()> {
+    Ok(())
 }
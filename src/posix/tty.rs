@@ -1,19 +1,216 @@
 use std::mem::MaybeUninit;
 use std::os::unix::prelude::*;
 use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
-use std::{io, mem};
+use std::{fmt, io, mem};
 
-use nix::fcntl::{fcntl, OFlag};
+use cfg_if::cfg_if;
+use nix::fcntl::{fcntl, FdFlag, OFlag};
 use nix::{self, libc, unistd};
 
 use crate::posix::ioctl::{self, SerialLines};
 use crate::posix::termios;
 use crate::{
-    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, Result, SerialPort,
-    SerialPortBuilder, StopBits,
+    BaudRateInfo, ClearBuffer, DataBits, Error, ErrorKind, FlowControl, LineCounters, LineEnding,
+    ModemLines, ModemStatus, OverflowPolicy, Parity, PortCapabilities, QueueStatus, ReopenState,
+    Result, SavedFlowControl, SerialPort, SerialPortBuilder, SerialPortType, StopBits,
+    SupportedOps, UsbIdentity,
 };
 
+cfg_if! {
+    if #[cfg(any(
+        target_os = "android",
+        all(
+            target_os = "linux",
+            not(any(
+                target_env = "musl",
+                target_arch = "powerpc",
+                target_arch = "powerpc64"
+            ))
+        )
+    ))] {
+        /// Whether arbitrary baud rates can be set, if that's known for the platform
+        ///
+        /// They're set with `BOTHER` on Linux and `IOSSIOSPEED` on macOS.
+        const ARBITRARY_BAUD_RATES: Option<bool> = Some(true);
+
+        /// Fails with `InvalidInput` if the baud rate achieved by the driver deviates from
+        /// `requested` by more than `tolerance_percent`.
+        ///
+        /// Only the Linux `termios2` interface reports the achieved rate, so this always succeeds
+        /// on the other platforms.
+        fn check_achieved_baud_rate(
+            fd: RawFd,
+            requested: u32,
+            tolerance_percent: u32,
+        ) -> Result<()> {
+            let achieved = termios::get_baud_rate(&termios::get_termios(fd)?);
+            if crate::baud_rate_matches(requested, achieved, tolerance_percent) {
+                Ok(())
+            } else {
+                Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Baud rate {} is not supported, the closest rate the driver achieves is {}",
+                        requested, achieved
+                    ),
+                ))
+            }
+        }
+    } else {
+        const ARBITRARY_BAUD_RATES: Option<bool> =
+            if cfg!(any(target_os = "ios", target_os = "macos")) {
+                Some(true)
+            } else if cfg!(target_os = "linux") {
+                Some(false)
+            } else {
+                None
+            };
+
+        fn check_achieved_baud_rate(
+            _fd: RawFd,
+            _requested: u32,
+            _tolerance_percent: u32,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+}
+
+/// Returns the baud rate the driver runs at while `configured` is the speed set in `termios`.
+///
+/// On Linux a custom divisor enabled with `ASYNC_SPD_CUST` replaces a speed of 38400.
+#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+fn actual_baud_rate(fd: RawFd, configured: u32) -> u32 {
+    #[cfg(target_os = "linux")]
+    {
+        if configured == 38400 {
+            if let Ok(serial) = ioctl::tiocgserial(fd) {
+                if serial.flags & ioctl::ASYNC_SPD_MASK == ioctl::ASYNC_SPD_CUST
+                    && serial.custom_divisor > 0
+                    && serial.baud_base > 0
+                {
+                    return serial.baud_base as u32 / serial.custom_divisor as u32;
+                }
+            }
+        }
+    }
+    configured
+}
+
+/// Decodes bytes read with `PARMRK` set into `buf`, and the values of their parity bits into
+/// `parity_bits`.
+///
+/// Returns the number of bytes of `raw` consumed, which falls short of its length if it ends
+/// in the middle of a mark, and the number of bytes decoded.
+fn decode_parity_marks(
+    raw: &[u8],
+    odd: bool,
+    buf: &mut [u8],
+    parity_bits: &mut [bool],
+) -> (usize, usize) {
+    let mut consumed = 0;
+    let mut decoded = 0;
+    while decoded < buf.len() {
+        let (byte, parity_error, len) = match raw[consumed..] {
+            [0xff, 0xff, ..] => (0xff, false, 2),
+            [0xff, 0x00, byte, ..] => (byte, true, 3),
+            [] | [0xff] | [0xff, 0x00] => break,
+            [byte, ..] => (byte, false, 1),
+        };
+        // The parity bit that makes the number of 1 bits odd or even, flipped if it was wrong
+        let expected = (byte.count_ones() % 2 == 1) != odd;
+        buf[decoded] = byte;
+        parity_bits[decoded] = expected != parity_error;
+        consumed += len;
+        decoded += 1;
+    }
+    (consumed, decoded)
+}
+
+/// Reads bytes with `PARMRK` set from `source`, and decodes them like `decode_parity_marks()`.
+///
+/// Only one read is made, unless it ends in the middle of a mark. The kernel queues a mark in one
+/// piece, so the rest of it is already waiting and is read byte by byte.
+fn read_parity_marks<R: io::Read>(
+    source: &mut R,
+    odd: bool,
+    buf: &mut [u8],
+    parity_bits: &mut [bool],
+) -> io::Result<usize> {
+    let mut raw = vec![0; buf.len()];
+    let n = source.read(&mut raw)?;
+    let (consumed, mut decoded) = decode_parity_marks(&raw[..n], odd, buf, parity_bits);
+    if consumed < n {
+        let mut mark = raw[consumed..n].to_vec();
+        loop {
+            let mut byte = [0];
+            source.read_exact(&mut byte)?;
+            mark.push(byte[0]);
+            let (consumed, produced) =
+                decode_parity_marks(&mark, odd, &mut buf[decoded..], &mut parity_bits[decoded..]);
+            if consumed > 0 {
+                decoded += produced;
+                break;
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+/// Returns the path of the sysfs attribute `name` of the tty device open as `fd`.
+#[cfg(target_os = "linux")]
+fn sysfs_attribute(fd: RawFd, name: &str) -> Result<std::path::PathBuf> {
+    use nix::sys::stat::{major, minor};
+
+    let rdev = nix::sys::stat::fstat(fd)?.st_rdev;
+    Ok(std::path::PathBuf::from(format!(
+        "/sys/dev/char/{}:{}/{}",
+        major(rdev),
+        minor(rdev),
+        name
+    )))
+}
+
+/// Converts an error accessing the `rx_trig_bytes` attribute
+///
+/// The attribute only exists for UARTs with an adjustable trigger level, and writing a level
+/// the UART can't use fails with `EINVAL`.
+#[cfg(target_os = "linux")]
+fn fifo_trigger_error(e: io::Error) -> Error {
+    match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::InvalidInput => Error::new(
+            ErrorKind::InvalidInput,
+            "The driver doesn't support setting the FIFO trigger level",
+        ),
+        _ => Error::from(e),
+    }
+}
+
+/// Checks whether the kernel can apply `level` as the transmit FIFO trigger level
+///
+/// Linux has no interface for it, so this holds for no level.
+#[cfg(target_os = "linux")]
+fn tx_fifo_trigger_supported(_level: FifoLevel) -> bool {
+    false
+}
+
+/// Returns the driver's counters for a newly opened port, which become its initial baseline.
+///
+/// The driver doesn't clear its counters on open, and not all drivers maintain them, in which case
+/// the baseline is left at zero.
+#[cfg(target_os = "linux")]
+fn initial_counters(fd: RawFd) -> LineCounters {
+    ioctl::tiocgicount(fd).unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn initial_counters(_fd: RawFd) -> LineCounters {
+    LineCounters::default()
+}
+
 /// Convenience method for removing exclusive access from
 /// a fd and closing it.
 fn close(fd: RawFd) {
@@ -58,10 +255,116 @@ pub struct TTYPort {
     timeout: Duration,
     exclusive: bool,
     port_name: Option<String>,
+    port_type: SerialPortType,
+    reopen: ReopenState,
+    counters_baseline: LineCounters,
+    line_ending: LineEnding,
+    write_delay: Duration,
+    write_unbuffered: bool,
+    max_read_size: Option<usize>,
+    close_dtr: Option<bool>,
+    close_rts: Option<bool>,
+    rx_watermark: AtomicU32,
+    baud_rate_tolerance: u32,
     #[cfg(any(target_os = "ios", target_os = "macos"))]
     baud_rate: u32,
 }
 
+/// Low-level UART settings of a Linux serial driver
+///
+/// This is a subset of the kernel's `struct serial_struct`, as reported by the `TIOCGSERIAL`
+/// ioctl and displayed by `setserial`.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SerialStruct {
+    /// The UART type, as one of the kernel's `PORT_*` constants
+    pub uart_type: i32,
+    /// Driver flags, as a combination of the kernel's `ASYNC_*` flags
+    pub flags: i32,
+    /// The UART input clock divided by 16, i.e. the baud rate obtained with a divisor of 1
+    pub baud_base: u32,
+    /// The divisor used instead of the baud rate while a custom speed is enabled
+    pub custom_divisor: u32,
+    /// Size of the transmit FIFO in bytes
+    pub xmit_fifo_size: u32,
+}
+
+/// How long closing a Linux serial port waits for pending output to be transmitted
+///
+/// See [`TTYPort::set_closing_wait`](struct.TTYPort.html#method.set_closing_wait).
+#[cfg(target_os = "linux")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClosingWait {
+    /// Don't wait, discarding output that hasn't been sent yet
+    None,
+    /// Wait until all output has been sent, however long that takes
+    Forever,
+    /// Wait for at most this long. The kernel counts in hundredths of a second, so this is
+    /// rounded up to the next one, and allows up to 655.34 seconds.
+    Duration(Duration),
+}
+
+#[cfg(target_os = "linux")]
+impl ClosingWait {
+    fn from_raw(closing_wait: libc::c_ushort) -> ClosingWait {
+        match closing_wait {
+            ioctl::ASYNC_CLOSING_WAIT_NONE => ClosingWait::None,
+            ioctl::ASYNC_CLOSING_WAIT_INF => ClosingWait::Forever,
+            centis => ClosingWait::Duration(Duration::from_millis(u64::from(centis) * 10)),
+        }
+    }
+
+    fn to_raw(self) -> Result<libc::c_ushort> {
+        match self {
+            ClosingWait::None => Ok(ioctl::ASYNC_CLOSING_WAIT_NONE),
+            ClosingWait::Forever => Ok(ioctl::ASYNC_CLOSING_WAIT_INF),
+            ClosingWait::Duration(duration) => {
+                // Round up so that a short wait doesn't turn into none at all
+                let centis = (duration.as_millis() + 9) / 10;
+                if centis == 0 || centis >= u128::from(ioctl::ASYNC_CLOSING_WAIT_NONE) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Closing wait must be greater than zero and at most 655.34s",
+                    ));
+                }
+                Ok(centis as libc::c_ushort)
+            }
+        }
+    }
+}
+
+/// Number of bytes at which a 16550-style UART raises an interrupt
+///
+/// These are the levels of the 16550A. UARTs with other levels use the highest one that isn't
+/// above the requested one.
+///
+/// See [`TTYPort::set_rx_fifo_trigger`](struct.TTYPort.html#method.set_rx_fifo_trigger) and
+/// [`TTYPort::set_fifo_trigger`](struct.TTYPort.html#method.set_fifo_trigger).
+#[cfg(target_os = "linux")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FifoLevel {
+    /// Interrupt on every byte, for the lowest latency
+    Bytes1,
+    /// Interrupt once the FIFO holds 4 bytes
+    Bytes4,
+    /// Interrupt once the FIFO holds 8 bytes
+    Bytes8,
+    /// Interrupt once the FIFO holds 14 bytes, for the fewest interrupts
+    Bytes14,
+}
+
+#[cfg(target_os = "linux")]
+impl FifoLevel {
+    fn bytes(self) -> u32 {
+        match self {
+            FifoLevel::Bytes1 => 1,
+            FifoLevel::Bytes4 => 4,
+            FifoLevel::Bytes8 => 8,
+            FifoLevel::Bytes14 => 14,
+        }
+    }
+}
+
 /// Specifies the duration of a transmission break
 #[derive(Clone, Copy, Debug)]
 pub enum BreakDuration {
@@ -71,6 +374,50 @@ pub enum BreakDuration {
     Arbitrary(std::num::NonZeroI32),
 }
 
+/// The driver's lines for each of the control signals
+const MODEM_LINE_PINS: [(ModemLines, SerialLines); 6] = [
+    (ModemLines::RTS, SerialLines::REQUEST_TO_SEND),
+    (ModemLines::DTR, SerialLines::DATA_TERMINAL_READY),
+    (ModemLines::CTS, SerialLines::CLEAR_TO_SEND),
+    (ModemLines::DSR, SerialLines::DATA_SET_READY),
+    (ModemLines::RI, SerialLines::RING),
+    (ModemLines::CD, SerialLines::DATA_CARRIER_DETECT),
+];
+
+/// Converts the driver's lines into control signals.
+fn modem_lines(pins: SerialLines) -> ModemLines {
+    MODEM_LINE_PINS
+        .iter()
+        .filter(|(_, pin)| pins.contains(*pin))
+        .fold(ModemLines::empty(), |lines, (line, _)| lines | *line)
+}
+
+/// Reads the input control signals of the port at `path` without configuring it.
+///
+/// The port is opened read-only and non-blocking, so this neither waits for a carrier nor
+/// changes any settings, and it's closed right away. Returns `None` if the port can't be
+/// opened, e.g. because another process holds it exclusively, or its signals can't be read.
+pub(crate) fn probe_modem_inputs(path: &str) -> Option<ModemLines> {
+    let fd = OwnedFd(
+        nix::fcntl::open(
+            Path::new(path),
+            OFlag::O_RDONLY | OFlag::O_NOCTTY | OFlag::O_NONBLOCK | OFlag::O_CLOEXEC,
+            nix::sys::stat::Mode::empty(),
+        )
+        .ok()?,
+    );
+    let lines = modem_lines(ioctl::tiocmget(fd.0).ok()?);
+    Some(lines & (ModemLines::CTS | ModemLines::DSR | ModemLines::RI | ModemLines::CD))
+}
+
+/// Converts control signals into the driver's lines.
+fn serial_lines(lines: ModemLines) -> SerialLines {
+    MODEM_LINE_PINS
+        .iter()
+        .filter(|(line, _)| lines.contains(*line))
+        .fold(SerialLines::empty(), |pins, (_, pin)| pins | *pin)
+}
+
 /// Wrapper for RawFd to assure that it's properly closed,
 /// even if the enclosing function exits early.
 ///
@@ -91,6 +438,74 @@ impl OwnedFd {
     }
 }
 
+/// A complete `termios` configuration, applied verbatim by `TTYPort::open_with_raw_config()`
+///
+/// **This is an advanced, platform-specific escape hatch.** The `termios` struct (`termios2` on
+/// Linux) is passed to the driver exactly as given, without the raw mode and other settings the
+/// crate normally applies, so anything `TTYPort` assumes about the port may not hold. Use `new()`
+/// to start from a `termios` struct built elsewhere, or `from_port()` to capture the
+/// configuration of an open port, and `termios_mut()` to adjust individual flags with the `libc`
+/// constants. Note that `from_port()` captures the port as configured by opening it, so it
+/// already includes the raw mode and the settings of the builder, unless the port was opened
+/// with `SerialPortBuilder::raw(false)`.
+#[derive(Clone, Copy)]
+pub struct RawPortConfig {
+    termios: termios::Termios,
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    baud_rate: u32,
+}
+
+impl RawPortConfig {
+    /// Creates a configuration applying `termios`
+    ///
+    /// This is a `libc::termios2` on Linux and Android, which carries the baud rate in its
+    /// `c_ispeed` and `c_ospeed` fields, and a `libc::termios` elsewhere. On macOS the baud rate
+    /// is taken from the output speed of `termios`.
+    pub fn new(termios: termios::Termios) -> RawPortConfig {
+        RawPortConfig {
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            baud_rate: unsafe { libc::cfgetospeed(&termios) } as u32,
+            termios,
+        }
+    }
+
+    /// Captures the current configuration of `port`
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn from_port(port: &TTYPort) -> Result<RawPortConfig> {
+        Ok(RawPortConfig {
+            termios: termios::get_termios(port.fd)?,
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            baud_rate: port.baud_rate,
+        })
+    }
+
+    /// Returns the `termios` struct that will be applied
+    pub fn termios(&self) -> &termios::Termios {
+        &self.termios
+    }
+
+    /// Returns the `termios` struct that will be applied for modification
+    pub fn termios_mut(&mut self) -> &mut termios::Termios {
+        &mut self.termios
+    }
+}
+
+impl fmt::Debug for RawPortConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RawPortConfig")
+            .field("c_iflag", &self.termios.c_iflag)
+            .field("c_oflag", &self.termios.c_oflag)
+            .field("c_cflag", &self.termios.c_cflag)
+            .field("c_lflag", &self.termios.c_lflag)
+            .field("c_cc", &self.termios.c_cc)
+            .finish()
+    }
+}
+
 impl TTYPort {
     /// Opens a TTY device as a serial port.
     ///
@@ -113,10 +528,28 @@ impl TTYPort {
         use nix::fcntl::FcntlArg::F_SETFL;
         use nix::libc::{cfmakeraw, tcgetattr, tcsetattr};
 
+        let port_type = crate::lookup_port_type(&builder.path);
+        crate::check_data_bits_supported(&port_type, builder.data_bits)?;
+
         let path = Path::new(&builder.path);
+        // Without `O_NOCTTY`, a process without a controlling terminal acquires the port as one,
+        // after which a hangup of the line delivers `SIGHUP` to it.
+        let mut flags = OFlag::O_RDWR | OFlag::O_NONBLOCK | OFlag::O_CLOEXEC;
+        if !builder.controlling_tty {
+            flags |= OFlag::O_NOCTTY;
+        }
+        if builder.append {
+            flags |= OFlag::O_APPEND;
+        }
+        if builder.exclusive_open {
+            flags |= OFlag::O_EXCL;
+        }
+        if !builder.follow_symlinks {
+            flags |= OFlag::O_NOFOLLOW;
+        }
         let fd = OwnedFd(nix::fcntl::open(
             path,
-            OFlag::O_RDWR | OFlag::O_NOCTTY | OFlag::O_NONBLOCK,
+            flags,
             nix::sys::stat::Mode::empty(),
         )?);
 
@@ -125,39 +558,42 @@ impl TTYPort {
         // other applications that may have an exclusive port lock.
         ioctl::tiocexcl(fd.0)?;
 
-        let mut termios = MaybeUninit::uninit();
-        nix::errno::Errno::result(unsafe { tcgetattr(fd.0, termios.as_mut_ptr()) })?;
-        let mut termios = unsafe { termios.assume_init() };
-
-        // setup TTY for binary serial port access
-        // Enable reading from the port and ignore all modem control lines
-        termios.c_cflag |= libc::CREAD | libc::CLOCAL;
-        // Enable raw mode which disables any implicit processing of the input or output data streams
-        // This also sets no timeout period and a read will block until at least one character is
-        // available.
-        unsafe { cfmakeraw(&mut termios) };
-
-        // write settings to TTY
-        unsafe { tcsetattr(fd.0, libc::TCSANOW, &termios) };
-
-        // Read back settings from port and confirm they were applied correctly
-        let mut actual_termios = MaybeUninit::uninit();
-        unsafe { tcgetattr(fd.0, actual_termios.as_mut_ptr()) };
-        let actual_termios = unsafe { actual_termios.assume_init() };
-
-        if actual_termios.c_iflag != termios.c_iflag
-            || actual_termios.c_oflag != termios.c_oflag
-            || actual_termios.c_lflag != termios.c_lflag
-            || actual_termios.c_cflag != termios.c_cflag
-        {
-            return Err(Error::new(
-                ErrorKind::Unknown,
-                "Settings did not apply correctly",
-            ));
-        };
+        // Leave the terminal settings found on the device alone unless raw mode is requested
+        if builder.raw {
+            let mut termios = MaybeUninit::uninit();
+            nix::errno::Errno::result(unsafe { tcgetattr(fd.0, termios.as_mut_ptr()) })?;
+            let mut termios = unsafe { termios.assume_init() };
+
+            // setup TTY for binary serial port access
+            // Enable reading from the port and ignore all modem control lines
+            termios.c_cflag |= libc::CREAD | libc::CLOCAL;
+            // Enable raw mode which disables any implicit processing of the input or output data
+            // streams. This also sets no timeout period and a read will block until at least one
+            // character is available.
+            unsafe { cfmakeraw(&mut termios) };
+
+            // write settings to TTY
+            unsafe { tcsetattr(fd.0, libc::TCSANOW, &termios) };
+
+            // Read back settings from port and confirm they were applied correctly
+            let mut actual_termios = MaybeUninit::uninit();
+            unsafe { tcgetattr(fd.0, actual_termios.as_mut_ptr()) };
+            let actual_termios = unsafe { actual_termios.assume_init() };
+
+            if actual_termios.c_iflag != termios.c_iflag
+                || actual_termios.c_oflag != termios.c_oflag
+                || actual_termios.c_lflag != termios.c_lflag
+                || actual_termios.c_cflag != termios.c_cflag
+            {
+                return Err(Error::new(
+                    ErrorKind::Unknown,
+                    "Settings did not apply correctly",
+                ));
+            };
+        }
 
-        // clear O_NONBLOCK flag
-        fcntl(fd.0, F_SETFL(nix::fcntl::OFlag::empty()))?;
+        // clear O_NONBLOCK flag, keeping O_APPEND which is also a file status flag
+        fcntl(fd.0, F_SETFL(flags & OFlag::O_APPEND))?;
 
         // Configure the low-level port settings
         let mut termios = termios::get_termios(fd.0)?;
@@ -171,6 +607,13 @@ impl TTYPort {
         termios::set_termios(fd.0, &termios, builder.baud_rate)?;
         #[cfg(not(any(target_os = "ios", target_os = "macos")))]
         termios::set_termios(fd.0, &termios)?;
+        check_achieved_baud_rate(fd.0, builder.baud_rate, builder.baud_rate_tolerance)?;
+
+        if let Some(settle_delay) = builder.settle_delay {
+            std::thread::sleep(settle_delay);
+        }
+
+        let counters_baseline = initial_counters(fd.0);
 
         // Return the final port object
         Ok(TTYPort {
@@ -178,6 +621,17 @@ impl TTYPort {
             timeout: builder.timeout,
             exclusive: true,
             port_name: Some(builder.path.clone()),
+            port_type,
+            reopen: ReopenState::new(builder),
+            counters_baseline,
+            line_ending: builder.line_ending,
+            write_delay: Duration::from_secs(0),
+            write_unbuffered: false,
+            max_read_size: None,
+            close_dtr: None,
+            close_rts: None,
+            rx_watermark: AtomicU32::new(0),
+            baud_rate_tolerance: builder.baud_rate_tolerance,
             #[cfg(any(target_os = "ios", target_os = "macos"))]
             baud_rate: builder.baud_rate,
         })
@@ -216,18 +670,6 @@ impl TTYPort {
         }
     }
 
-    fn set_pin(&mut self, pin: ioctl::SerialLines, level: bool) -> Result<()> {
-        if level {
-            ioctl::tiocmbis(self.fd, pin)
-        } else {
-            ioctl::tiocmbic(self.fd, pin)
-        }
-    }
-
-    fn read_pin(&mut self, pin: ioctl::SerialLines) -> Result<bool> {
-        ioctl::tiocmget(self.fd).map(|pins| pins.contains(pin))
-    }
-
     /// Create a pair of pseudo serial terminals
     ///
     /// ## Returns
@@ -250,6 +692,11 @@ impl TTYPort {
     pub fn pair() -> Result<(Self, Self)> {
         // Open the next free pty.
         let next_pty_fd = nix::pty::posix_openpt(nix::fcntl::OFlag::O_RDWR)?;
+        // `posix_openpt()` doesn't portably accept `O_CLOEXEC`
+        fcntl(
+            next_pty_fd.as_raw_fd(),
+            nix::fcntl::F_SETFD(FdFlag::FD_CLOEXEC),
+        )?;
 
         // Grant access to the associated slave pty
         nix::pty::grantpt(&next_pty_fd)?;
@@ -279,7 +726,7 @@ impl TTYPort {
         let baud_rate = 9600;
         let fd = nix::fcntl::open(
             Path::new(&ptty_name),
-            OFlag::O_RDWR | OFlag::O_NOCTTY | OFlag::O_NONBLOCK,
+            OFlag::O_RDWR | OFlag::O_NOCTTY | OFlag::O_NONBLOCK | OFlag::O_CLOEXEC,
             nix::sys::stat::Mode::empty(),
         )?;
 
@@ -301,9 +748,20 @@ impl TTYPort {
 
         let slave_tty = TTYPort {
             fd,
-            timeout: Duration::from_millis(100),
+            timeout: crate::REASONABLE_TIMEOUT,
             exclusive: true,
             port_name: Some(ptty_name),
+            port_type: SerialPortType::Unknown,
+            reopen: ReopenState::default(),
+            counters_baseline: LineCounters::default(),
+            line_ending: LineEnding::None,
+            write_delay: Duration::from_secs(0),
+            write_unbuffered: false,
+            max_read_size: None,
+            close_dtr: None,
+            close_rts: None,
+            rx_watermark: AtomicU32::new(0),
+            baud_rate_tolerance: crate::BAUD_RATE_TOLERANCE_PERCENT,
             #[cfg(any(target_os = "ios", target_os = "macos"))]
             baud_rate,
         };
@@ -313,9 +771,20 @@ impl TTYPort {
         // BSDs when used on the master port.
         let master_tty = TTYPort {
             fd: next_pty_fd.into_raw_fd(),
-            timeout: Duration::from_millis(100),
+            timeout: crate::REASONABLE_TIMEOUT,
             exclusive: true,
             port_name: None,
+            port_type: SerialPortType::Unknown,
+            reopen: ReopenState::default(),
+            counters_baseline: LineCounters::default(),
+            line_ending: LineEnding::None,
+            write_delay: Duration::from_secs(0),
+            write_unbuffered: false,
+            max_read_size: None,
+            close_dtr: None,
+            close_rts: None,
+            rx_watermark: AtomicU32::new(0),
+            baud_rate_tolerance: crate::BAUD_RATE_TOLERANCE_PERCENT,
             #[cfg(any(target_os = "ios", target_os = "macos"))]
             baud_rate,
         };
@@ -323,293 +792,765 @@ impl TTYPort {
         Ok((master_tty, slave_tty))
     }
 
-    /// Sends 0-valued bits over the port for a set duration
-    pub fn send_break(&self, duration: BreakDuration) -> Result<()> {
-        match duration {
-            BreakDuration::Short => nix::sys::termios::tcsendbreak(self.fd, 0),
-            BreakDuration::Arbitrary(n) => nix::sys::termios::tcsendbreak(self.fd, n.get()),
+    /// Reads bytes into a buffer which doesn't need to be initialized
+    ///
+    /// This behaves like `io::Read::read()`, including the timeout, but reads directly into
+    /// uninitialized memory so callers don't have to zero large buffers before every read. Only
+    /// the first `n` bytes of `buf` are initialized once this returns `Ok(n)`.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(TimedOut)` if no data was received within the timeout.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn read_uninit(&mut self, buf: &mut [MaybeUninit<u8>]) -> Result<usize> {
+        let len = self
+            .max_read_size
+            .map_or(buf.len(), |max| buf.len().min(max));
+        let buf = &mut buf[..len];
+        super::poll::wait_read_fd(self.fd, self.timeout)?;
+        let res = unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        match nix::errno::Errno::result(res) {
+            // With a `VMIN` of 0 the read may still come up empty, e.g. if the data was taken by
+            // another handle in the meantime. Std adapters like `read_to_end()` would take
+            // `Ok(0)` for the end of the stream.
+            Ok(0) if !buf.is_empty() => Err(Error::new(
+                ErrorKind::Io(io::ErrorKind::TimedOut),
+                "Operation timed out",
+            )),
+            Ok(n) => Ok(n as usize),
+            Err(e) => Err(port_error(e)),
         }
-        .map_err(|e| e.into())
     }
 
-    /// Attempts to clone the `SerialPort`. This allow you to write and read simultaneously from the
-    /// same serial connection. Please note that if you want a real asynchronous serial port you
-    /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
-    /// [tokio-serial](https://crates.io/crates/tokio-serial).
-    ///
-    /// Also, you must be very careful when changing the settings of a cloned `SerialPort` : since
-    /// the settings are cached on a per object basis, trying to modify them from two different
-    /// objects can cause some nasty behavior.
+    /// Opens a TTY device and applies `config` verbatim
     ///
-    /// This is the same as `SerialPort::try_clone()` but returns the concrete type instead.
+    /// **This is an advanced, platform-specific escape hatch**, see `RawPortConfig`. The port is
+    /// opened exclusively, like with `open()`, but none of the usual settings are applied, so the
+    /// port ends up configured exactly as described by `config`. The timeout is set to
+    /// [`REASONABLE_TIMEOUT`](../constant.REASONABLE_TIMEOUT.html).
     ///
-    /// # Errors
+    /// ## Errors
     ///
-    /// This function returns an error if the serial port couldn't be cloned.
-    pub fn try_clone_native(&self) -> Result<TTYPort> {
-        let fd_cloned: i32 = fcntl(self.fd, nix::fcntl::F_DUPFD(self.fd))?;
+    /// * `NoDevice` if the device could not be opened. This could indicate that the device is
+    ///   already in use.
+    /// * `InvalidInput` if `path` is not a valid device name.
+    /// * `Io` for any other error while opening or configuring the device.
+    pub fn open_with_raw_config(path: &str, config: &RawPortConfig) -> Result<TTYPort> {
+        let fd = OwnedFd(nix::fcntl::open(
+            Path::new(path),
+            OFlag::O_RDWR | OFlag::O_NOCTTY | OFlag::O_NONBLOCK | OFlag::O_CLOEXEC,
+            nix::sys::stat::Mode::empty(),
+        )?);
+        ioctl::tiocexcl(fd.0)?;
+        fcntl(fd.0, nix::fcntl::F_SETFL(OFlag::empty()))?;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        termios::set_termios(fd.0, &config.termios, config.baud_rate)?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_termios(fd.0, &config.termios)?;
+
+        let counters_baseline = initial_counters(fd.0);
         Ok(TTYPort {
-            fd: fd_cloned,
-            exclusive: self.exclusive,
-            port_name: self.port_name.clone(),
-            timeout: self.timeout,
+            fd: fd.into_raw(),
+            timeout: crate::REASONABLE_TIMEOUT,
+            exclusive: true,
+            port_name: Some(path.to_string()),
+            port_type: crate::lookup_port_type(path),
+            reopen: ReopenState::default(),
+            counters_baseline,
+            line_ending: LineEnding::None,
+            write_delay: Duration::from_secs(0),
+            write_unbuffered: false,
+            max_read_size: None,
+            close_dtr: None,
+            close_rts: None,
+            rx_watermark: AtomicU32::new(0),
+            baud_rate_tolerance: crate::BAUD_RATE_TOLERANCE_PERCENT,
             #[cfg(any(target_os = "ios", target_os = "macos"))]
-            baud_rate: self.baud_rate,
+            baud_rate: config.baud_rate,
         })
     }
-}
 
-impl Drop for TTYPort {
-    fn drop(&mut self) {
-        close(self.fd);
+    /// Returns how far the achieved baud rate may deviate from the requested one, in percent
+    ///
+    /// See `set_baud_rate_tolerance()`.
+    pub fn baud_rate_tolerance(&self) -> u32 {
+        self.baud_rate_tolerance
     }
-}
 
-impl AsRawFd for TTYPort {
-    fn as_raw_fd(&self) -> RawFd {
-        self.fd
+    /// Sets how far the achieved baud rate may deviate from the requested one, in percent
+    ///
+    /// On Linux, arbitrary baud rates are set with `BOTHER`, and the driver picks the closest
+    /// rate its clock divisor can produce. `set_baud_rate()` reads back the achieved rate and
+    /// fails with `InvalidInput` if it deviates by more than this tolerance, leaving the
+    /// previous baud rate in place. Ports are opened with the tolerance set by
+    /// `SerialPortBuilder::baud_rate_tolerance()`, which defaults to 2%, about what a UART
+    /// tolerates. This has no effect on other platforms.
+    pub fn set_baud_rate_tolerance(&mut self, percent: u32) {
+        self.baud_rate_tolerance = percent;
+        self.reopen.update(|s| s.baud_rate_tolerance = percent);
     }
-}
 
-impl IntoRawFd for TTYPort {
-    fn into_raw_fd(self) -> RawFd {
-        // Pull just the file descriptor out. We also prevent the destructor
-        // from being run by calling `mem::forget`. If we didn't do this, the
-        // port would be closed, which would make `into_raw_fd` unusable.
-        let TTYPort { fd, .. } = self;
-        mem::forget(self);
-        fd
+    /// Gets the low-level UART settings of the port
+    ///
+    /// This is mostly useful to find out how the driver derives non-standard baud rates, since
+    /// the actual rate is `baud_base / divisor`.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` if the driver doesn't support `TIOCGSERIAL`, which is the case for most USB adapters
+    ///   and pseudo terminals, or for any other type of I/O error.
+    /// * `NoDevice` if the device was disconnected.
+    #[cfg(target_os = "linux")]
+    pub fn serial_struct(&self) -> Result<SerialStruct> {
+        let serial = ioctl::tiocgserial(self.fd)?;
+        Ok(SerialStruct {
+            uart_type: serial.type_,
+            flags: serial.flags,
+            baud_base: serial.baud_base as u32,
+            custom_divisor: serial.custom_divisor as u32,
+            xmit_fifo_size: serial.xmit_fifo_size as u32,
+        })
     }
-}
-
-/// Get the baud speed for a port from its file descriptor
-#[cfg(any(target_os = "ios", target_os = "macos"))]
-fn get_termios_speed(fd: RawFd) -> u32 {
-    let mut termios = MaybeUninit::uninit();
-    let res = unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) };
-    nix::errno::Errno::result(res).expect("Failed to get termios data");
-    let termios = unsafe { termios.assume_init() };
-    assert_eq!(termios.c_ospeed, termios.c_ispeed);
-    termios.c_ospeed as u32
-}
 
-impl FromRawFd for TTYPort {
-    unsafe fn from_raw_fd(fd: RawFd) -> Self {
-        TTYPort {
-            fd,
-            timeout: Duration::from_millis(100),
-            exclusive: ioctl::tiocexcl(fd).is_ok(),
-            // It is not trivial to get the file path corresponding to a file descriptor.
-            // We'll punt on it and set it to `None` here.
-            port_name: None,
-            // It's not guaranteed that the baud rate in the `termios` struct is correct, as
-            // setting an arbitrary baud rate via the `iossiospeed` ioctl overrides that value,
-            // but extract that value anyways as a best-guess of the actual baud rate.
-            #[cfg(any(target_os = "ios", target_os = "macos"))]
-            baud_rate: get_termios_speed(fd),
+    /// Sets the baud rate through a raw UART clock divisor
+    ///
+    /// The port then runs at `baud_base / divisor` (see `serial_struct()`). This uses the legacy
+    /// `ASYNC_SPD_CUST` mechanism of the kernel, so the baud rate is also set to 38400, which is
+    /// what the divisor replaces. A `divisor` of 0 disables the custom divisor again.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` if the driver doesn't support `TIOCSSERIAL`, if changing the divisor requires
+    ///   privileges the process doesn't have, or for any other type of I/O error.
+    /// * `NoDevice` if the device was disconnected.
+    #[cfg(target_os = "linux")]
+    pub fn set_custom_divisor(&mut self, divisor: u32) -> Result<()> {
+        let mut serial = ioctl::tiocgserial(self.fd)?;
+        serial.flags &= !ioctl::ASYNC_SPD_MASK;
+        if divisor != 0 {
+            serial.flags |= ioctl::ASYNC_SPD_CUST;
         }
+        serial.custom_divisor = divisor as libc::c_int;
+        ioctl::tiocsserial(self.fd, &serial)?;
+        self.set_baud_rate(38400)
     }
-}
-
-impl io::Read for TTYPort {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if let Err(e) = super::poll::wait_read_fd(self.fd, self.timeout) {
-            return Err(io::Error::from(Error::from(e)));
-        }
 
-        nix::unistd::read(self.fd, buf).map_err(|e| io::Error::from(Error::from(e)))
+    /// Gets how long closing the port waits for pending output to be transmitted
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` if the driver doesn't support `TIOCGSERIAL`, which is the case for most USB adapters
+    ///   and pseudo terminals, or for any other type of I/O error.
+    /// * `NoDevice` if the device was disconnected.
+    #[cfg(target_os = "linux")]
+    pub fn closing_wait(&self) -> Result<ClosingWait> {
+        Ok(ClosingWait::from_raw(
+            ioctl::tiocgserial(self.fd)?.closing_wait,
+        ))
     }
-}
-
-impl io::Write for TTYPort {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        if let Err(e) = super::poll::wait_write_fd(self.fd, self.timeout) {
-            return Err(io::Error::from(Error::from(e)));
-        }
 
-        nix::unistd::write(self.fd, buf).map_err(|e| io::Error::from(Error::from(e)))
+    /// Sets how long closing the port waits for pending output to be transmitted
+    ///
+    /// The kernel's default is 30 seconds, so a program that exits while flow control holds off
+    /// the output, or while a lot of data is still queued at a low baud rate, seems to hang on
+    /// exit. `ClosingWait::None` makes closing return right away, discarding what hasn't been
+    /// sent, while `ClosingWait::Forever` makes sure no data is ever lost. The setting belongs to
+    /// the device rather than this handle, so it stays in effect for later opens until the
+    /// device is removed.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if a `ClosingWait::Duration` is zero or longer than 655.34 seconds.
+    /// * `Io` if the driver doesn't support `TIOCSSERIAL`, if changing the setting requires
+    ///   privileges the process doesn't have, or for any other type of I/O error.
+    /// * `NoDevice` if the device was disconnected.
+    #[cfg(target_os = "linux")]
+    pub fn set_closing_wait(&mut self, wait: ClosingWait) -> Result<()> {
+        let closing_wait = wait.to_raw()?;
+        let mut serial = ioctl::tiocgserial(self.fd)?;
+        serial.closing_wait = closing_wait;
+        ioctl::tiocsserial(self.fd, &serial)
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        nix::sys::termios::tcdrain(self.fd)
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "flush failed"))
+    /// Gets the number of received bytes at which the UART raises an interrupt
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if the driver doesn't expose the trigger level, see
+    ///   `set_rx_fifo_trigger()`.
+    /// * `Io` for any other type of I/O error.
+    #[cfg(target_os = "linux")]
+    pub fn rx_fifo_trigger(&self) -> Result<u32> {
+        let path = sysfs_attribute(self.fd, "rx_trig_bytes")?;
+        let value = std::fs::read_to_string(path).map_err(fifo_trigger_error)?;
+        value.trim().parse().map_err(|_| {
+            Error::new(
+                ErrorKind::Unknown,
+                "The driver reported an invalid FIFO trigger level",
+            )
+        })
     }
-}
 
-impl SerialPort for TTYPort {
-    fn name(&self) -> Option<String> {
-        self.port_name.clone()
+    /// Sets the number of received bytes at which the UART raises an interrupt
+    ///
+    /// A low level reduces the latency of reads, a high one the interrupt load at high baud
+    /// rates. This is the knob that's otherwise only reachable through sysfs: it's written to the
+    /// `rx_trig_bytes` attribute of the device, which the `8250` driver provides for UARTs with
+    /// an adjustable level. The level applies to the device rather than this handle, and is
+    /// reset when the device is removed.
+    ///
+    /// The kernel has no interface for the transmit FIFO trigger level, so only the receive
+    /// level can be set.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if the driver doesn't expose the trigger level, which is the case for USB
+    ///   adapters, pseudo terminals and UARTs without an adjustable level.
+    /// * `Io` if changing the level requires privileges the process doesn't have, or for any
+    ///   other type of I/O error.
+    #[cfg(target_os = "linux")]
+    pub fn set_rx_fifo_trigger(&mut self, level: FifoLevel) -> Result<()> {
+        let path = sysfs_attribute(self.fd, "rx_trig_bytes")?;
+        std::fs::write(path, level.bytes().to_string()).map_err(fifo_trigger_error)
     }
 
-    /// Returns the port's baud rate
+    /// Sets the numbers of bytes at which the UART raises its receive and transmit interrupts
     ///
-    /// On some platforms this will be the actual device baud rate, which may differ from the
-    /// desired baud rate.
-    #[cfg(any(
-        target_os = "android",
-        all(
-            target_os = "linux",
-            not(any(
-                target_env = "musl",
-                target_arch = "powerpc",
-                target_arch = "powerpc64"
-            ))
-        )
-    ))]
-    fn baud_rate(&self) -> Result<u32> {
-        let termios2 = ioctl::tcgets2(self.fd)?;
+    /// `rx_level` is applied like with `set_rx_fifo_trigger()`. A `tx_level` of `None` leaves
+    /// the transmit level to the driver. The kernel has no interface for the transmit level: the
+    /// `8250` driver programs a fixed level for each UART type, and the 16550A only raises the
+    /// transmit interrupt once its FIFO is empty. So any other `tx_level` can't be applied on
+    /// Linux, and this fails without changing the receive level.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `tx_level` can't be applied, or for the reasons listed for
+    ///   `set_rx_fifo_trigger()`.
+    /// * `Io` for any other type of I/O error.
+    #[cfg(target_os = "linux")]
+    pub fn set_fifo_trigger(
+        &mut self,
+        rx_level: FifoLevel,
+        tx_level: Option<FifoLevel>,
+    ) -> Result<()> {
+        if let Some(tx_level) = tx_level {
+            if !tx_fifo_trigger_supported(tx_level) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "The transmit FIFO trigger level can't be set to {} bytes",
+                        tx_level.bytes()
+                    ),
+                ));
+            }
+        }
+        self.set_rx_fifo_trigger(rx_level)
+    }
 
-        assert!(termios2.c_ospeed == termios2.c_ispeed);
+    /// Sends 0-valued bits over the port for a set duration
+    pub fn send_break(&self, duration: BreakDuration) -> Result<()> {
+        match duration {
+            BreakDuration::Short => nix::sys::termios::tcsendbreak(self.fd, 0),
+            BreakDuration::Arbitrary(n) => nix::sys::termios::tcsendbreak(self.fd, n.get()),
+        }
+        .map_err(|e| e.into())
+    }
 
-        Ok(termios2.c_ospeed as u32)
+    /// Returns whether the port ignores the modem control lines, see `set_ignore_hup()`
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn ignore_hup(&self) -> Result<bool> {
+        let termios = termios::get_termios(self.fd)?;
+        Ok(termios::get_ignore_hup(&termios))
     }
 
-    /// Returns the port's baud rate
+    /// Sets whether the port ignores the modem control lines
     ///
-    /// On some platforms this will be the actual device baud rate, which may differ from the
-    /// desired baud rate.
-    #[cfg(any(
-        target_os = "dragonflybsd",
-        target_os = "freebsd",
-        target_os = "netbsd",
-        target_os = "openbsd"
-    ))]
-    fn baud_rate(&self) -> Result<u32> {
+    /// This controls `CLOCAL`, which is set when the port is opened, unless it's opened with
+    /// `SerialPortBuilder::raw(false)`, which leaves it as it was found. While it's set, losing the
+    /// carrier (CD) doesn't hang up the line, which is what long-running services reading from a
+    /// serial console want. Once it's cleared, a carrier loss hangs up the line: reads and writes
+    /// then fail with `NoDevice` until the port is reopened, and if the port is the controlling
+    /// terminal of a session, its processes are sent `SIGHUP`, which terminates them by default.
+    /// Ports only become the controlling terminal when opened with
+    /// `SerialPortBuilder::controlling_tty(true)`, so a daemon that keeps the default `O_NOCTTY`
+    /// isn't sent `SIGHUP` either way.
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn set_ignore_hup(&mut self, ignore: bool) -> Result<()> {
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_ignore_hup(&mut termios, ignore);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        return termios::set_termios(self.fd, &termios, self.baud_rate);
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        return termios::set_termios(self.fd, &termios);
+    }
+
+    /// Enables or disables marking of bytes received with a parity error
+    ///
+    /// This sets `PARMRK` together with `INPCK`, so that the kernel passes a byte with a bad
+    /// parity bit on as the sequence `0xff 0x00 byte`, and escapes a received `0xff` as
+    /// `0xff 0xff`. Use `read_with_parity()` to decode the marks while this is enabled; `read()`
+    /// returns them as they are. Only bytes received after the marking is enabled are marked.
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn set_parity_marking(&mut self, enabled: bool) -> Result<()> {
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_parity_marking(&mut termios, enabled);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        return termios::set_termios(self.fd, &termios, self.baud_rate);
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        return termios::set_termios(self.fd, &termios);
+    }
+
+    /// Reads bytes along with the value of their parity bits
+    ///
+    /// This is meant for multidrop protocols that use the parity bit as an address marker, i.e.
+    /// mark or space ("stick") parity. The port is set to odd or even parity instead, and the
+    /// parity bit of each byte is worked out from whether it was received with a parity error,
+    /// which works without `CMSPAR`. For each byte read into `buf`, the corresponding entry of
+    /// `parity_bits` is set to `true` if its parity bit was 1 (mark) and `false` if it was 0
+    /// (space). Parity marking must be enabled with `set_parity_marking()` first.
+    ///
+    /// The kernel marks framing errors and breaks the same way as parity errors, so those are
+    /// reported as a byte with an unexpected parity bit. There's no equivalent on Windows, where
+    /// the driver only counts parity errors, and the marks depend on the driver reporting parity
+    /// errors to the tty layer, which pseudo terminals and some USB drivers don't.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `parity_bits` is shorter than `buf`, if the parity is
+    ///   `Parity::None`, or if parity marking isn't enabled.
+    /// * `Io(TimedOut)` if no data was received within the timeout.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn read_with_parity(&mut self, buf: &mut [u8], parity_bits: &mut [bool]) -> Result<usize> {
+        if parity_bits.len() < buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "parity_bits is shorter than buf",
+            ));
+        }
         let termios = termios::get_termios(self.fd)?;
+        let odd = match termios::get_parity(&termios) {
+            Parity::None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Parity bits can't be read with parity disabled",
+                ))
+            }
+            Parity::Odd => true,
+            Parity::Even => false,
+        };
+        if !termios::get_parity_marking(&termios) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Parity marking is not enabled",
+            ));
+        }
+
+        Ok(read_parity_marks(self, odd, buf, parity_bits)?)
+    }
 
-        let ospeed = unsafe { libc::cfgetospeed(&termios) };
-        let ispeed = unsafe { libc::cfgetispeed(&termios) };
+    /// Leaves DTR and RTS at the levels requested with `set_close_behavior()`
+    fn apply_close_behavior(&mut self) -> Result<()> {
+        if self.close_dtr.is_none() && self.close_rts.is_none() {
+            return Ok(());
+        }
+
+        let mut set = ModemLines::empty();
+        let mut clear = ModemLines::empty();
+        for &(line, level) in &[
+            (ModemLines::DTR, self.close_dtr),
+            (ModemLines::RTS, self.close_rts),
+        ] {
+            match level {
+                Some(true) => set |= line,
+                Some(false) => clear |= line,
+                None => {}
+            }
+        }
+        // Clear `HUPCL` even if the lines can't be written, e.g. on a pseudo terminal
+        let lines = self.set_modem_lines_masked(set, clear);
 
-        assert!(ospeed == ispeed);
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_hangup_on_close(&mut termios, false);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        termios::set_termios(self.fd, &termios, self.baud_rate)?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_termios(self.fd, &termios)?;
+        lines
+    }
 
-        Ok(ospeed as u32)
+    /// Attempts to clone the `SerialPort`. This allow you to write and read simultaneously from the
+    /// same serial connection. Please note that if you want a real asynchronous serial port you
+    /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
+    /// [tokio-serial](https://crates.io/crates/tokio-serial).
+    ///
+    /// Also, you must be very careful when changing the settings of a cloned `SerialPort` : while
+    /// most settings are read from and written to the driver directly, the timeout is cached on a
+    /// per object basis, and modifying settings from two different objects can cause some nasty
+    /// behavior.
+    ///
+    /// This is the same as `SerialPort::try_clone()` but returns the concrete type instead.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the serial port couldn't be cloned.
+    pub fn try_clone_native(&self) -> Result<TTYPort> {
+        let fd_cloned: i32 = fcntl(self.fd, nix::fcntl::F_DUPFD_CLOEXEC(self.fd))?;
+        Ok(TTYPort {
+            fd: fd_cloned,
+            exclusive: self.exclusive,
+            port_name: self.port_name.clone(),
+            port_type: self.port_type.clone(),
+            reopen: self.reopen.clone(),
+            timeout: self.timeout,
+            counters_baseline: self.counters_baseline,
+            line_ending: self.line_ending,
+            write_delay: self.write_delay,
+            write_unbuffered: self.write_unbuffered,
+            max_read_size: self.max_read_size,
+            close_dtr: None,
+            close_rts: None,
+            rx_watermark: AtomicU32::new(self.rx_watermark.load(Ordering::Relaxed)),
+            baud_rate_tolerance: self.baud_rate_tolerance,
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            baud_rate: self.baud_rate,
+        })
+    }
+}
+
+impl Drop for TTYPort {
+    fn drop(&mut self) {
+        // There's no way to report a failure from here, the port is closed regardless
+        let _ = self.apply_close_behavior();
+        close(self.fd);
+    }
+}
+
+impl AsRawFd for TTYPort {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl IntoRawFd for TTYPort {
+    fn into_raw_fd(self) -> RawFd {
+        // Pull just the file descriptor out. We also prevent the destructor
+        // from being run by calling `mem::forget`. If we didn't do this, the
+        // port would be closed, which would make `into_raw_fd` unusable.
+        let TTYPort { fd, .. } = self;
+        mem::forget(self);
+        fd
+    }
+}
+
+/// Returns whether `fd` is either end of a pseudo terminal
+#[cfg(target_os = "linux")]
+fn is_pty(fd: RawFd) -> bool {
+    use nix::sys::stat::{fstat, major, minor};
+
+    match fstat(fd) {
+        // Unix98 slaves in `/dev/pts/` have majors 136 to 143 and `/dev/ptmx` is 5:2, legacy BSD
+        // masters and slaves have majors 2 and 3.
+        Ok(stat) => {
+            let (major, minor) = (major(stat.st_rdev), minor(stat.st_rdev));
+            matches!(major, 2 | 3 | 136..=143) || (major == 5 && minor == 2)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Returns whether `fd` is either end of a pseudo terminal
+#[cfg(not(target_os = "linux"))]
+fn is_pty(fd: RawFd) -> bool {
+    // Only masters have the name of a slave. The name is ignored, so it doesn't matter that
+    // `ptsname()` returns it in a shared buffer.
+    if !unsafe { libc::ptsname(fd) }.is_null() {
+        return true;
+    }
+
+    let mut name = [0 as libc::c_char; 128];
+    if unsafe { libc::ttyname_r(fd, name.as_mut_ptr(), name.len()) } != 0 {
+        return false;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }.to_bytes();
+    // Slaves are `/dev/pts/N` on most systems, `/dev/ttysNNN` on macOS and `/dev/ttypN` on
+    // systems with BSD-style ptys
+    ["/dev/pts/", "/dev/ttys", "/dev/ttyp"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix.as_bytes()))
+}
+
+/// Get the baud speed for a port from its file descriptor
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+fn get_termios_speed(fd: RawFd) -> u32 {
+    let mut termios = MaybeUninit::uninit();
+    let res = unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) };
+    nix::errno::Errno::result(res).expect("Failed to get termios data");
+    let termios = unsafe { termios.assume_init() };
+    assert_eq!(termios.c_ospeed, termios.c_ispeed);
+    termios.c_ospeed as u32
+}
+
+impl FromRawFd for TTYPort {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        TTYPort {
+            fd,
+            timeout: crate::REASONABLE_TIMEOUT,
+            exclusive: ioctl::tiocexcl(fd).is_ok(),
+            // It is not trivial to get the file path corresponding to a file descriptor.
+            // We'll punt on it and set it to `None` here.
+            port_name: None,
+            port_type: SerialPortType::Unknown,
+            reopen: ReopenState::default(),
+            counters_baseline: initial_counters(fd),
+            line_ending: LineEnding::None,
+            write_delay: Duration::from_secs(0),
+            write_unbuffered: false,
+            max_read_size: None,
+            close_dtr: None,
+            close_rts: None,
+            rx_watermark: AtomicU32::new(0),
+            baud_rate_tolerance: crate::BAUD_RATE_TOLERANCE_PERCENT,
+            // It's not guaranteed that the baud rate in the `termios` struct is correct, as
+            // setting an arbitrary baud rate via the `iossiospeed` ioctl overrides that value,
+            // but extract that value anyways as a best-guess of the actual baud rate.
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            baud_rate: get_termios_speed(fd),
+        }
+    }
+}
+
+impl io::Read for TTYPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // `read_uninit()` only ever writes initialized bytes into the buffer
+        let buf = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.read_uninit(buf).map_err(io::Error::from)
+    }
+}
+
+/// Converts an error from `read()`/`write()` on the port
+///
+/// A tty that has been hung up, e.g. because the USB adapter behind it was unplugged or the
+/// master side of a pty was closed, fails with `EIO`. Report it as `ErrorKind::NoDevice` like
+/// `ENXIO` and `ENODEV` so that reconnect logic only has to look at one kind.
+fn port_error(e: nix::Error) -> Error {
+    match e {
+        nix::errno::Errno::EIO => Error::new(ErrorKind::NoDevice, e.desc()),
+        e => Error::from(e),
+    }
+}
+
+/// Converts an error from `read()`/`write()` on the port into an `io::Error`, see `port_error()`
+fn io_error(e: nix::Error) -> io::Error {
+    io::Error::from(port_error(e))
+}
+
+impl TTYPort {
+    /// Writes the line settings of `new` on top of `original` and checks that they took effect
+    ///
+    /// Leaves restoring `original` on failure to the caller.
+    fn apply_settings(
+        &mut self,
+        original: &termios::Termios,
+        new: &SerialPortBuilder,
+    ) -> Result<()> {
+        let mut termios = *original;
+        termios::set_parity(&mut termios, new.parity);
+        termios::set_flow_control(&mut termios, new.flow_control);
+        termios::set_data_bits(&mut termios, new.data_bits);
+        termios::set_stop_bits(&mut termios, new.stop_bits);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        {
+            self.baud_rate = new.baud_rate;
+            termios::set_termios(self.fd, &termios, new.baud_rate)?;
+        }
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        {
+            termios::set_baud_rate(&mut termios, new.baud_rate);
+            termios::set_termios(self.fd, &termios)?;
+        }
+
+        check_achieved_baud_rate(self.fd, new.baud_rate, self.baud_rate_tolerance)?;
+
+        // `tcsetattr()` succeeds if any of the changes could be made, so read them back
+        let applied = termios::get_termios(self.fd)?;
+        if termios::get_data_bits(&applied)? != new.data_bits {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Device doesn't support this number of data bits",
+            ));
+        }
+        if termios::get_parity(&applied) != new.parity
+            || termios::get_stop_bits(&applied) != new.stop_bits
+            || termios::get_flow_control(&applied) != new.flow_control
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Device didn't accept the settings",
+            ));
+        }
+        Ok(())
+    }
+
+    fn write_unpaced(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Err(e) = super::poll::wait_write_fd(self.fd, self.timeout) {
+            return Err(io::Error::from(Error::from(e)));
+        }
+
+        nix::unistd::write(self.fd, buf).map_err(io_error)
+    }
+}
+
+impl io::Write for TTYPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.write_delay == Duration::from_secs(0) {
+            let written = self.write_unpaced(buf)?;
+            if self.write_unbuffered {
+                // The bytes are written already, so a failed drain is left to the next call
+                let _ = self.flush();
+            }
+            return Ok(written);
+        }
+        let delay = self.write_delay;
+        crate::write_paced(buf, delay, |byte| {
+            let written = self.write_unpaced(byte)?;
+            let _ = self.flush();
+            Ok(written)
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        nix::sys::termios::tcdrain(self.fd).map_err(|e| match e {
+            nix::errno::Errno::EIO | nix::errno::Errno::ENXIO | nix::errno::Errno::ENODEV => {
+                io_error(e)
+            }
+            _ => io::Error::new(io::ErrorKind::Other, "flush failed"),
+        })
+    }
+}
+
+impl SerialPort for TTYPort {
+    fn name(&self) -> Option<String> {
+        self.port_name.clone()
+    }
+
+    fn port_type(&self) -> SerialPortType {
+        self.port_type.clone()
+    }
+
+    fn is_virtual(&self) -> bool {
+        is_pty(self.fd)
     }
 
     /// Returns the port's baud rate
     ///
     /// On some platforms this will be the actual device baud rate, which may differ from the
     /// desired baud rate.
-    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    #[cfg(not(any(target_os = "ios", target_os = "macos")))]
     fn baud_rate(&self) -> Result<u32> {
-        Ok(self.baud_rate)
+        let termios = termios::get_termios(self.fd)?;
+        Ok(termios::get_baud_rate(&termios))
     }
 
     /// Returns the port's baud rate
     ///
     /// On some platforms this will be the actual device baud rate, which may differ from the
     /// desired baud rate.
-    #[cfg(all(
-        target_os = "linux",
-        any(
-            target_env = "musl",
-            target_arch = "powerpc",
-            target_arch = "powerpc64"
-        )
-    ))]
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
     fn baud_rate(&self) -> Result<u32> {
-        use self::libc::{
-            B1000000, B1152000, B1500000, B2000000, B2500000, B3000000, B3500000, B4000000,
-            B460800, B500000, B576000, B921600,
-        };
-        use self::libc::{
-            B110, B115200, B1200, B134, B150, B1800, B19200, B200, B230400, B2400, B300, B38400,
-            B4800, B50, B57600, B600, B75, B9600,
-        };
+        Ok(self.baud_rate)
+    }
 
+    fn baud_rate_info(&self) -> Result<BaudRateInfo> {
         let termios = termios::get_termios(self.fd)?;
-        let ospeed = unsafe { libc::cfgetospeed(&termios) };
-        let ispeed = unsafe { libc::cfgetispeed(&termios) };
-
-        assert!(ospeed == ispeed);
-
-        let res: u32 = match ospeed {
-            B50 => 50,
-            B75 => 75,
-            B110 => 110,
-            B134 => 134,
-            B150 => 150,
-            B200 => 200,
-            B300 => 300,
-            B600 => 600,
-            B1200 => 1200,
-            B1800 => 1800,
-            B2400 => 2400,
-            B4800 => 4800,
-            B9600 => 9600,
-            B19200 => 19_200,
-            B38400 => 38_400,
-            B57600 => 57_600,
-            B115200 => 115_200,
-            B230400 => 230_400,
-            B460800 => 460_800,
-            B500000 => 500_000,
-            B576000 => 576_000,
-            B921600 => 921_600,
-            B1000000 => 1_000_000,
-            B1152000 => 1_152_000,
-            B1500000 => 1_500_000,
-            B2000000 => 2_000_000,
-            B2500000 => 2_500_000,
-            B3000000 => 3_000_000,
-            B3500000 => 3_500_000,
-            B4000000 => 4_000_000,
-            _ => unreachable!(),
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        let (configured, actual) = {
+            let configured = termios::get_baud_rate(&termios);
+            (configured, Some(actual_baud_rate(self.fd, configured)))
         };
-
-        Ok(res)
+        // `IOSSIOSPEED` has no counterpart to read the speed back
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        let (configured, actual) = {
+            let _ = termios;
+            (self.baud_rate, None)
+        };
+        Ok(BaudRateInfo {
+            requested: self
+                .reopen
+                .settings
+                .as_ref()
+                .map_or(configured, |s| s.baud_rate),
+            actual,
+        })
     }
 
     fn data_bits(&self) -> Result<DataBits> {
         let termios = termios::get_termios(self.fd)?;
-        match termios.c_cflag & libc::CSIZE {
-            libc::CS8 => Ok(DataBits::Eight),
-            libc::CS7 => Ok(DataBits::Seven),
-            libc::CS6 => Ok(DataBits::Six),
-            libc::CS5 => Ok(DataBits::Five),
-            _ => Err(Error::new(
-                ErrorKind::Unknown,
-                "Invalid data bits setting encountered",
-            )),
-        }
+        termios::get_data_bits(&termios)
     }
 
     fn flow_control(&self) -> Result<FlowControl> {
         let termios = termios::get_termios(self.fd)?;
-        if termios.c_cflag & libc::CRTSCTS == libc::CRTSCTS {
-            Ok(FlowControl::Hardware)
-        } else if termios.c_iflag & (libc::IXON | libc::IXOFF) == (libc::IXON | libc::IXOFF) {
-            Ok(FlowControl::Software)
-        } else {
-            Ok(FlowControl::None)
-        }
+        Ok(termios::get_flow_control(&termios))
     }
 
     fn parity(&self) -> Result<Parity> {
         let termios = termios::get_termios(self.fd)?;
-        if termios.c_cflag & libc::PARENB == libc::PARENB {
-            if termios.c_cflag & libc::PARODD == libc::PARODD {
-                Ok(Parity::Odd)
-            } else {
-                Ok(Parity::Even)
-            }
-        } else {
-            Ok(Parity::None)
-        }
+        Ok(termios::get_parity(&termios))
     }
 
     fn stop_bits(&self) -> Result<StopBits> {
         let termios = termios::get_termios(self.fd)?;
-        if termios.c_cflag & libc::CSTOPB == libc::CSTOPB {
-            Ok(StopBits::Two)
-        } else {
-            Ok(StopBits::One)
-        }
+        Ok(termios::get_stop_bits(&termios))
     }
 
     fn timeout(&self) -> Duration {
         self.timeout
     }
 
+    fn timeout_resolution(&self) -> Duration {
+        // `poll()` takes milliseconds, and `wait_fd()` truncates the timeout to them for
+        // `ppoll()` as well
+        Duration::from_millis(1)
+    }
+
+    fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    fn settings(&self) -> Result<SerialPortBuilder> {
+        // Read the `termios` struct only once so that the returned settings are a consistent
+        // snapshot of the driver state.
+        let termios = termios::get_termios(self.fd)?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        let baud_rate = termios::get_baud_rate(&termios);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        let baud_rate = self.baud_rate;
+
+        Ok(
+            crate::new(self.port_name.clone().unwrap_or_default(), baud_rate)
+                .data_bits(termios::get_data_bits(&termios)?)
+                .flow_control(termios::get_flow_control(&termios))
+                .parity(termios::get_parity(&termios))
+                .stop_bits(termios::get_stop_bits(&termios))
+                .timeout(self.timeout)
+                .line_ending(self.line_ending),
+        )
+    }
+
     #[cfg(any(
         target_os = "android",
         target_os = "dragonflybsd",
@@ -620,8 +1561,15 @@ impl SerialPort for TTYPort {
     ))]
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
         let mut termios = termios::get_termios(self.fd)?;
+        let original = termios;
         termios::set_baud_rate(&mut termios, baud_rate);
-        termios::set_termios(self.fd, &termios)
+        termios::set_termios(self.fd, &termios)?;
+        if let Err(e) = check_achieved_baud_rate(self.fd, baud_rate, self.baud_rate_tolerance) {
+            termios::set_termios(self.fd, &original)?;
+            return Err(e);
+        }
+        self.reopen.update(|s| s.baud_rate = baud_rate);
+        Ok(())
     }
 
     // Mac OS needs special logic for setting arbitrary baud rates.
@@ -629,6 +1577,7 @@ impl SerialPort for TTYPort {
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
         ioctl::iossiospeed(self.fd, &(baud_rate as libc::speed_t))?;
         self.baud_rate = baud_rate;
+        self.reopen.update(|s| s.baud_rate = baud_rate);
         Ok(())
     }
 
@@ -636,75 +1585,375 @@ impl SerialPort for TTYPort {
         let mut termios = termios::get_termios(self.fd)?;
         termios::set_flow_control(&mut termios, flow_control);
         #[cfg(any(target_os = "ios", target_os = "macos"))]
-        return termios::set_termios(self.fd, &termios, self.baud_rate);
+        termios::set_termios(self.fd, &termios, self.baud_rate)?;
         #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-        return termios::set_termios(self.fd, &termios);
+        termios::set_termios(self.fd, &termios)?;
+        self.reopen.update(|s| s.flow_control = flow_control);
+        Ok(())
     }
 
     fn set_parity(&mut self, parity: Parity) -> Result<()> {
         let mut termios = termios::get_termios(self.fd)?;
         termios::set_parity(&mut termios, parity);
         #[cfg(any(target_os = "ios", target_os = "macos"))]
+        termios::set_termios(self.fd, &termios, self.baud_rate)?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_termios(self.fd, &termios)?;
+        self.reopen.update(|s| s.parity = parity);
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        crate::check_data_bits_supported(&self.port_type, data_bits)?;
+        let original = termios::get_termios(self.fd)?;
+        let mut termios = original;
+        termios::set_data_bits(&mut termios, data_bits);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        termios::set_termios(self.fd, &termios, self.baud_rate)?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_termios(self.fd, &termios)?;
+
+        // Drivers substitute unsupported sizes without failing, but report the substitute
+        if termios::get_data_bits(&termios::get_termios(self.fd)?)? != data_bits {
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            termios::set_termios(self.fd, &original, self.baud_rate)?;
+            #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+            termios::set_termios(self.fd, &original)?;
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Device doesn't support this number of data bits",
+            ));
+        }
+        self.reopen.update(|s| s.data_bits = data_bits);
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_stop_bits(&mut termios, stop_bits);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        termios::set_termios(self.fd, &termios, self.baud_rate)?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_termios(self.fd, &termios)?;
+        self.reopen.update(|s| s.stop_bits = stop_bits);
+        Ok(())
+    }
+
+    fn swap_settings(&mut self, new: &SerialPortBuilder) -> Result<SerialPortBuilder> {
+        crate::check_data_bits_supported(&self.port_type, new.data_bits)?;
+        let old = self.settings()?;
+        let original = termios::get_termios(self.fd)?;
+        if let Err(e) = self.apply_settings(&original, new) {
+            // Best effort: if the original settings can't be written back either, the device is
+            // most likely gone and the first error is the more useful one
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            {
+                let _ = termios::set_termios(self.fd, &original, old.baud_rate);
+                self.baud_rate = old.baud_rate;
+            }
+            #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+            let _ = termios::set_termios(self.fd, &original);
+            return Err(e);
+        }
+
+        self.timeout = new.timeout;
+        self.line_ending = new.line_ending;
+        self.reopen.record_settings(new);
+        Ok(old)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        self.reopen.update(|s| s.timeout = timeout);
+        Ok(())
+    }
+
+    fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+        self.reopen.update(|s| s.line_ending = line_ending);
+    }
+
+    fn set_read_mode(&mut self, min_bytes: u8, inter_byte_timeout: Duration) -> Result<()> {
+        crate::check_read_mode(min_bytes, inter_byte_timeout)?;
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_read_mode(&mut termios, min_bytes, inter_byte_timeout)?;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
         return termios::set_termios(self.fd, &termios, self.baud_rate);
         #[cfg(not(any(target_os = "ios", target_os = "macos")))]
         return termios::set_termios(self.fd, &termios);
     }
 
-    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+    fn inter_byte_write_delay(&self) -> Duration {
+        self.write_delay
+    }
+
+    fn set_inter_byte_write_delay(&mut self, delay: Duration) -> Result<()> {
+        self.write_delay = delay;
+        Ok(())
+    }
+
+    fn write_unbuffered(&self) -> bool {
+        self.write_unbuffered
+    }
+
+    fn set_write_unbuffered(&mut self, unbuffered: bool) -> Result<()> {
+        self.write_unbuffered = unbuffered;
+        Ok(())
+    }
+
+    fn max_read_size(&self) -> Option<usize> {
+        self.max_read_size
+    }
+
+    fn set_max_read_size(&mut self, max: Option<usize>) -> Result<()> {
+        crate::check_max_read_size(max)?;
+        self.max_read_size = max;
+        Ok(())
+    }
+
+    fn close_on_exec(&self) -> Result<bool> {
+        let flags = FdFlag::from_bits_truncate(fcntl(self.fd, nix::fcntl::F_GETFD)?);
+        Ok(flags.contains(FdFlag::FD_CLOEXEC))
+    }
+
+    fn set_close_on_exec(&mut self, cloexec: bool) -> Result<()> {
+        let mut flags = FdFlag::from_bits_truncate(fcntl(self.fd, nix::fcntl::F_GETFD)?);
+        flags.set(FdFlag::FD_CLOEXEC, cloexec);
+        fcntl(self.fd, nix::fcntl::F_SETFD(flags))?;
+        Ok(())
+    }
+
+    fn parity_check(&self) -> Result<bool> {
+        let termios = termios::get_termios(self.fd)?;
+        Ok(termios::get_parity_check(&termios))
+    }
+
+    fn set_parity_check(&mut self, enabled: bool) -> Result<()> {
         let mut termios = termios::get_termios(self.fd)?;
-        termios::set_data_bits(&mut termios, data_bits);
+        termios::set_parity_check(&mut termios, enabled);
         #[cfg(any(target_os = "ios", target_os = "macos"))]
         return termios::set_termios(self.fd, &termios, self.baud_rate);
         #[cfg(not(any(target_os = "ios", target_os = "macos")))]
         return termios::set_termios(self.fd, &termios);
     }
 
-    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+    fn set_binary_mode(&mut self, binary: bool) -> Result<()> {
         let mut termios = termios::get_termios(self.fd)?;
-        termios::set_stop_bits(&mut termios, stop_bits);
+        termios::set_binary_mode(&mut termios, binary);
         #[cfg(any(target_os = "ios", target_os = "macos"))]
         return termios::set_termios(self.fd, &termios, self.baud_rate);
         #[cfg(not(any(target_os = "ios", target_os = "macos")))]
         return termios::set_termios(self.fd, &termios);
     }
 
-    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
-        self.timeout = timeout;
+    fn set_overflow_policy(&mut self, policy: OverflowPolicy) -> Result<()> {
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_overflow_policy(&mut termios, policy)?;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        return termios::set_termios(self.fd, &termios, self.baud_rate);
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        return termios::set_termios(self.fd, &termios);
+    }
+
+    fn set_flow_control_passthrough(&mut self, passthrough: bool) -> Result<()> {
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_flow_control_passthrough(&mut termios, passthrough)?;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        return termios::set_termios(self.fd, &termios, self.baud_rate);
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        return termios::set_termios(self.fd, &termios);
+    }
+
+    fn save_flow_control(&self) -> Result<SavedFlowControl> {
+        let termios = termios::get_termios(self.fd)?;
+        Ok(SavedFlowControl {
+            flow_control: termios::get_flow_control(&termios),
+            raw: Some(termios::get_raw_flow_control(&termios)),
+        })
+    }
+
+    fn restore_flow_control(&mut self, saved: &SavedFlowControl) -> Result<()> {
+        let raw = match saved.raw {
+            Some(raw) => raw,
+            None => return self.set_flow_control(saved.flow_control),
+        };
+        let mut termios = termios::get_termios(self.fd)?;
+        termios::set_raw_flow_control(&mut termios, raw);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        termios::set_termios(self.fd, &termios, self.baud_rate)?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_termios(self.fd, &termios)?;
+        self.reopen.update(|s| s.flow_control = saved.flow_control);
         Ok(())
     }
 
-    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
-        self.set_pin(SerialLines::REQUEST_TO_SEND, level)
+    fn set_flow_watermarks(&mut self, _low: usize, _high: usize) -> Result<()> {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Flow control watermarks are not configurable on this platform",
+        ))
     }
 
-    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
-        self.set_pin(SerialLines::DATA_TERMINAL_READY, level)
+    fn modem_lines(&self) -> Result<ModemLines> {
+        Ok(modem_lines(ioctl::tiocmget(self.fd)?))
     }
 
-    fn read_clear_to_send(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::CLEAR_TO_SEND)
+    fn set_modem_lines_masked(&mut self, set: ModemLines, clear: ModemLines) -> Result<()> {
+        crate::check_modem_line_masks(set, clear)?;
+        // Unlike reading the lines and writing them back, these leave the other lines alone even
+        // if another handle changes them in between
+        if !set.is_empty() {
+            ioctl::tiocmbis(self.fd, serial_lines(set))?;
+        }
+        if !clear.is_empty() {
+            ioctl::tiocmbic(self.fd, serial_lines(clear))?;
+        }
+        self.reopen.record_lines(set, clear);
+        Ok(())
     }
 
-    fn read_data_set_ready(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::DATA_SET_READY)
+    fn set_close_behavior(&mut self, dtr: Option<bool>, rts: Option<bool>) -> Result<()> {
+        self.close_dtr = dtr;
+        self.close_rts = rts;
+        Ok(())
+    }
+
+    fn wait_for_modem_change(&mut self, timeout: Option<Duration>) -> Result<ModemStatus> {
+        let initial = self.read_modem_status()?;
+        #[cfg(target_os = "linux")]
+        {
+            if timeout.is_none() {
+                ioctl::tiocmiwait(
+                    self.fd,
+                    SerialLines::CLEAR_TO_SEND
+                        | SerialLines::DATA_SET_READY
+                        | SerialLines::RING
+                        | SerialLines::DATA_CARRIER_DETECT,
+                )?;
+                return self.read_modem_status();
+            }
+        }
+        crate::poll_modem_change(self, initial, timeout)
     }
 
-    fn read_ring_indicator(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::RING)
+    fn wait_reenumerate(&mut self, identity: &UsbIdentity, timeout: Duration) -> Result<()> {
+        *self = crate::reenumerate(self, identity, timeout, TTYPort::open)?;
+        Ok(())
     }
 
-    fn read_carrier_detect(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::DATA_CARRIER_DETECT)
+    fn reopen(&mut self) -> Result<()> {
+        let settings = match self.reopen.settings.clone() {
+            Some(settings) => settings,
+            None => self
+                .settings()
+                .ok()
+                .filter(|settings| !settings.path.is_empty())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        "The port wasn't opened from a path",
+                    )
+                })?,
+        };
+
+        // Close the old descriptor first, its exclusive lock would make the open fail. The
+        // invalid descriptor left behind is ignored when `self` is dropped or replaced.
+        let _ = self.apply_close_behavior();
+        close(self.fd);
+        self.fd = -1;
+
+        let mut port = TTYPort::open(&settings)?;
+        if !self.exclusive {
+            port.set_exclusive(false)?;
+        }
+        if let Some(level) = self.reopen.dtr {
+            port.write_data_terminal_ready(level)?;
+        }
+        if let Some(level) = self.reopen.rts {
+            port.write_request_to_send(level)?;
+        }
+        port.write_delay = self.write_delay;
+        port.write_unbuffered = self.write_unbuffered;
+        port.max_read_size = self.max_read_size;
+        port.close_dtr = self.close_dtr;
+        port.close_rts = self.close_rts;
+        port.baud_rate_tolerance = self.baud_rate_tolerance;
+        *self = port;
+        Ok(())
     }
 
     fn bytes_to_read(&self) -> Result<u32> {
-        ioctl::fionread(self.fd)
+        let bytes = ioctl::fionread(self.fd)?;
+        self.rx_watermark.fetch_max(bytes, Ordering::Relaxed);
+        Ok(bytes)
+    }
+
+    fn has_data(&self, timeout: Duration) -> Result<bool> {
+        match super::poll::wait_read_fd(self.fd, timeout) {
+            Ok(()) => Ok(true),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn rx_watermark(&self) -> u32 {
+        self.rx_watermark.load(Ordering::Relaxed)
+    }
+
+    fn reset_rx_watermark(&mut self) {
+        self.rx_watermark.store(0, Ordering::Relaxed);
     }
 
     fn bytes_to_write(&self) -> Result<u32> {
         ioctl::tiocoutq(self.fd)
     }
 
+    fn rx_buffer_capacity(&self) -> Result<u32> {
+        Err(Error::new(
+            ErrorKind::Unknown,
+            "The input buffer size is not available on this platform",
+        ))
+    }
+
+    fn tx_buffer_capacity(&self) -> Result<u32> {
+        Err(Error::new(
+            ErrorKind::Unknown,
+            "The output buffer size is not available on this platform",
+        ))
+    }
+
+    fn queue_status(&self) -> Result<QueueStatus> {
+        Ok(QueueStatus {
+            bytes_to_read: self.bytes_to_read()?,
+            bytes_to_write: self.bytes_to_write()?,
+            rx_capacity: None,
+            tx_capacity: None,
+        })
+    }
+
+    fn capabilities(&self) -> Result<PortCapabilities> {
+        // Fail like the other getters if the device is gone
+        termios::get_termios(self.fd)?;
+        let mut capabilities = crate::chipset_capabilities(&self.port_type);
+        if ARBITRARY_BAUD_RATES.is_some() {
+            capabilities.arbitrary_baud_rates = ARBITRARY_BAUD_RATES;
+        }
+        Ok(capabilities)
+    }
+
+    fn supported_operations(&self) -> SupportedOps {
+        #[allow(unused_mut)]
+        let mut ops = crate::common_supported_operations(self);
+        // Drivers without a UART behind them, like USB CDC-ACM, don't implement `TIOCGSERIAL`
+        #[cfg(target_os = "linux")]
+        {
+            if ioctl::tiocgserial(self.fd).is_ok() {
+                ops |= SupportedOps::CUSTOM_DIVISOR;
+            }
+        }
+        ops
+    }
+
     fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
         let buffer_id = match buffer_to_clear {
             ClearBuffer::Input => libc::TCIFLUSH,
@@ -719,6 +1968,25 @@ impl SerialPort for TTYPort {
             .map_err(|e| e.into())
     }
 
+    #[cfg(target_os = "linux")]
+    fn counters(&self) -> Result<LineCounters> {
+        ioctl::tiocgicount(self.fd).map(|c| c.since(&self.counters_baseline))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn counters(&self) -> Result<LineCounters> {
+        Err(Error::new(
+            ErrorKind::Unknown,
+            "Line counters are not supported on this platform",
+        ))
+    }
+
+    fn reset_counters(&mut self) -> Result<()> {
+        self.counters_baseline = LineCounters::default();
+        self.counters_baseline = self.counters()?;
+        Ok(())
+    }
+
     fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
         match self.try_clone_native() {
             Ok(p) => Ok(Box::new(p)),
@@ -735,6 +2003,130 @@ impl SerialPort for TTYPort {
     }
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+fn test_closing_wait_raw() {
+    assert_eq!(ClosingWait::from_raw(0), ClosingWait::Forever);
+    assert_eq!(ClosingWait::from_raw(65535), ClosingWait::None);
+    assert_eq!(
+        ClosingWait::from_raw(3000),
+        ClosingWait::Duration(Duration::from_secs(30))
+    );
+
+    assert_eq!(ClosingWait::Forever.to_raw().unwrap(), 0);
+    assert_eq!(ClosingWait::None.to_raw().unwrap(), 65535);
+    assert_eq!(
+        ClosingWait::Duration(Duration::from_millis(1))
+            .to_raw()
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        ClosingWait::Duration(Duration::from_millis(655_340))
+            .to_raw()
+            .unwrap(),
+        65534
+    );
+    for duration in [Duration::from_secs(0), Duration::from_millis(655_341)].iter() {
+        let err = ClosingWait::Duration(*duration).to_raw().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn test_decode_parity_marks() {
+    let mut buf = [0; 8];
+    let mut bits = [false; 8];
+
+    // 0x01 has one bit set, so its even parity bit is 1 and its odd parity bit is 0
+    let raw = [0x01, 0xff, 0x00, 0x01, 0xff, 0xff, 0x03];
+    assert_eq!(
+        decode_parity_marks(&raw, false, &mut buf, &mut bits),
+        (7, 4)
+    );
+    assert_eq!(&buf[..4], &[0x01, 0x01, 0xff, 0x03]);
+    assert_eq!(&bits[..4], &[true, false, false, false]);
+    assert_eq!(decode_parity_marks(&raw, true, &mut buf, &mut bits), (7, 4));
+    assert_eq!(&bits[..4], &[false, true, true, true]);
+
+    // A mark cut off at the end is left for the next read
+    assert_eq!(
+        decode_parity_marks(&[0x01, 0xff, 0x00], false, &mut buf, &mut bits),
+        (1, 1)
+    );
+    assert_eq!(
+        decode_parity_marks(&[0xff], false, &mut buf, &mut bits),
+        (0, 0)
+    );
+
+    // Decoding stops once `buf` is full
+    assert_eq!(
+        decode_parity_marks(&raw, false, &mut buf[..2], &mut bits),
+        (4, 2)
+    );
+}
+
+#[test]
+fn test_read_parity_marks() {
+    use std::collections::VecDeque;
+
+    /// Returns one chunk per read, like a port that received them one at a time
+    struct ChunkedReader(VecDeque<Vec<u8>>);
+
+    impl io::Read for ChunkedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut chunk = match self.0.pop_front() {
+                Some(chunk) => chunk,
+                None => return Err(io::Error::new(io::ErrorKind::TimedOut, "No data")),
+            };
+            let n = chunk.len().min(buf.len());
+            buf[..n].copy_from_slice(&chunk[..n]);
+            chunk.drain(..n);
+            if !chunk.is_empty() {
+                self.0.push_front(chunk);
+            }
+            Ok(n)
+        }
+    }
+
+    let mut buf = [0; 4];
+    let mut bits = [false; 4];
+
+    // Nothing is left over, so a single read is made
+    let mut source = ChunkedReader(vec![vec![0x01, 0x03], vec![0x07]].into());
+    assert_eq!(
+        read_parity_marks(&mut source, false, &mut buf, &mut bits).unwrap(),
+        2
+    );
+    assert_eq!(&buf[..2], &[0x01, 0x03]);
+    assert_eq!(&bits[..2], &[true, false]);
+    assert_eq!(source.0.len(), 1);
+
+    // A mark split across three reads is completed
+    let mut source = ChunkedReader(vec![vec![0x03, 0xff], vec![0x00], vec![0x01]].into());
+    assert_eq!(
+        read_parity_marks(&mut source, false, &mut buf, &mut bits).unwrap(),
+        2
+    );
+    assert_eq!(&buf[..2], &[0x03, 0x01]);
+    assert_eq!(&bits[..2], &[false, false]);
+    assert!(source.0.is_empty());
+
+    // So is an escaped 0xff, without reading past it
+    let mut source = ChunkedReader(vec![vec![0xff], vec![0xff, 0x01]].into());
+    assert_eq!(
+        read_parity_marks(&mut source, true, &mut buf, &mut bits).unwrap(),
+        1
+    );
+    assert_eq!((buf[0], bits[0]), (0xff, true));
+    assert_eq!(source.0, VecDeque::from(vec![vec![0x01]]));
+
+    // A mark that isn't completed before the timeout fails the read
+    let mut source = ChunkedReader(vec![vec![0x01, 0xff, 0x00]].into());
+    let err = read_parity_marks(&mut source, false, &mut buf, &mut bits).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+}
+
 #[test]
 fn test_ttyport_into_raw_fd() {
     // `master` must be used here as Dropping it causes slave to be deleted by the OS.
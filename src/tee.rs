@@ -0,0 +1,443 @@
+//! A `SerialPort` wrapper recording all traffic for debugging
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{
+    BaudRateInfo, ClearBuffer, DataBits, FlowControl, LineCounters, LineEnding, ModemLines,
+    ModemStatus, OverflowPolicy, Parity, PortCapabilities, QueueStatus, Result, SavedFlowControl,
+    SerialPort, SerialPortBuilder, SerialPortType, StopBits, SupportedOps, UsbIdentity,
+};
+
+/// Number of bytes per line of a data dump
+const BYTES_PER_LINE: usize = 16;
+
+/// A serial port that logs all data and control signal changes to a sink
+///
+/// Every call is forwarded to the wrapped port, so protocol code can use a `TeePort` in place of
+/// the port itself. Alongside, a timestamped log is written to the sink: a hex and ASCII dump of
+/// all bytes read (`RX`) and written (`TX`), failed reads other than timeouts, and a note for
+/// every change of RTS, DTR, the break condition and the line settings. Timestamps are the
+/// seconds since the `TeePort` was created:
+///
+/// ```text
+/// [    0.000012] RTS on
+/// [    0.001043] TX 41 54 0d                                        |AT.|
+/// [    0.013877] RX 0d 0a 4f 4b 0d 0a                               |..OK..|
+/// ```
+///
+/// Errors writing to the sink are ignored, as the log must not affect the protocol. Ports
+/// returned by `try_clone()` are clones of the wrapped port, so their traffic isn't logged.
+pub struct TeePort<W: Write + Send> {
+    port: Box<dyn SerialPort>,
+    sink: Mutex<W>,
+    start: Instant,
+}
+
+impl<W: Write + Send> TeePort<W> {
+    /// Wraps `port`, logging its traffic to `sink`
+    pub fn new(port: Box<dyn SerialPort>, sink: W) -> Self {
+        TeePort {
+            port,
+            sink: Mutex::new(sink),
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns the wrapped port and the sink
+    pub fn into_inner(self) -> (Box<dyn SerialPort>, W) {
+        let sink = match self.sink.into_inner() {
+            Ok(sink) => sink,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        (self.port, sink)
+    }
+
+    fn note(&self, message: fmt::Arguments) {
+        let elapsed = self.start.elapsed();
+        if let Ok(mut sink) = self.sink.lock() {
+            let _ = writeln!(sink, "[{}] {}", format_timestamp(elapsed), message);
+        }
+    }
+
+    fn dump(&self, direction: &str, data: &[u8]) {
+        for chunk in data.chunks(BYTES_PER_LINE) {
+            self.note(format_args!("{} {}", direction, format_chunk(chunk)));
+        }
+    }
+
+    /// Notes `message` if `result` is a success, and passes `result` on
+    fn note_ok(&self, result: Result<()>, message: fmt::Arguments) -> Result<()> {
+        if result.is_ok() {
+            self.note(message);
+        }
+        result
+    }
+}
+
+fn format_timestamp(elapsed: Duration) -> String {
+    format!("{:5}.{:06}", elapsed.as_secs(), elapsed.subsec_micros())
+}
+
+/// Formats up to `BYTES_PER_LINE` bytes as hex, followed by their printable ASCII characters
+fn format_chunk(chunk: &[u8]) -> String {
+    let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+    let ascii: String = chunk
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    format!(
+        "{:width$} |{}|",
+        hex.join(" "),
+        ascii,
+        width = BYTES_PER_LINE * 3 - 1
+    )
+}
+
+impl<W: Write + Send> fmt::Debug for TeePort<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TeePort")
+            .field("port", &self.port.name())
+            .finish()
+    }
+}
+
+impl<W: Write + Send> io::Read for TeePort<W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.port.read(buf) {
+            Ok(n) => {
+                self.dump("RX", &buf[..n]);
+                Ok(n)
+            }
+            Err(e) => {
+                if e.kind() != io::ErrorKind::TimedOut {
+                    self.note(format_args!("RX error: {}", e));
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<W: Write + Send> io::Write for TeePort<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.port.write(buf)?;
+        self.dump("TX", &buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl<W: Write + Send> SerialPort for TeePort<W> {
+    fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    fn port_type(&self) -> SerialPortType {
+        self.port.port_type()
+    }
+
+    fn is_virtual(&self) -> bool {
+        self.port.is_virtual()
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        self.port.baud_rate()
+    }
+
+    fn baud_rate_info(&self) -> Result<BaudRateInfo> {
+        self.port.baud_rate_info()
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        self.port.data_bits()
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        self.port.flow_control()
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        self.port.parity()
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        self.port.stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+
+    fn timeout_resolution(&self) -> Duration {
+        self.port.timeout_resolution()
+    }
+
+    fn line_ending(&self) -> LineEnding {
+        self.port.line_ending()
+    }
+
+    fn settings(&self) -> Result<SerialPortBuilder> {
+        self.port.settings()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        let result = self.port.set_baud_rate(baud_rate);
+        self.note_ok(result, format_args!("baud rate {}", baud_rate))
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        let result = self.port.set_data_bits(data_bits);
+        self.note_ok(result, format_args!("data bits {:?}", data_bits))
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        let result = self.port.set_flow_control(flow_control);
+        self.note_ok(result, format_args!("flow control {:?}", flow_control))
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        let result = self.port.set_parity(parity);
+        self.note_ok(result, format_args!("parity {:?}", parity))
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        let result = self.port.set_stop_bits(stop_bits);
+        self.note_ok(result, format_args!("stop bits {:?}", stop_bits))
+    }
+
+    fn swap_settings(&mut self, new: &SerialPortBuilder) -> Result<SerialPortBuilder> {
+        let old = self.port.swap_settings(new)?;
+        self.note(format_args!(
+            "settings {} {:?} {:?} {:?} {:?}",
+            new.baud_rate, new.data_bits, new.parity, new.stop_bits, new.flow_control
+        ));
+        Ok(old)
+    }
+
+    fn parity_check(&self) -> Result<bool> {
+        self.port.parity_check()
+    }
+
+    fn set_parity_check(&mut self, enabled: bool) -> Result<()> {
+        let result = self.port.set_parity_check(enabled);
+        self.note_ok(result, format_args!("parity check {}", on_off(enabled)))
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.port.set_timeout(timeout)
+    }
+
+    fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.port.set_line_ending(line_ending)
+    }
+
+    fn set_read_mode(&mut self, min_bytes: u8, inter_byte_timeout: Duration) -> Result<()> {
+        self.port.set_read_mode(min_bytes, inter_byte_timeout)
+    }
+
+    fn inter_byte_write_delay(&self) -> Duration {
+        self.port.inter_byte_write_delay()
+    }
+
+    fn set_inter_byte_write_delay(&mut self, delay: Duration) -> Result<()> {
+        self.port.set_inter_byte_write_delay(delay)
+    }
+
+    fn write_unbuffered(&self) -> bool {
+        self.port.write_unbuffered()
+    }
+
+    fn set_write_unbuffered(&mut self, unbuffered: bool) -> Result<()> {
+        self.port.set_write_unbuffered(unbuffered)
+    }
+
+    fn max_read_size(&self) -> Option<usize> {
+        self.port.max_read_size()
+    }
+
+    fn set_max_read_size(&mut self, max: Option<usize>) -> Result<()> {
+        self.port.set_max_read_size(max)
+    }
+
+    fn close_on_exec(&self) -> Result<bool> {
+        self.port.close_on_exec()
+    }
+
+    fn set_close_on_exec(&mut self, cloexec: bool) -> Result<()> {
+        self.port.set_close_on_exec(cloexec)
+    }
+
+    fn set_binary_mode(&mut self, binary: bool) -> Result<()> {
+        self.port.set_binary_mode(binary)
+    }
+
+    fn set_flow_watermarks(&mut self, low: usize, high: usize) -> Result<()> {
+        self.port.set_flow_watermarks(low, high)
+    }
+
+    fn set_overflow_policy(&mut self, policy: OverflowPolicy) -> Result<()> {
+        let result = self.port.set_overflow_policy(policy);
+        self.note_ok(result, format_args!("overflow policy {:?}", policy))
+    }
+
+    fn set_flow_control_passthrough(&mut self, passthrough: bool) -> Result<()> {
+        let result = self.port.set_flow_control_passthrough(passthrough);
+        self.note_ok(
+            result,
+            format_args!("flow control passthrough {}", passthrough),
+        )
+    }
+
+    fn save_flow_control(&self) -> Result<SavedFlowControl> {
+        self.port.save_flow_control()
+    }
+
+    fn restore_flow_control(&mut self, saved: &SavedFlowControl) -> Result<()> {
+        let result = self.port.restore_flow_control(saved);
+        self.note_ok(
+            result,
+            format_args!("flow control {:?} restored", saved.flow_control),
+        )
+    }
+
+    fn modem_lines(&self) -> Result<ModemLines> {
+        self.port.modem_lines()
+    }
+
+    fn set_modem_lines_masked(&mut self, set: ModemLines, clear: ModemLines) -> Result<()> {
+        let result = self.port.set_modem_lines_masked(set, clear);
+        if result.is_ok() {
+            for &(line, name) in &[(ModemLines::RTS, "RTS"), (ModemLines::DTR, "DTR")] {
+                if set.contains(line) {
+                    self.note(format_args!("{} on", name));
+                } else if clear.contains(line) {
+                    self.note(format_args!("{} off", name));
+                }
+            }
+        }
+        result
+    }
+
+    fn set_close_behavior(&mut self, dtr: Option<bool>, rts: Option<bool>) -> Result<()> {
+        self.port.set_close_behavior(dtr, rts)
+    }
+
+    fn wait_for_modem_change(&mut self, timeout: Option<Duration>) -> Result<ModemStatus> {
+        self.port.wait_for_modem_change(timeout)
+    }
+
+    fn wait_reenumerate(&mut self, identity: &UsbIdentity, timeout: Duration) -> Result<()> {
+        self.port.wait_reenumerate(identity, timeout)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        let result = self.port.reopen();
+        self.note_ok(result, format_args!("reopened"))
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        self.port.bytes_to_read()
+    }
+
+    fn has_data(&self, timeout: Duration) -> Result<bool> {
+        self.port.has_data(timeout)
+    }
+
+    fn rx_watermark(&self) -> u32 {
+        self.port.rx_watermark()
+    }
+
+    fn reset_rx_watermark(&mut self) {
+        self.port.reset_rx_watermark()
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        self.port.bytes_to_write()
+    }
+
+    fn rx_buffer_capacity(&self) -> Result<u32> {
+        self.port.rx_buffer_capacity()
+    }
+
+    fn tx_buffer_capacity(&self) -> Result<u32> {
+        self.port.tx_buffer_capacity()
+    }
+
+    fn queue_status(&self) -> Result<QueueStatus> {
+        self.port.queue_status()
+    }
+
+    fn capabilities(&self) -> Result<PortCapabilities> {
+        self.port.capabilities()
+    }
+
+    fn supported_operations(&self) -> SupportedOps {
+        self.port.supported_operations()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        let result = self.port.clear(buffer_to_clear);
+        self.note_ok(result, format_args!("cleared {:?}", buffer_to_clear))
+    }
+
+    fn counters(&self) -> Result<LineCounters> {
+        self.port.counters()
+    }
+
+    fn reset_counters(&mut self) -> Result<()> {
+        self.port.reset_counters()
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        self.port.try_clone()
+    }
+
+    fn set_break(&self) -> Result<()> {
+        let result = self.port.set_break();
+        self.note_ok(result, format_args!("break on"))
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        let result = self.port.clear_break();
+        self.note_ok(result, format_args!("break off"))
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[test]
+fn test_format_chunk() {
+    assert_eq!(
+        format_chunk(b"AT\r"),
+        "41 54 0d                                        |AT.|"
+    );
+    let full = format_chunk(b"0123456789abcdef");
+    assert!(full.starts_with("30 31 32"));
+    assert!(full.ends_with("66 |0123456789abcdef|"));
+}
+
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(
+        format_timestamp(Duration::from_micros(1_000_012)),
+        "    1.000012"
+    );
+}
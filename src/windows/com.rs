@@ -1,24 +1,253 @@
 use std::mem::MaybeUninit;
 use std::os::windows::prelude::*;
-use std::time::Duration;
-use std::{io, ptr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{fmt, io, ptr, thread};
 
+use bitflags::bitflags;
 use winapi::shared::minwindef::*;
+use winapi::shared::winerror::ERROR_OPERATION_ABORTED;
 use winapi::um::commapi::*;
+use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::fileapi::*;
 use winapi::um::handleapi::*;
+use winapi::um::ioapiset::CancelSynchronousIo;
 use winapi::um::processthreadsapi::GetCurrentProcess;
 use winapi::um::winbase::*;
 use winapi::um::winnt::{
-    DUPLICATE_SAME_ACCESS, FILE_ATTRIBUTE_NORMAL, GENERIC_READ, GENERIC_WRITE, HANDLE, MAXDWORD,
+    DUPLICATE_SAME_ACCESS, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ,
+    GENERIC_WRITE, HANDLE, MAXDWORD,
 };
 
 use crate::windows::dcb;
 use crate::{
-    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, Result, SerialPort,
-    SerialPortBuilder, StopBits,
+    BaudRateInfo, ClearBuffer, DataBits, Error, ErrorKind, FlowControl, LineCounters, LineEnding,
+    ModemLines, ModemStatus, OverflowPolicy, Parity, PortCapabilities, QueueStatus, ReopenState,
+    Result, SavedFlowControl, SerialPort, SerialPortBuilder, SerialPortType, StopBits,
+    SupportedOps, UsbIdentity,
 };
 
+/// Services of drivers that only provide virtual ports
+const VIRTUAL_PORT_DRIVERS: [&str; 1] = ["com0com"];
+
+/// Reads the input control signals of a port.
+fn modem_inputs(handle: HANDLE) -> Result<ModemLines> {
+    let mut status: DWORD = 0;
+    if unsafe { GetCommModemStatus(handle, &mut status) } == 0 {
+        return Err(super::error::last_os_error());
+    }
+
+    let mut lines = ModemLines::empty();
+    lines.set(ModemLines::CTS, status & MS_CTS_ON != 0);
+    lines.set(ModemLines::DSR, status & MS_DSR_ON != 0);
+    lines.set(ModemLines::RI, status & MS_RING_ON != 0);
+    lines.set(ModemLines::CD, status & MS_RLSD_ON != 0);
+    Ok(lines)
+}
+
+/// Reads the input control signals of the port at `path` without configuring it.
+///
+/// The port is closed right away. Returns `None` if the port can't be opened, e.g. because
+/// another process holds it, or its signals can't be read.
+pub(crate) fn probe_modem_inputs(path: &str) -> Option<ModemLines> {
+    let name = device_path(path);
+    let handle = unsafe {
+        CreateFileW(
+            name.as_ptr(),
+            GENERIC_READ,
+            0,
+            ptr::null_mut(),
+            OPEN_EXISTING,
+            FILE_ATTRIBUTE_NORMAL,
+            0 as HANDLE,
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return None;
+    }
+    let lines = modem_inputs(handle).ok();
+    unsafe { CloseHandle(handle) };
+    lines
+}
+
+/// Converts a port name into the nul-terminated wide string passed to `CreateFileW`.
+///
+/// Names that are already device paths (starting with `\\`) are used as-is, everything else is
+/// prefixed with the `\\.\` device namespace. The prefix is required for COM ports numbered
+/// above 9.
+fn device_path(port_name: &str) -> Vec<u16> {
+    let mut name = Vec::<u16>::with_capacity(4 + port_name.len() + 1);
+
+    if !port_name.starts_with(r"\\") {
+        name.extend(r"\\.\".encode_utf16());
+    }
+    name.extend(port_name.encode_utf16());
+    name.push(0);
+    name
+}
+
+bitflags! {
+    /// Events that can be waited for with `COMPort::wait_comm_event()`
+    pub struct CommEventMask: DWORD {
+        /// A character was received and placed in the input buffer
+        const RXCHAR = EV_RXCHAR;
+        /// The event character was received and placed in the input buffer
+        const RXFLAG = EV_RXFLAG;
+        /// The last character in the output buffer was sent
+        const TXEMPTY = EV_TXEMPTY;
+        /// The CTS signal changed state
+        const CTS = EV_CTS;
+        /// The DSR signal changed state
+        const DSR = EV_DSR;
+        /// The carrier detect (RLSD) signal changed state
+        const RLSD = EV_RLSD;
+        /// A break was detected on input
+        const BREAK = EV_BREAK;
+        /// A framing, overrun or parity error occurred
+        const ERR = EV_ERR;
+        /// A ring indicator was detected
+        const RING = EV_RING;
+    }
+}
+
+/// Who drives the RTS or DTR line of a `COMPort`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DtrRtsControl {
+    /// The line is deasserted, and can be controlled manually
+    Disable,
+    /// The line is asserted, and can be controlled manually
+    Enable,
+    /// The line is driven by the driver for hardware flow control
+    Handshake,
+    /// RTS is asserted while bytes are being transmitted, as needed by RS-485 transceivers
+    ///
+    /// This is only valid for RTS.
+    Toggle,
+}
+
+/// The timeouts of a COM port, as passed to `SetCommTimeouts()`
+///
+/// All values are in milliseconds, and 0 disables a timeout. A read times out after
+/// `read_total_timeout_multiplier` times the number of bytes requested plus
+/// `read_total_timeout_constant`, or once `read_interval_timeout` passes between two bytes.
+/// Writes time out likewise. See the documentation of `COMMTIMEOUTS` for the special meaning of
+/// `MAXDWORD`.
+///
+/// See [`COMPort::set_comm_timeouts`](struct.COMPort.html#method.set_comm_timeouts).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CommTimeouts {
+    /// Longest time allowed between two received bytes
+    pub read_interval_timeout: u32,
+    /// Time added to the total read timeout for each byte requested
+    pub read_total_timeout_multiplier: u32,
+    /// Time added to the total read timeout once per read
+    pub read_total_timeout_constant: u32,
+    /// Time added to the total write timeout for each byte written
+    pub write_total_timeout_multiplier: u32,
+    /// Time added to the total write timeout once per write
+    pub write_total_timeout_constant: u32,
+}
+
+impl CommTimeouts {
+    fn from_raw(timeouts: &COMMTIMEOUTS) -> CommTimeouts {
+        CommTimeouts {
+            read_interval_timeout: timeouts.ReadIntervalTimeout,
+            read_total_timeout_multiplier: timeouts.ReadTotalTimeoutMultiplier,
+            read_total_timeout_constant: timeouts.ReadTotalTimeoutConstant,
+            write_total_timeout_multiplier: timeouts.WriteTotalTimeoutMultiplier,
+            write_total_timeout_constant: timeouts.WriteTotalTimeoutConstant,
+        }
+    }
+
+    fn to_raw(self) -> COMMTIMEOUTS {
+        COMMTIMEOUTS {
+            ReadIntervalTimeout: self.read_interval_timeout,
+            ReadTotalTimeoutMultiplier: self.read_total_timeout_multiplier,
+            ReadTotalTimeoutConstant: self.read_total_timeout_constant,
+            WriteTotalTimeoutMultiplier: self.write_total_timeout_multiplier,
+            WriteTotalTimeoutConstant: self.write_total_timeout_constant,
+        }
+    }
+}
+
+/// A complete `DCB` and `COMMTIMEOUTS` configuration, applied verbatim by
+/// `COMPort::open_with_raw_config()`
+///
+/// **This is an advanced, platform-specific escape hatch.** Both structs are passed to the
+/// driver exactly as given, without the settings the crate normally applies, so anything
+/// `COMPort` assumes about the port may not hold. Use `new()` to start from structs built
+/// elsewhere, or `from_port()` to capture the configuration of an open port, and `dcb_mut()` and
+/// `timeouts_mut()` to adjust individual fields. Note that `from_port()` captures the port as
+/// configured by opening it, so it already includes the settings of the builder.
+#[derive(Clone, Copy)]
+pub struct RawPortConfig {
+    dcb: DCB,
+    timeouts: COMMTIMEOUTS,
+}
+
+impl RawPortConfig {
+    /// Creates a configuration applying `dcb` and `timeouts`
+    ///
+    /// `dcb` is passed on as given, so its `DCBlength` must be set to the size of the struct.
+    pub fn new(dcb: DCB, timeouts: COMMTIMEOUTS) -> RawPortConfig {
+        RawPortConfig { dcb, timeouts }
+    }
+
+    /// Captures the current configuration of `port`
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn from_port(port: &COMPort) -> Result<RawPortConfig> {
+        let mut timeouts: COMMTIMEOUTS = unsafe { MaybeUninit::zeroed().assume_init() };
+        if unsafe { GetCommTimeouts(port.handle, &mut timeouts) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(RawPortConfig {
+            dcb: dcb::get_dcb(port.handle)?,
+            timeouts,
+        })
+    }
+
+    /// Returns the `DCB` struct that will be applied
+    pub fn dcb(&self) -> &DCB {
+        &self.dcb
+    }
+
+    /// Returns the `DCB` struct that will be applied for modification
+    pub fn dcb_mut(&mut self) -> &mut DCB {
+        &mut self.dcb
+    }
+
+    /// Returns the `COMMTIMEOUTS` struct that will be applied
+    pub fn timeouts(&self) -> &COMMTIMEOUTS {
+        &self.timeouts
+    }
+
+    /// Returns the `COMMTIMEOUTS` struct that will be applied for modification
+    pub fn timeouts_mut(&mut self) -> &mut COMMTIMEOUTS {
+        &mut self.timeouts
+    }
+}
+
+impl fmt::Debug for RawPortConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RawPortConfig")
+            .field("BaudRate", &self.dcb.BaudRate)
+            .field("ByteSize", &self.dcb.ByteSize)
+            .field("Parity", &self.dcb.Parity)
+            .field("StopBits", &self.dcb.StopBits)
+            .field("ReadIntervalTimeout", &self.timeouts.ReadIntervalTimeout)
+            .field(
+                "ReadTotalTimeoutConstant",
+                &self.timeouts.ReadTotalTimeoutConstant,
+            )
+            .finish()
+    }
+}
+
 /// A serial port implementation for Windows COM ports
 ///
 /// The port will be closed when the value is dropped. However, this struct
@@ -29,7 +258,17 @@ use crate::{
 pub struct COMPort {
     handle: HANDLE,
     timeout: Duration,
+    inter_byte_timeout: Duration,
+    line_ending: LineEnding,
+    write_delay: Duration,
+    write_unbuffered: bool,
+    max_read_size: Option<usize>,
+    close_dtr: Option<bool>,
+    close_rts: Option<bool>,
+    rx_watermark: AtomicU32,
     port_name: Option<String>,
+    port_type: SerialPortType,
+    reopen: ReopenState,
 }
 
 unsafe impl Send for COMPort {}
@@ -37,7 +276,12 @@ unsafe impl Send for COMPort {}
 impl COMPort {
     /// Opens a COM port as a serial device.
     ///
-    /// `port` should be the name of a COM port, e.g., `COM1`.
+    /// `port` should be the name of a COM port, e.g., `COM1`. Names are looked up in the `\\.\`
+    /// device namespace, so other devices like `CNCA0` (com0com) or `AUX` can be opened the same
+    /// way. Full device paths such as `\\.\COM10` or `\\?\` device interface paths (as used by
+    /// some USB CDC and virtual port drivers) are passed to the OS unchanged.
+    ///
+    /// The port is opened exclusively unless `SerialPortBuilder::shared()` was set.
     ///
     /// If the COM port handle needs to be opened with special flags, use
     /// `from_raw_handle` method to create the `COMPort`. Note that you should
@@ -50,17 +294,21 @@ impl COMPort {
     /// * `InvalidInput` if `port` is not a valid device name.
     /// * `Io` for any other I/O error while opening or initializing the device.
     pub fn open(builder: &SerialPortBuilder) -> Result<COMPort> {
-        let mut name = Vec::<u16>::with_capacity(4 + builder.path.len() + 1);
+        let port_type = crate::lookup_port_type(&builder.path);
+        crate::check_data_bits_supported(&port_type, builder.data_bits)?;
 
-        name.extend(r"\\.\".encode_utf16());
-        name.extend(builder.path.encode_utf16());
-        name.push(0);
+        let name = device_path(&builder.path);
+        let share_mode = if builder.shared {
+            FILE_SHARE_READ | FILE_SHARE_WRITE
+        } else {
+            0
+        };
 
         let handle = unsafe {
             CreateFileW(
                 name.as_ptr(),
                 GENERIC_READ | GENERIC_WRITE,
-                0,
+                share_mode,
                 ptr::null_mut(),
                 OPEN_EXISTING,
                 FILE_ATTRIBUTE_NORMAL,
@@ -87,6 +335,14 @@ impl COMPort {
 
         com.set_timeout(builder.timeout)?;
         com.port_name = Some(builder.path.clone());
+        com.port_type = port_type;
+        com.reopen = ReopenState::new(builder);
+        com.line_ending = builder.line_ending;
+
+        if let Some(settle_delay) = builder.settle_delay {
+            std::thread::sleep(settle_delay);
+        }
+
         Ok(com)
     }
 
@@ -95,9 +351,10 @@ impl COMPort {
     /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
     /// [tokio-serial](https://crates.io/crates/tokio-serial).
     ///
-    /// Also, you must be very careful when changing the settings of a cloned `SerialPort` : since
-    /// the settings are cached on a per object basis, trying to modify them from two different
-    /// objects can cause some nasty behavior.
+    /// Also, you must be very careful when changing the settings of a cloned `SerialPort` : while
+    /// most settings are read from and written to the driver directly, the timeout is cached on a
+    /// per object basis, and modifying settings from two different objects can cause some nasty
+    /// behavior.
     ///
     /// This is the same as `SerialPort::try_clone()` but returns the concrete type instead.
     ///
@@ -114,14 +371,24 @@ impl COMPort {
                 process_handle,
                 &mut cloned_handle,
                 0,
-                TRUE,
+                FALSE,
                 DUPLICATE_SAME_ACCESS,
             );
             if cloned_handle != INVALID_HANDLE_VALUE {
                 Ok(COMPort {
                     handle: cloned_handle,
                     port_name: self.port_name.clone(),
+                    port_type: self.port_type.clone(),
+                    reopen: self.reopen.clone(),
                     timeout: self.timeout,
+                    inter_byte_timeout: self.inter_byte_timeout,
+                    line_ending: self.line_ending,
+                    write_delay: self.write_delay,
+                    write_unbuffered: self.write_unbuffered,
+                    max_read_size: self.max_read_size,
+                    close_dtr: None,
+                    close_rts: None,
+                    rx_watermark: AtomicU32::new(self.rx_watermark.load(Ordering::Relaxed)),
                 })
             } else {
                 Err(super::error::last_os_error())
@@ -129,6 +396,241 @@ impl COMPort {
         }
     }
 
+    /// Reads bytes into a buffer which doesn't need to be initialized
+    ///
+    /// This behaves like `io::Read::read()`, including the timeout, but reads directly into
+    /// uninitialized memory so callers don't have to zero large buffers before every read. Only
+    /// the first `n` bytes of `buf` are initialized once this returns `Ok(n)`.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(TimedOut)` if no data was received within the timeout.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn read_uninit(&mut self, buf: &mut [MaybeUninit<u8>]) -> Result<usize> {
+        let max = self
+            .max_read_size
+            .map_or(buf.len(), |max| buf.len().min(max));
+        let buf = &mut buf[..max];
+        let mut len: DWORD = 0;
+
+        match unsafe {
+            ReadFile(
+                self.handle,
+                buf.as_mut_ptr() as LPVOID,
+                buf.len() as DWORD,
+                &mut len,
+                ptr::null_mut(),
+            )
+        } {
+            0 => Err(Error::from(super::error::last_io_error())),
+            _ => {
+                if len != 0 {
+                    Ok(len as usize)
+                } else {
+                    Err(Error::new(
+                        ErrorKind::Io(io::ErrorKind::TimedOut),
+                        "Operation timed out",
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Gets who drives the RTS line
+    pub fn rts_control_mode(&self) -> Result<DtrRtsControl> {
+        let dcb = dcb::get_dcb(self.handle)?;
+        dcb::get_rts_control(&dcb)
+    }
+
+    /// Sets who drives the RTS line
+    ///
+    /// While RTS is in `Handshake` or `Toggle` mode, `write_request_to_send()` fails since the
+    /// driver owns the line.
+    pub fn set_rts_control_mode(&mut self, mode: DtrRtsControl) -> Result<()> {
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb::set_rts_control(&mut dcb, mode);
+        dcb::set_dcb(self.handle, dcb)
+    }
+
+    /// Gets who drives the DTR line
+    pub fn dtr_control_mode(&self) -> Result<DtrRtsControl> {
+        let dcb = dcb::get_dcb(self.handle)?;
+        dcb::get_dtr_control(&dcb)
+    }
+
+    /// Sets who drives the DTR line
+    ///
+    /// While DTR is in `Handshake` mode, `write_data_terminal_ready()` fails since the driver
+    /// owns the line.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` for `DtrRtsControl::Toggle`, which only exists for RTS.
+    pub fn set_dtr_control_mode(&mut self, mode: DtrRtsControl) -> Result<()> {
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb::set_dtr_control(&mut dcb, mode)?;
+        dcb::set_dcb(self.handle, dcb)
+    }
+
+    /// Gets the timeouts the driver currently applies
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn comm_timeouts(&self) -> Result<CommTimeouts> {
+        let mut timeouts: COMMTIMEOUTS = unsafe { MaybeUninit::zeroed().assume_init() };
+        if unsafe { GetCommTimeouts(self.handle, &mut timeouts) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(CommTimeouts::from_raw(&timeouts))
+    }
+
+    /// Sets all five timeouts of the driver directly
+    ///
+    /// `set_timeout()` and `set_read_mode()` only set a total read timeout, an interval timeout,
+    /// and no write timeout at all. The per-byte multipliers set here allow timeouts that scale
+    /// with the size of a transfer. A read that times out returns the bytes received so far, or
+    /// fails with `Io(TimedOut)` if there are none, and a write that times out returns the number
+    /// of bytes written.
+    ///
+    /// The timeouts belong to the open device rather than to this handle, and `try_clone()`
+    /// duplicates the handle to the same device, so setting them on one handle affects every
+    /// clone. They're replaced again by the next call to `set_timeout()` or `set_read_mode()` on
+    /// any of these handles, and aren't carried over by `reopen()`. `timeout()` keeps returning
+    /// the timeout last set with `set_timeout()` on this handle.
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    pub fn set_comm_timeouts(&mut self, timeouts: CommTimeouts) -> Result<()> {
+        let mut timeouts = timeouts.to_raw();
+        if unsafe { SetCommTimeouts(self.handle, &mut timeouts) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Sets the events that `wait_comm_event()` waits for
+    ///
+    /// Setting an empty mask clears it, which makes a pending `wait_comm_event()` on another
+    /// handle to the same port fail.
+    pub fn set_comm_mask(&mut self, mask: CommEventMask) -> Result<()> {
+        if unsafe { SetCommMask(self.handle, mask.bits()) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Gets the events that `wait_comm_event()` waits for
+    pub fn comm_mask(&self) -> Result<CommEventMask> {
+        let mut mask: DWORD = 0;
+        if unsafe { GetCommMask(self.handle, &mut mask) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(CommEventMask::from_bits_truncate(mask))
+    }
+
+    /// Blocks until one of the events set with `set_comm_mask()` occurs
+    ///
+    /// Returns the events that occurred. With a `timeout`, the wait runs on a helper thread and
+    /// is cancelled once the timeout elapses, since the port isn't opened for overlapped I/O.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(TimedOut)` if no event occurred within `timeout`.
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error, including an empty event mask.
+    pub fn wait_comm_event(&self, timeout: Option<Duration>) -> Result<CommEventMask> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return wait_comm_event(self.handle),
+        };
+
+        // `HANDLE` isn't `Send`, but the handle stays valid until the thread has been joined
+        let handle = self.handle as usize;
+        let (sender, receiver) = mpsc::channel();
+        let waiter = thread::spawn(move || {
+            let _ = sender.send(wait_comm_event(handle as HANDLE));
+        });
+
+        let result = match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            // Keep cancelling in case the thread didn't start waiting yet
+            Err(mpsc::RecvTimeoutError::Timeout) => loop {
+                unsafe { CancelSynchronousIo(waiter.as_raw_handle() as HANDLE) };
+                match receiver.recv_timeout(Duration::from_millis(10)) {
+                    Ok(result) => break result,
+                    Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        break Err(Error::new(ErrorKind::Unknown, "Comm event wait failed"))
+                    }
+                }
+            },
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(Error::new(ErrorKind::Unknown, "Comm event wait failed"))
+            }
+        };
+        let _ = waiter.join();
+        result
+    }
+
+    fn apply_timeouts(&mut self, timeout: Duration, inter_byte_timeout: Duration) -> Result<()> {
+        let milliseconds = timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000;
+
+        let (interval, multiplier) = if inter_byte_timeout == Duration::from_secs(0) {
+            // return as soon as bytes become available (like POSIX would) and
+            // block up to given duration otherwise
+            // https://docs.microsoft.com/en-us/windows/win32/api/winbase/ns-winbase-commtimeouts#remarks
+            (MAXDWORD, MAXDWORD)
+        } else {
+            // keep reading until the line goes quiet for the inter-byte timeout, the buffer
+            // is full or the total timeout elapsed
+            (inter_byte_timeout.as_millis().max(1) as DWORD, 0)
+        };
+
+        let mut timeouts = COMMTIMEOUTS {
+            ReadIntervalTimeout: interval,
+            ReadTotalTimeoutMultiplier: multiplier,
+            ReadTotalTimeoutConstant: milliseconds as DWORD,
+            // block without timeout until write is complete
+            // MAXDWORD is *not* a reserved WriteTotalTimeoutMultiplier
+            // value, i.e., setting it incurs a long write timeout
+            WriteTotalTimeoutMultiplier: 0,
+            WriteTotalTimeoutConstant: 0,
+        };
+
+        if unsafe { SetCommTimeouts(self.handle, &mut timeouts) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Leaves DTR and RTS at the levels requested with `set_close_behavior()`
+    fn apply_close_behavior(&mut self) -> Result<()> {
+        if self.close_dtr.is_none() && self.close_rts.is_none() {
+            return Ok(());
+        }
+
+        let control = |level| {
+            if level {
+                DtrRtsControl::Enable
+            } else {
+                DtrRtsControl::Disable
+            }
+        };
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        if let Some(level) = self.close_dtr {
+            dcb::set_dtr_control(&mut dcb, control(level))?;
+        }
+        if let Some(level) = self.close_rts {
+            dcb::set_rts_control(&mut dcb, control(level));
+        }
+        dcb::set_dcb(self.handle, dcb)
+    }
+
     fn escape_comm_function(&mut self, function: DWORD) -> Result<()> {
         match unsafe { EscapeCommFunction(self.handle, function) } {
             0 => Err(super::error::last_os_error()),
@@ -136,13 +638,55 @@ impl COMPort {
         }
     }
 
-    fn read_pin(&mut self, pin: DWORD) -> Result<bool> {
-        let mut status: DWORD = 0;
+    /// Opens a COM port and applies `config` verbatim
+    ///
+    /// **This is an advanced, platform-specific escape hatch**, see `RawPortConfig`. The port is
+    /// opened exclusively and none of the usual settings are applied, so the port ends up
+    /// configured exactly as described by `config`. The cached timeout is taken from
+    /// `ReadTotalTimeoutConstant`; note that calling `set_timeout()` replaces the
+    /// `COMMTIMEOUTS` of `config`.
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the device could not be opened. This could indicate that the device is
+    ///   already in use.
+    /// * `InvalidInput` if `path` is not a valid device name.
+    /// * `Io` for any other I/O error while opening or configuring the device.
+    pub fn open_with_raw_config(path: &str, config: &RawPortConfig) -> Result<COMPort> {
+        let name = device_path(path);
+        let handle = unsafe {
+            CreateFileW(
+                name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                FILE_ATTRIBUTE_NORMAL,
+                0 as HANDLE,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(super::error::last_os_error());
+        }
 
-        match unsafe { GetCommModemStatus(self.handle, &mut status) } {
-            0 => Err(super::error::last_os_error()),
-            _ => Ok(status & pin != 0),
+        let mut com = COMPort::open_from_raw_handle(handle as RawHandle);
+        dcb::set_dcb(handle, config.dcb)?;
+        let mut timeouts = config.timeouts;
+        if unsafe { SetCommTimeouts(handle, &mut timeouts) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        com.timeout = Duration::from_millis(u64::from(timeouts.ReadTotalTimeoutConstant));
+        com.port_name = Some(path.to_string());
+        com.port_type = crate::lookup_port_type(path);
+        Ok(com)
+    }
+
+    fn comm_properties(&self) -> Result<COMMPROP> {
+        let mut properties: COMMPROP = unsafe { MaybeUninit::zeroed().assume_init() };
+        if unsafe { GetCommProperties(self.handle, &mut properties) } == 0 {
+            return Err(super::error::last_os_error());
         }
+        Ok(properties)
     }
 
     fn open_from_raw_handle(handle: RawHandle) -> Self {
@@ -150,14 +694,40 @@ impl COMPort {
         // We'll punt and set it `None` here.
         COMPort {
             handle: handle as HANDLE,
-            timeout: Duration::from_millis(100),
+            timeout: crate::REASONABLE_TIMEOUT,
+            inter_byte_timeout: Duration::from_secs(0),
+            line_ending: LineEnding::None,
+            write_delay: Duration::from_secs(0),
+            write_unbuffered: false,
+            max_read_size: None,
+            close_dtr: None,
+            close_rts: None,
+            rx_watermark: AtomicU32::new(0),
             port_name: None,
+            port_type: SerialPortType::Unknown,
+            reopen: ReopenState::default(),
+        }
+    }
+}
+
+fn wait_comm_event(handle: HANDLE) -> Result<CommEventMask> {
+    let mut events: DWORD = 0;
+    if unsafe { WaitCommEvent(handle, &mut events, ptr::null_mut()) } == 0 {
+        if unsafe { GetLastError() } == ERROR_OPERATION_ABORTED {
+            return Err(Error::new(
+                ErrorKind::Io(io::ErrorKind::TimedOut),
+                "No comm event occurred before the timeout",
+            ));
         }
+        return Err(super::error::last_os_error());
     }
+    Ok(CommEventMask::from_bits_truncate(events))
 }
 
 impl Drop for COMPort {
     fn drop(&mut self) {
+        // There's no way to report a failure from here, the handle is closed regardless
+        let _ = self.apply_close_behavior();
         unsafe {
             CloseHandle(self.handle);
         }
@@ -178,34 +748,48 @@ impl FromRawHandle for COMPort {
 
 impl io::Read for COMPort {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let mut len: DWORD = 0;
+        // `read_uninit()` only ever writes initialized bytes into the buffer
+        let buf = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.read_uninit(buf).map_err(io::Error::from)
+    }
+}
 
-        match unsafe {
-            ReadFile(
-                self.handle,
-                buf.as_mut_ptr() as LPVOID,
-                buf.len() as DWORD,
-                &mut len,
-                ptr::null_mut(),
-            )
-        } {
-            0 => Err(io::Error::last_os_error()),
-            _ => {
-                if len != 0 {
-                    Ok(len as usize)
-                } else {
-                    Err(io::Error::new(
-                        io::ErrorKind::TimedOut,
-                        "Operation timed out",
-                    ))
-                }
-            }
+impl COMPort {
+    /// Writes the line settings and timeout of `new` on top of `original` and checks that they
+    /// took effect
+    ///
+    /// Leaves restoring `original` on failure to the caller.
+    fn apply_settings(&mut self, original: DCB, new: &SerialPortBuilder) -> Result<()> {
+        let mut dcb = original;
+        dcb::set_baud_rate(&mut dcb, new.baud_rate);
+        dcb::set_data_bits(&mut dcb, new.data_bits);
+        dcb::set_flow_control(&mut dcb, new.flow_control);
+        dcb::set_parity(&mut dcb, new.parity);
+        dcb::set_stop_bits(&mut dcb, new.stop_bits);
+        dcb::set_dcb(self.handle, dcb)?;
+
+        // Drivers substitute unsupported sizes without failing, but report the substitute
+        let applied = dcb::get_dcb(self.handle)?;
+        if dcb::get_data_bits(&applied)? != new.data_bits {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Device doesn't support this number of data bits",
+            ));
         }
+        if dcb::get_parity(&applied)? != new.parity
+            || dcb::get_stop_bits(&applied)? != new.stop_bits
+            || dcb::get_flow_control(&applied) != new.flow_control
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Device didn't accept the settings",
+            ));
+        }
+
+        self.apply_timeouts(new.timeout, self.inter_byte_timeout)
     }
-}
 
-impl io::Write for COMPort {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    fn write_unpaced(&mut self, buf: &[u8]) -> io::Result<usize> {
         let mut len: DWORD = 0;
 
         match unsafe {
@@ -217,14 +801,33 @@ impl io::Write for COMPort {
                 ptr::null_mut(),
             )
         } {
-            0 => Err(io::Error::last_os_error()),
+            0 => Err(super::error::last_io_error()),
             _ => Ok(len as usize),
         }
     }
+}
+
+impl io::Write for COMPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.write_delay == Duration::from_secs(0) {
+            let written = self.write_unpaced(buf)?;
+            if self.write_unbuffered {
+                // The bytes are written already, so a failed drain is left to the next call
+                let _ = self.flush();
+            }
+            return Ok(written);
+        }
+        let delay = self.write_delay;
+        crate::write_paced(buf, delay, |byte| {
+            let written = self.write_unpaced(byte)?;
+            let _ = self.flush();
+            Ok(written)
+        })
+    }
 
     fn flush(&mut self) -> io::Result<()> {
         match unsafe { FlushFileBuffers(self.handle) } {
-            0 => Err(io::Error::last_os_error()),
+            0 => Err(super::error::last_io_error()),
             _ => Ok(()),
         }
     }
@@ -235,151 +838,425 @@ impl SerialPort for COMPort {
         self.port_name.clone()
     }
 
+    fn port_type(&self) -> SerialPortType {
+        self.port_type.clone()
+    }
+
+    fn is_virtual(&self) -> bool {
+        let name = match self.port_name {
+            Some(ref name) => name.trim_start_matches(r"\\.\").to_ascii_uppercase(),
+            None => return false,
+        };
+        // com0com names its port pairs `CNCAn` and `CNCBn` unless they're renamed to COM ports,
+        // in which case the driver is still listed
+        if name.starts_with("CNC") {
+            return true;
+        }
+        crate::available_ports_iter().map_or(false, |mut ports| {
+            ports.any(|info| match info {
+                Ok(info) => {
+                    info.port_name.to_ascii_uppercase() == name
+                        && info.driver_name.map_or(false, |driver| {
+                            VIRTUAL_PORT_DRIVERS
+                                .iter()
+                                .any(|virtual_driver| driver.eq_ignore_ascii_case(virtual_driver))
+                        })
+                }
+                Err(_) => false,
+            })
+        })
+    }
+
     fn timeout(&self) -> Duration {
         self.timeout
     }
 
+    fn timeout_resolution(&self) -> Duration {
+        // `COMMTIMEOUTS` counts in milliseconds
+        Duration::from_millis(1)
+    }
+
+    fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
     fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
-        let milliseconds = timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000;
+        self.apply_timeouts(timeout, self.inter_byte_timeout)?;
+        self.timeout = timeout;
+        self.reopen.update(|s| s.timeout = timeout);
+        Ok(())
+    }
 
-        
-        let mut timeouts = COMMTIMEOUTS {
-            // return as soon as bytes become available (like POSIX would) and
-            // block up to given duration otherwise
-            // https://docs.microsoft.com/en-us/windows/win32/api/winbase/ns-winbase-commtimeouts#remarks
-            ReadIntervalTimeout: MAXDWORD,
-            ReadTotalTimeoutMultiplier: MAXDWORD,
-            ReadTotalTimeoutConstant: milliseconds as DWORD,
-            // block without timeout until write is complete
-            // MAXDWORD is *not* a reserved WriteTotalTimeoutMultiplier
-            // value, i.e., setting it incurs a long write timeout
-            WriteTotalTimeoutMultiplier: 0,
-            WriteTotalTimeoutConstant: 0,
-        };
-        
-        if unsafe { SetCommTimeouts(self.handle, &mut timeouts) } == 0 {
-            return Err(super::error::last_os_error());
+    fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+        self.reopen.update(|s| s.line_ending = line_ending);
+    }
+
+    fn set_read_mode(&mut self, min_bytes: u8, inter_byte_timeout: Duration) -> Result<()> {
+        crate::check_read_mode(min_bytes, inter_byte_timeout)?;
+        if inter_byte_timeout.as_millis() >= MAXDWORD as u128 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Inter-byte timeout is too long",
+            ));
         }
+        self.apply_timeouts(self.timeout, inter_byte_timeout)?;
+        self.inter_byte_timeout = inter_byte_timeout;
+        Ok(())
+    }
 
-        self.timeout = timeout;
+    fn inter_byte_write_delay(&self) -> Duration {
+        self.write_delay
+    }
+
+    fn set_inter_byte_write_delay(&mut self, delay: Duration) -> Result<()> {
+        self.write_delay = delay;
         Ok(())
     }
 
-    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
-        if level {
-            self.escape_comm_function(SETRTS)
-        } else {
-            self.escape_comm_function(CLRRTS)
+    fn write_unbuffered(&self) -> bool {
+        self.write_unbuffered
+    }
+
+    fn set_write_unbuffered(&mut self, unbuffered: bool) -> Result<()> {
+        self.write_unbuffered = unbuffered;
+        Ok(())
+    }
+
+    fn max_read_size(&self) -> Option<usize> {
+        self.max_read_size
+    }
+
+    fn set_max_read_size(&mut self, max: Option<usize>) -> Result<()> {
+        crate::check_max_read_size(max)?;
+        self.max_read_size = max;
+        Ok(())
+    }
+
+    fn close_on_exec(&self) -> Result<bool> {
+        let mut flags: DWORD = 0;
+        match unsafe { GetHandleInformation(self.handle, &mut flags) } {
+            0 => Err(super::error::last_os_error()),
+            _ => Ok(flags & HANDLE_FLAG_INHERIT == 0),
         }
     }
 
-    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
-        if level {
-            self.escape_comm_function(SETDTR)
-        } else {
-            self.escape_comm_function(CLRDTR)
+    fn set_close_on_exec(&mut self, cloexec: bool) -> Result<()> {
+        let flags = if cloexec { 0 } else { HANDLE_FLAG_INHERIT };
+        match unsafe { SetHandleInformation(self.handle, HANDLE_FLAG_INHERIT, flags) } {
+            0 => Err(super::error::last_os_error()),
+            _ => Ok(()),
         }
     }
 
-    fn read_clear_to_send(&mut self) -> Result<bool> {
-        self.read_pin(MS_CTS_ON)
+    fn parity_check(&self) -> Result<bool> {
+        let dcb = dcb::get_dcb(self.handle)?;
+        Ok(dcb::get_parity_check(&dcb))
     }
 
-    fn read_data_set_ready(&mut self) -> Result<bool> {
-        self.read_pin(MS_DSR_ON)
+    fn set_parity_check(&mut self, enabled: bool) -> Result<()> {
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb::set_parity_check(&mut dcb, enabled);
+        dcb::set_dcb(self.handle, dcb)
+    }
+
+    fn set_binary_mode(&mut self, binary: bool) -> Result<()> {
+        if !binary {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Windows doesn't support non-binary transfers",
+            ));
+        }
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb::set_binary_mode(&mut dcb);
+        dcb::set_dcb(self.handle, dcb)
     }
 
-    fn read_ring_indicator(&mut self) -> Result<bool> {
-        self.read_pin(MS_RING_ON)
+    fn set_overflow_policy(&mut self, policy: OverflowPolicy) -> Result<()> {
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb::set_overflow_policy(&mut dcb, policy)?;
+        dcb::set_dcb(self.handle, dcb)
     }
 
-    fn read_carrier_detect(&mut self) -> Result<bool> {
-        self.read_pin(MS_RLSD_ON)
+    fn set_flow_control_passthrough(&mut self, passthrough: bool) -> Result<()> {
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb::set_flow_control_passthrough(&mut dcb, passthrough)?;
+        dcb::set_dcb(self.handle, dcb)
+    }
+
+    fn save_flow_control(&self) -> Result<SavedFlowControl> {
+        let dcb = dcb::get_dcb(self.handle)?;
+        Ok(SavedFlowControl {
+            flow_control: dcb::get_flow_control(&dcb),
+            raw: Some(dcb::get_raw_flow_control(&dcb)),
+        })
+    }
+
+    fn restore_flow_control(&mut self, saved: &SavedFlowControl) -> Result<()> {
+        let raw = match saved.raw {
+            Some(raw) => raw,
+            None => return self.set_flow_control(saved.flow_control),
+        };
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb::set_raw_flow_control(&mut dcb, raw);
+        dcb::set_dcb(self.handle, dcb)?;
+        self.reopen.update(|s| s.flow_control = saved.flow_control);
+        Ok(())
+    }
+
+    fn set_flow_watermarks(&mut self, low: usize, high: usize) -> Result<()> {
+        let properties = self.comm_properties()?;
+
+        // `XoffLim` is expressed as the number of free bytes left in the input buffer
+        let queue_size = properties.dwCurrentRxQueue as usize;
+        if low >= high || high > queue_size || queue_size > WORD::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid flow control watermarks for the input buffer size",
+            ));
+        }
+
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        dcb.XonLim = low as WORD;
+        dcb.XoffLim = (queue_size - high) as WORD;
+        dcb::set_dcb(self.handle, dcb)
+    }
+
+    fn modem_lines(&self) -> Result<ModemLines> {
+        let mut lines = modem_inputs(self.handle)?;
+
+        // The output levels can't be read back, so fall back to the ones set by the DCB
+        let rts = match self.reopen.rts {
+            Some(level) => level,
+            None => self.rts_control_mode()? == DtrRtsControl::Enable,
+        };
+        let dtr = match self.reopen.dtr {
+            Some(level) => level,
+            None => self.dtr_control_mode()? == DtrRtsControl::Enable,
+        };
+        lines.set(ModemLines::RTS, rts);
+        lines.set(ModemLines::DTR, dtr);
+        Ok(lines)
+    }
+
+    fn set_modem_lines_masked(&mut self, set: ModemLines, clear: ModemLines) -> Result<()> {
+        crate::check_modem_line_masks(set, clear)?;
+        let changed = set | clear;
+        if changed.contains(ModemLines::RTS) {
+            match self.rts_control_mode()? {
+                DtrRtsControl::Handshake | DtrRtsControl::Toggle => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "RTS is controlled by the driver",
+                    ))
+                }
+                DtrRtsControl::Disable | DtrRtsControl::Enable => {}
+            }
+        }
+        if changed.contains(ModemLines::DTR) && self.dtr_control_mode()? == DtrRtsControl::Handshake
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "DTR is controlled by the driver",
+            ));
+        }
+
+        for &(line, on, off) in &[
+            (ModemLines::RTS, SETRTS, CLRRTS),
+            (ModemLines::DTR, SETDTR, CLRDTR),
+        ] {
+            if set.contains(line) {
+                self.escape_comm_function(on)?;
+            } else if clear.contains(line) {
+                self.escape_comm_function(off)?;
+            }
+            self.reopen.record_lines(set & line, clear & line);
+        }
+        Ok(())
+    }
+
+    fn set_close_behavior(&mut self, dtr: Option<bool>, rts: Option<bool>) -> Result<()> {
+        self.close_dtr = dtr;
+        self.close_rts = rts;
+        Ok(())
+    }
+
+    fn wait_for_modem_change(&mut self, timeout: Option<Duration>) -> Result<ModemStatus> {
+        let original_mask = self.comm_mask()?;
+        self.set_comm_mask(
+            CommEventMask::CTS | CommEventMask::DSR | CommEventMask::RING | CommEventMask::RLSD,
+        )?;
+        let event = self.wait_comm_event(timeout);
+        self.set_comm_mask(original_mask)?;
+        event?;
+        self.read_modem_status()
     }
 
     fn baud_rate(&self) -> Result<u32> {
         let dcb = dcb::get_dcb(self.handle)?;
-        Ok(dcb.BaudRate as u32)
+        Ok(dcb::get_baud_rate(&dcb))
+    }
+
+    fn baud_rate_info(&self) -> Result<BaudRateInfo> {
+        let actual = self.baud_rate()?;
+        Ok(BaudRateInfo {
+            requested: self
+                .reopen
+                .settings
+                .as_ref()
+                .map_or(actual, |s| s.baud_rate),
+            actual: Some(actual),
+        })
     }
 
     fn data_bits(&self) -> Result<DataBits> {
         let dcb = dcb::get_dcb(self.handle)?;
-        match dcb.ByteSize {
-            5 => Ok(DataBits::Five),
-            6 => Ok(DataBits::Six),
-            7 => Ok(DataBits::Seven),
-            8 => Ok(DataBits::Eight),
-            _ => Err(Error::new(
-                ErrorKind::Unknown,
-                "Invalid data bits setting encountered",
-            )),
-        }
+        dcb::get_data_bits(&dcb)
     }
 
     fn parity(&self) -> Result<Parity> {
         let dcb = dcb::get_dcb(self.handle)?;
-        match dcb.Parity {
-            ODDPARITY => Ok(Parity::Odd),
-            EVENPARITY => Ok(Parity::Even),
-            NOPARITY => Ok(Parity::None),
-            _ => Err(Error::new(
-                ErrorKind::Unknown,
-                "Invalid parity bits setting encountered",
-            )),
-        }
+        dcb::get_parity(&dcb)
     }
 
     fn stop_bits(&self) -> Result<StopBits> {
         let dcb = dcb::get_dcb(self.handle)?;
-        match dcb.StopBits {
-            TWOSTOPBITS => Ok(StopBits::Two),
-            ONESTOPBIT => Ok(StopBits::One),
-            _ => Err(Error::new(
-                ErrorKind::Unknown,
-                "Invalid stop bits setting encountered",
-            )),
-        }
+        dcb::get_stop_bits(&dcb)
     }
 
     fn flow_control(&self) -> Result<FlowControl> {
         let dcb = dcb::get_dcb(self.handle)?;
-        if dcb.fOutxCtsFlow() != 0 || dcb.fRtsControl() != 0 {
-            Ok(FlowControl::Hardware)
-        } else if dcb.fOutX() != 0 || dcb.fInX() != 0 {
-            Ok(FlowControl::Software)
-        } else {
-            Ok(FlowControl::None)
-        }
+        Ok(dcb::get_flow_control(&dcb))
+    }
+
+    fn settings(&self) -> Result<SerialPortBuilder> {
+        // Read the `DCB` struct only once so that the returned settings are a consistent snapshot
+        // of the driver state.
+        let dcb = dcb::get_dcb(self.handle)?;
+        Ok(crate::new(
+            self.port_name.clone().unwrap_or_default(),
+            dcb::get_baud_rate(&dcb),
+        )
+        .data_bits(dcb::get_data_bits(&dcb)?)
+        .flow_control(dcb::get_flow_control(&dcb))
+        .parity(dcb::get_parity(&dcb)?)
+        .stop_bits(dcb::get_stop_bits(&dcb)?)
+        .timeout(self.timeout)
+        .line_ending(self.line_ending))
     }
 
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
         let mut dcb = dcb::get_dcb(self.handle)?;
         dcb::set_baud_rate(&mut dcb, baud_rate);
-        dcb::set_dcb(self.handle, dcb)
+        dcb::set_dcb(self.handle, dcb)?;
+        self.reopen.update(|s| s.baud_rate = baud_rate);
+        Ok(())
     }
 
     fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
-        let mut dcb = dcb::get_dcb(self.handle)?;
+        crate::check_data_bits_supported(&self.port_type, data_bits)?;
+        let original = dcb::get_dcb(self.handle)?;
+        let mut dcb = original;
         dcb::set_data_bits(&mut dcb, data_bits);
-        dcb::set_dcb(self.handle, dcb)
+        dcb::set_dcb(self.handle, dcb)?;
+
+        // Drivers substitute unsupported sizes without failing, but report the substitute
+        if dcb::get_data_bits(&dcb::get_dcb(self.handle)?)? != data_bits {
+            dcb::set_dcb(self.handle, original)?;
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Device doesn't support this number of data bits",
+            ));
+        }
+        self.reopen.update(|s| s.data_bits = data_bits);
+        Ok(())
     }
 
     fn set_parity(&mut self, parity: Parity) -> Result<()> {
         let mut dcb = dcb::get_dcb(self.handle)?;
         dcb::set_parity(&mut dcb, parity);
-        dcb::set_dcb(self.handle, dcb)
+        dcb::set_dcb(self.handle, dcb)?;
+        self.reopen.update(|s| s.parity = parity);
+        Ok(())
     }
 
     fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
         let mut dcb = dcb::get_dcb(self.handle)?;
         dcb::set_stop_bits(&mut dcb, stop_bits);
-        dcb::set_dcb(self.handle, dcb)
+        dcb::set_dcb(self.handle, dcb)?;
+        self.reopen.update(|s| s.stop_bits = stop_bits);
+        Ok(())
     }
 
     fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
         let mut dcb = dcb::get_dcb(self.handle)?;
         dcb::set_flow_control(&mut dcb, flow_control);
-        dcb::set_dcb(self.handle, dcb)
+        dcb::set_dcb(self.handle, dcb)?;
+        self.reopen.update(|s| s.flow_control = flow_control);
+        Ok(())
+    }
+
+    fn swap_settings(&mut self, new: &SerialPortBuilder) -> Result<SerialPortBuilder> {
+        crate::check_data_bits_supported(&self.port_type, new.data_bits)?;
+        let old = self.settings()?;
+        let original = dcb::get_dcb(self.handle)?;
+        if let Err(e) = self.apply_settings(original, new) {
+            // Best effort: if the original settings can't be written back either, the device is
+            // most likely gone and the first error is the more useful one
+            let _ = dcb::set_dcb(self.handle, original);
+            let _ = self.apply_timeouts(self.timeout, self.inter_byte_timeout);
+            return Err(e);
+        }
+
+        self.timeout = new.timeout;
+        self.line_ending = new.line_ending;
+        self.reopen.record_settings(new);
+        Ok(old)
+    }
+
+    fn wait_reenumerate(&mut self, identity: &UsbIdentity, timeout: Duration) -> Result<()> {
+        *self = crate::reenumerate(self, identity, timeout, COMPort::open)?;
+        Ok(())
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        let settings = match self.reopen.settings.clone() {
+            Some(settings) => settings,
+            None => self
+                .settings()
+                .ok()
+                .filter(|settings| !settings.path.is_empty())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        "The port wasn't opened from a path",
+                    )
+                })?,
+        };
+
+        // Close the old handle first, as ports are opened without sharing. The invalid handle
+        // left behind is ignored when `self` is dropped or replaced.
+        let _ = self.apply_close_behavior();
+        unsafe { CloseHandle(self.handle) };
+        self.handle = INVALID_HANDLE_VALUE;
+
+        let mut port = COMPort::open(&settings)?;
+        if self.inter_byte_timeout != Duration::from_secs(0) {
+            port.set_read_mode(1, self.inter_byte_timeout)?;
+        }
+        if let Some(level) = self.reopen.dtr {
+            port.write_data_terminal_ready(level)?;
+        }
+        if let Some(level) = self.reopen.rts {
+            port.write_request_to_send(level)?;
+        }
+        port.write_delay = self.write_delay;
+        port.write_unbuffered = self.write_unbuffered;
+        port.max_read_size = self.max_read_size;
+        port.close_dtr = self.close_dtr;
+        port.close_rts = self.close_rts;
+        *self = port;
+        Ok(())
     }
 
     fn bytes_to_read(&self) -> Result<u32> {
@@ -387,12 +1264,75 @@ impl SerialPort for COMPort {
         let mut comstat = MaybeUninit::uninit();
 
         if unsafe { ClearCommError(self.handle, &mut errors, comstat.as_mut_ptr()) != 0 } {
-            unsafe { Ok(comstat.assume_init().cbInQue) }
+            let bytes = unsafe { comstat.assume_init().cbInQue };
+            self.rx_watermark.fetch_max(bytes, Ordering::Relaxed);
+            Ok(bytes)
         } else {
             Err(super::error::last_os_error())
         }
     }
 
+    fn has_data(&self, timeout: Duration) -> Result<bool> {
+        if self.bytes_to_read()? > 0 {
+            return Ok(true);
+        }
+
+        let original_mask = self.comm_mask()?;
+        if unsafe { SetCommMask(self.handle, CommEventMask::RXCHAR.bits()) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        let deadline = Instant::now() + timeout;
+        let result = loop {
+            // Data may have arrived before the mask was set
+            match self.bytes_to_read() {
+                Ok(0) => {}
+                Ok(_) => break Ok(true),
+                Err(e) => break Err(e),
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                break Ok(false);
+            }
+            match self.wait_comm_event(Some(remaining)) {
+                Ok(_) => {}
+                Err(ref e) if e.kind() == ErrorKind::Io(io::ErrorKind::TimedOut) => {}
+                Err(e) => break Err(e),
+            }
+        };
+        if unsafe { SetCommMask(self.handle, original_mask.bits()) } == 0 && result.is_ok() {
+            return Err(super::error::last_os_error());
+        }
+        result
+    }
+
+    fn rx_watermark(&self) -> u32 {
+        self.rx_watermark.load(Ordering::Relaxed)
+    }
+
+    fn reset_rx_watermark(&mut self) {
+        self.rx_watermark.store(0, Ordering::Relaxed);
+    }
+
+    fn rx_buffer_capacity(&self) -> Result<u32> {
+        match self.comm_properties()?.dwCurrentRxQueue {
+            0 => Err(Error::new(
+                ErrorKind::Unknown,
+                "The driver doesn't report its input buffer size",
+            )),
+            size => Ok(size),
+        }
+    }
+
+    fn tx_buffer_capacity(&self) -> Result<u32> {
+        match self.comm_properties()?.dwCurrentTxQueue {
+            0 => Err(Error::new(
+                ErrorKind::Unknown,
+                "The driver doesn't report its output buffer size",
+            )),
+            size => Ok(size),
+        }
+    }
+
     fn bytes_to_write(&self) -> Result<u32> {
         let mut errors: DWORD = 0;
         let mut comstat = MaybeUninit::uninit();
@@ -404,6 +1344,43 @@ impl SerialPort for COMPort {
         }
     }
 
+    fn queue_status(&self) -> Result<QueueStatus> {
+        let mut errors: DWORD = 0;
+        let mut comstat = MaybeUninit::uninit();
+
+        if unsafe { ClearCommError(self.handle, &mut errors, comstat.as_mut_ptr()) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        let comstat = unsafe { comstat.assume_init() };
+        self.rx_watermark
+            .fetch_max(comstat.cbInQue, Ordering::Relaxed);
+
+        // A queue size of 0 means the driver doesn't report it
+        let properties = self.comm_properties()?;
+        let capacity = |size| if size == 0 { None } else { Some(size) };
+        Ok(QueueStatus {
+            bytes_to_read: comstat.cbInQue,
+            bytes_to_write: comstat.cbOutQue,
+            rx_capacity: capacity(properties.dwCurrentRxQueue),
+            tx_capacity: capacity(properties.dwCurrentTxQueue),
+        })
+    }
+
+    fn capabilities(&self) -> Result<PortCapabilities> {
+        let properties = self.comm_properties()?;
+        Ok(capabilities(&properties, &self.port_type))
+    }
+
+    fn supported_operations(&self) -> SupportedOps {
+        let mut ops = crate::common_supported_operations(self);
+        // There's no way to ask a driver whether it implements `RTS_CONTROL_TOGGLE`, but virtual
+        // ports have no transceiver to drive
+        if !self.is_virtual() {
+            ops |= SupportedOps::RS485_RTS_TOGGLE;
+        }
+        ops
+    }
+
     fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
         let buffer_flags = match buffer_to_clear {
             ClearBuffer::Input => PURGE_RXABORT | PURGE_RXCLEAR,
@@ -418,6 +1395,17 @@ impl SerialPort for COMPort {
         }
     }
 
+    fn counters(&self) -> Result<LineCounters> {
+        Err(Error::new(
+            ErrorKind::Unknown,
+            "Line counters are not supported on this platform",
+        ))
+    }
+
+    fn reset_counters(&mut self) -> Result<()> {
+        self.counters().map(|_| ())
+    }
+
     fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
         match self.try_clone_native() {
             Ok(p) => Ok(Box::new(p)),
@@ -441,3 +1429,149 @@ impl SerialPort for COMPort {
         }
     }
 }
+
+/// The standard baud rates of the `dwSettableBaud` and `dwMaxBaud` bits of `COMMPROP`
+const SETTABLE_BAUD_RATES: [(DWORD, u32); 18] = [
+    (BAUD_075, 75),
+    (BAUD_110, 110),
+    (BAUD_150, 150),
+    (BAUD_300, 300),
+    (BAUD_600, 600),
+    (BAUD_1200, 1200),
+    (BAUD_1800, 1800),
+    (BAUD_2400, 2400),
+    (BAUD_4800, 4800),
+    (BAUD_7200, 7200),
+    (BAUD_9600, 9600),
+    (BAUD_14400, 14400),
+    (BAUD_19200, 19200),
+    (BAUD_38400, 38400),
+    (BAUD_56K, 56000),
+    (BAUD_57600, 57600),
+    (BAUD_115200, 115_200),
+    (BAUD_128K, 128_000),
+];
+
+/// Combines the settings reported by the driver with what's known about the chipset
+fn capabilities(properties: &COMMPROP, port_type: &SerialPortType) -> PortCapabilities {
+    let chipset = crate::chipset_capabilities(port_type);
+    let baud_rates = SETTABLE_BAUD_RATES
+        .iter()
+        .filter(|&&(bit, _)| properties.dwSettableBaud & bit != 0)
+        .map(|&(_, rate)| rate)
+        .collect();
+    // `BAUD_USER` as the maximum means there's no fixed limit
+    let max_baud_rate = SETTABLE_BAUD_RATES
+        .iter()
+        .find(|&&(bit, _)| properties.dwMaxBaud == bit)
+        .map(|&(_, rate)| rate);
+
+    let data_bits = [
+        (DATABITS_5, DataBits::Five),
+        (DATABITS_6, DataBits::Six),
+        (DATABITS_7, DataBits::Seven),
+        (DATABITS_8, DataBits::Eight),
+    ]
+    .iter()
+    .filter(|&&(bit, data_bits)| {
+        properties.wSettableData & bit != 0 && chipset.data_bits.contains(&data_bits)
+    })
+    .map(|&(_, data_bits)| data_bits)
+    .collect();
+    // Mark and space parity and 1.5 stop bits can't be set through this crate
+    let parity = [
+        (PARITY_NONE, Parity::None),
+        (PARITY_ODD, Parity::Odd),
+        (PARITY_EVEN, Parity::Even),
+    ]
+    .iter()
+    .filter(|&&(bit, _)| properties.wSettableStopParity & bit != 0)
+    .map(|&(_, parity)| parity)
+    .collect();
+    let stop_bits = [(STOPBITS_10, StopBits::One), (STOPBITS_20, StopBits::Two)]
+        .iter()
+        .filter(|&&(bit, _)| properties.wSettableStopParity & bit != 0)
+        .map(|&(_, stop_bits)| stop_bits)
+        .collect();
+
+    let mut flow_control = vec![FlowControl::None];
+    if properties.dwProvCapabilities & PCF_XONXOFF != 0 {
+        flow_control.push(FlowControl::Software);
+    }
+    if properties.dwProvCapabilities & PCF_RTSCTS != 0
+        && chipset.flow_control.contains(&FlowControl::Hardware)
+    {
+        flow_control.push(FlowControl::Hardware);
+    }
+
+    PortCapabilities {
+        baud_rates: Some(baud_rates),
+        arbitrary_baud_rates: Some(properties.dwSettableBaud & BAUD_USER != 0),
+        max_baud_rate,
+        data_bits,
+        parity,
+        stop_bits,
+        flow_control,
+    }
+}
+
+#[test]
+fn test_capabilities() {
+    let mut properties: COMMPROP = unsafe { MaybeUninit::zeroed().assume_init() };
+    properties.dwSettableBaud = BAUD_9600 | BAUD_115200 | BAUD_USER;
+    properties.dwMaxBaud = BAUD_USER;
+    properties.wSettableData = DATABITS_7 | DATABITS_8;
+    properties.wSettableStopParity = STOPBITS_10 | PARITY_NONE | PARITY_EVEN | PARITY_MARK;
+    properties.dwProvCapabilities = PCF_RTSCTS;
+
+    let reported = capabilities(&properties, &SerialPortType::Unknown);
+    assert_eq!(reported.baud_rates, Some(vec![9600, 115_200]));
+    assert_eq!(reported.arbitrary_baud_rates, Some(true));
+    assert_eq!(reported.max_baud_rate, None);
+    assert_eq!(reported.data_bits, vec![DataBits::Seven, DataBits::Eight]);
+    assert_eq!(reported.parity, vec![Parity::None, Parity::Even]);
+    assert_eq!(reported.stop_bits, vec![StopBits::One]);
+    assert_eq!(
+        reported.flow_control,
+        vec![FlowControl::None, FlowControl::Hardware]
+    );
+
+    properties.dwMaxBaud = BAUD_115200;
+    let reported = capabilities(&properties, &SerialPortType::Unknown);
+    assert_eq!(reported.max_baud_rate, Some(115_200));
+}
+
+#[test]
+fn test_comm_timeouts_raw() {
+    let timeouts = CommTimeouts {
+        read_interval_timeout: 1,
+        read_total_timeout_multiplier: 2,
+        read_total_timeout_constant: 3,
+        write_total_timeout_multiplier: 4,
+        write_total_timeout_constant: 5,
+    };
+    let raw = timeouts.to_raw();
+    assert_eq!(raw.ReadTotalTimeoutMultiplier, 2);
+    assert_eq!(raw.WriteTotalTimeoutConstant, 5);
+    assert_eq!(CommTimeouts::from_raw(&raw), timeouts);
+}
+
+#[test]
+fn test_device_path() {
+    fn path(name: &str) -> String {
+        let wide = device_path(name);
+        assert_eq!(wide.last(), Some(&0));
+        String::from_utf16(&wide[..wide.len() - 1]).unwrap()
+    }
+
+    assert_eq!(path("COM1"), r"\\.\COM1");
+    assert_eq!(path("COM15"), r"\\.\COM15");
+    assert_eq!(path("CNCA0"), r"\\.\CNCA0");
+    assert_eq!(path(r"\\.\COM15"), r"\\.\COM15");
+    assert_eq!(
+        path(
+            r"\\?\USB#VID_2341&PID_0043#85736323838351F0C1B1#{86e0d1e0-8089-11d0-9ce4-08003e301f73}"
+        ),
+        r"\\?\USB#VID_2341&PID_0043#85736323838351F0C1B1#{86e0d1e0-8089-11d0-9ce4-08003e301f73}"
+    );
+}
@@ -4,7 +4,8 @@ use winapi::um::commapi::*;
 use winapi::um::winbase::*;
 use winapi::um::winnt::HANDLE;
 
-use crate::{DataBits, FlowControl, Parity, Result, StopBits};
+use crate::windows::DtrRtsControl;
+use crate::{DataBits, Error, ErrorKind, FlowControl, OverflowPolicy, Parity, Result, StopBits};
 
 pub(crate) fn get_dcb(handle: HANDLE) -> Result<DCB> {
     let mut dcb: DCB = unsafe { MaybeUninit::zeroed().assume_init() };
@@ -63,17 +64,63 @@ pub(crate) fn set_dcb(handle: HANDLE, mut dcb: DCB) -> Result<()> {
     }
 }
 
+pub(crate) fn get_baud_rate(dcb: &DCB) -> u32 {
+    dcb.BaudRate as u32
+}
+
+pub(crate) fn get_data_bits(dcb: &DCB) -> Result<DataBits> {
+    match dcb.ByteSize {
+        5 => Ok(DataBits::Five),
+        6 => Ok(DataBits::Six),
+        7 => Ok(DataBits::Seven),
+        8 => Ok(DataBits::Eight),
+        _ => Err(Error::new(
+            ErrorKind::Unknown,
+            "Invalid data bits setting encountered",
+        )),
+    }
+}
+
+pub(crate) fn get_parity(dcb: &DCB) -> Result<Parity> {
+    match dcb.Parity {
+        ODDPARITY => Ok(Parity::Odd),
+        EVENPARITY => Ok(Parity::Even),
+        NOPARITY => Ok(Parity::None),
+        _ => Err(Error::new(
+            ErrorKind::Unknown,
+            "Invalid parity bits setting encountered",
+        )),
+    }
+}
+
+pub(crate) fn get_stop_bits(dcb: &DCB) -> Result<StopBits> {
+    match dcb.StopBits {
+        TWOSTOPBITS => Ok(StopBits::Two),
+        ONESTOPBIT => Ok(StopBits::One),
+        _ => Err(Error::new(
+            ErrorKind::Unknown,
+            "Invalid stop bits setting encountered",
+        )),
+    }
+}
+
+pub(crate) fn get_flow_control(dcb: &DCB) -> FlowControl {
+    // `fRtsControl` alone doesn't hold back output, e.g. after `set_rts_control_mode()`
+    if dcb.fOutxCtsFlow() != 0 {
+        FlowControl::Hardware
+    } else if dcb.fOutX() != 0 || dcb.fInX() != 0 {
+        FlowControl::Software
+    } else {
+        FlowControl::None
+    }
+}
+
 pub(crate) fn set_baud_rate(dcb: &mut DCB, baud_rate: u32) {
     dcb.BaudRate = baud_rate as DWORD;
 }
 
 pub(crate) fn set_data_bits(dcb: &mut DCB, data_bits: DataBits) {
-    dcb.ByteSize = match data_bits {
-        DataBits::Five => 5,
-        DataBits::Six => 6,
-        DataBits::Seven => 7,
-        DataBits::Eight => 8,
-    };
+    dcb.ByteSize = data_bits.bits();
 }
 
 pub(crate) fn set_parity(dcb: &mut DCB, parity: Parity) {
@@ -86,6 +133,14 @@ pub(crate) fn set_parity(dcb: &mut DCB, parity: Parity) {
     dcb.set_fParity(if parity == Parity::None { FALSE } else { TRUE } as DWORD);
 }
 
+pub(crate) fn get_parity_check(dcb: &DCB) -> bool {
+    dcb.fParity() != 0
+}
+
+pub(crate) fn set_parity_check(dcb: &mut DCB, enabled: bool) {
+    dcb.set_fParity(if enabled { TRUE } else { FALSE } as DWORD);
+}
+
 pub(crate) fn set_stop_bits(dcb: &mut DCB, stop_bits: StopBits) {
     dcb.StopBits = match stop_bits {
         StopBits::One => ONESTOPBIT as u8,
@@ -115,3 +170,146 @@ pub(crate) fn set_flow_control(dcb: &mut DCB, flow_control: FlowControl) {
         }
     }
 }
+
+/// The fields `set_flow_control()` changes, as saved by `SerialPort::save_flow_control()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct RawFlowControl {
+    out_cts_flow: DWORD,
+    rts_control: DWORD,
+    out_x: DWORD,
+    in_x: DWORD,
+}
+
+pub(crate) fn get_raw_flow_control(dcb: &DCB) -> RawFlowControl {
+    RawFlowControl {
+        out_cts_flow: dcb.fOutxCtsFlow(),
+        rts_control: dcb.fRtsControl(),
+        out_x: dcb.fOutX(),
+        in_x: dcb.fInX(),
+    }
+}
+
+pub(crate) fn set_raw_flow_control(dcb: &mut DCB, raw: RawFlowControl) {
+    dcb.set_fOutxCtsFlow(raw.out_cts_flow);
+    dcb.set_fRtsControl(raw.rts_control);
+    dcb.set_fOutX(raw.out_x);
+    dcb.set_fInX(raw.in_x);
+}
+
+/// Enables or disables pausing the sender as the input buffer fills up
+pub(crate) fn set_overflow_policy(dcb: &mut DCB, policy: OverflowPolicy) -> Result<()> {
+    let hardware = dcb.fOutxCtsFlow() != 0;
+    let software = dcb.fOutX() != 0;
+    match policy {
+        OverflowPolicy::DropIncoming => {
+            if dcb.fRtsControl() == RTS_CONTROL_HANDSHAKE {
+                dcb.set_fRtsControl(RTS_CONTROL_ENABLE);
+            }
+            dcb.set_fInX(FALSE as DWORD);
+        }
+        OverflowPolicy::AssertFlowControl => {
+            if !hardware && !software {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Flow control is disabled",
+                ));
+            }
+            if hardware {
+                dcb.set_fRtsControl(RTS_CONTROL_HANDSHAKE);
+            }
+            if software {
+                dcb.set_fInX(TRUE as DWORD);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Hands XON/XOFF over to the application or back to the driver
+pub(crate) fn set_flow_control_passthrough(dcb: &mut DCB, passthrough: bool) -> Result<()> {
+    if !passthrough && dcb.fOutxCtsFlow() != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Software flow control can't be combined with RTS/CTS flow control",
+        ));
+    }
+    let enabled = !passthrough as DWORD;
+    dcb.set_fOutX(enabled);
+    dcb.set_fInX(enabled);
+    Ok(())
+}
+
+/// Disables all DCB features that insert, drop or replace received bytes
+pub(crate) fn set_binary_mode(dcb: &mut DCB) {
+    dcb.set_fBinary(TRUE as DWORD);
+    dcb.set_fNull(FALSE as DWORD);
+    dcb.set_fErrorChar(FALSE as DWORD);
+    dcb.set_fOutX(FALSE as DWORD);
+    dcb.set_fInX(FALSE as DWORD);
+    dcb.ByteSize = 8;
+}
+
+pub(crate) fn get_rts_control(dcb: &DCB) -> Result<DtrRtsControl> {
+    match dcb.fRtsControl() {
+        RTS_CONTROL_DISABLE => Ok(DtrRtsControl::Disable),
+        RTS_CONTROL_ENABLE => Ok(DtrRtsControl::Enable),
+        RTS_CONTROL_HANDSHAKE => Ok(DtrRtsControl::Handshake),
+        RTS_CONTROL_TOGGLE => Ok(DtrRtsControl::Toggle),
+        _ => Err(Error::new(
+            ErrorKind::Unknown,
+            "Invalid RTS control setting encountered",
+        )),
+    }
+}
+
+pub(crate) fn set_rts_control(dcb: &mut DCB, mode: DtrRtsControl) {
+    dcb.set_fRtsControl(match mode {
+        DtrRtsControl::Disable => RTS_CONTROL_DISABLE,
+        DtrRtsControl::Enable => RTS_CONTROL_ENABLE,
+        DtrRtsControl::Handshake => RTS_CONTROL_HANDSHAKE,
+        DtrRtsControl::Toggle => RTS_CONTROL_TOGGLE,
+    });
+}
+
+pub(crate) fn get_dtr_control(dcb: &DCB) -> Result<DtrRtsControl> {
+    match dcb.fDtrControl() {
+        DTR_CONTROL_DISABLE => Ok(DtrRtsControl::Disable),
+        DTR_CONTROL_ENABLE => Ok(DtrRtsControl::Enable),
+        DTR_CONTROL_HANDSHAKE => Ok(DtrRtsControl::Handshake),
+        _ => Err(Error::new(
+            ErrorKind::Unknown,
+            "Invalid DTR control setting encountered",
+        )),
+    }
+}
+
+pub(crate) fn set_dtr_control(dcb: &mut DCB, mode: DtrRtsControl) -> Result<()> {
+    dcb.set_fDtrControl(match mode {
+        DtrRtsControl::Disable => DTR_CONTROL_DISABLE,
+        DtrRtsControl::Enable => DTR_CONTROL_ENABLE,
+        DtrRtsControl::Handshake => DTR_CONTROL_HANDSHAKE,
+        DtrRtsControl::Toggle => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "DTR doesn't support toggle mode",
+            ))
+        }
+    });
+    Ok(())
+}
+
+#[test]
+fn test_get_flow_control() {
+    let mut dcb: DCB = unsafe { MaybeUninit::zeroed().assume_init() };
+    assert_eq!(get_flow_control(&dcb), FlowControl::None);
+
+    dcb.set_fRtsControl(RTS_CONTROL_ENABLE);
+    assert_eq!(get_flow_control(&dcb), FlowControl::None);
+    dcb.set_fRtsControl(RTS_CONTROL_TOGGLE);
+    dcb.set_fOutX(TRUE as DWORD);
+    dcb.set_fInX(TRUE as DWORD);
+    assert_eq!(get_flow_control(&dcb), FlowControl::Software);
+
+    set_flow_control(&mut dcb, FlowControl::Hardware);
+    assert_eq!(get_flow_control(&dcb), FlowControl::Hardware);
+}
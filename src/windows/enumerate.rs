@@ -257,6 +257,11 @@ impl PortDevice {
             .unwrap_or(SerialPortType::Unknown)
     }
 
+    // Retrieves the name of the service (driver) installed for this device.
+    pub fn driver_name(&mut self) -> Option<String> {
+        self.property(SPDRP_SERVICE)
+    }
+
     // Retrieves a device property and returns it, if it exists. Returns None if the property
     // doesn't exist.
     fn property(&mut self, property_id: DWORD) -> Option<String> {
@@ -289,10 +294,17 @@ impl PortDevice {
 
 /// List available serial ports on the system.
 pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
-    let mut ports = Vec::new();
-    for guid in get_ports_guids()? {
-        let port_devices = PortDevices::new(&guid);
-        for mut port_device in port_devices {
+    available_ports_iter()?.collect()
+}
+
+/// List available serial ports on the system, one at a time.
+///
+/// Each device is only queried once the iterator reaches it.
+pub fn available_ports_iter() -> Result<impl Iterator<Item = Result<SerialPortInfo>>> {
+    let ports = get_ports_guids()?
+        .into_iter()
+        .flat_map(|guid| PortDevices::new(&guid))
+        .filter_map(|mut port_device| {
             let port_name = port_device.name();
 
             debug_assert!(
@@ -303,16 +315,110 @@ pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
 
             // This technique also returns parallel ports, so we filter these out.
             if port_name.starts_with("LPT") {
-                continue;
+                return None;
             }
 
-            ports.push(SerialPortInfo {
+            Some(Ok(SerialPortInfo {
                 port_name: port_name,
                 port_type: port_device.port_type(),
+                driver_name: port_device.driver_name(),
+                by_id_path: None,
+            }))
+        });
+    Ok(ports)
+}
+
+/// Returns the type `available_ports()` reports for the port named `port_name`, e.g. `COM3`.
+///
+/// The name is compared without the `\\.\` prefix and ignoring case. Only the names of the other
+/// devices are read, the rest is only queried for the matching device.
+pub(crate) fn port_type_of(port_name: &str) -> Result<SerialPortType> {
+    let wanted = port_name.trim_start_matches(r"\\.\");
+    Ok(get_ports_guids()?
+        .iter()
+        .flat_map(PortDevices::new)
+        .find_map(|mut port_device| {
+            if port_device.name().eq_ignore_ascii_case(wanted) {
+                Some(port_device.port_type())
+            } else {
+                None
+            }
+        })
+        .unwrap_or(SerialPortType::Unknown))
+}
+
+/// Lists the ports recorded in the `HARDWARE\\DEVICEMAP\\SERIALCOMM` registry key.
+///
+/// Serial drivers register their ports here independently of the device installation data used
+/// by `available_ports_iter()`, so this still lists ports whose device information is missing or
+/// broken. Nothing beyond the port name is known about them.
+pub(crate) fn serialcomm_ports() -> Vec<SerialPortInfo> {
+    let key_name = CString::new("HARDWARE\\DEVICEMAP\\SERIALCOMM").unwrap();
+    let mut hkey: HKEY = ptr::null_mut();
+    if unsafe {
+        RegOpenKeyExA(
+            HKEY_LOCAL_MACHINE,
+            key_name.as_ptr(),
+            0,
+            KEY_READ,
+            &mut hkey,
+        )
+    } != ERROR_SUCCESS as i32
+    {
+        return Vec::new();
+    }
+
+    let mut ports = Vec::new();
+    for index in 0.. {
+        let mut value_name = [0 as CHAR; MAX_PATH];
+        let mut value_name_len = value_name.len() as DWORD;
+        let mut data = [0u8; MAX_PATH];
+        let mut data_len = data.len() as DWORD;
+        let result = unsafe {
+            RegEnumValueA(
+                hkey,
+                index,
+                value_name.as_mut_ptr(),
+                &mut value_name_len,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                data.as_mut_ptr(),
+                &mut data_len,
+            )
+        };
+        if result == ERROR_MORE_DATA as i32 {
+            // Skip values too long to be a port name
+            continue;
+        } else if result != ERROR_SUCCESS as i32 {
+            // `ERROR_NO_MORE_ITEMS` at the end, and any other error would repeat for every index
+            break;
+        }
+
+        let mut port_name = &data[0..data_len as usize];
+        while port_name.last().map_or(false, |c| *c == b'\0') {
+            port_name = &port_name[..port_name.len() - 1];
+        }
+        if !port_name.is_empty() {
+            ports.push(SerialPortInfo {
+                port_name: String::from_utf8_lossy(port_name).into_owned(),
+                port_type: SerialPortType::Unknown,
+                driver_name: None,
+                by_id_path: None,
             });
         }
     }
-    Ok(ports)
+    unsafe { RegCloseKey(hkey) };
+    ports
+}
+
+/// List available USB serial ports on the system.
+pub fn available_usb_ports() -> Result<Vec<SerialPortInfo>> {
+    available_ports_iter()?
+        .filter(|port| match port {
+            Ok(info) => matches!(info.port_type, SerialPortType::UsbPort(_)),
+            Err(_) => true,
+        })
+        .collect()
 }
 
 #[test]
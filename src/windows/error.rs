@@ -13,13 +13,35 @@ use crate::{Error, ErrorKind};
 
 pub fn last_os_error() -> Error {
     let errno = errno();
+    Error::new(error_kind(errno), error_string(errno).trim())
+}
 
-    let kind = match errno {
-        ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND | ERROR_ACCESS_DENIED => ErrorKind::NoDevice,
-        _ => ErrorKind::Io(io::ErrorKind::Other),
-    };
+/// Returns the error of a failed `ReadFile()`, `WriteFile()` or `FlushFileBuffers()`
+///
+/// Errors that aren't mapped by `last_os_error()` keep the `io::ErrorKind` that std assigns to
+/// them. std reports `ERROR_OPERATION_ABORTED` as `TimedOut`, though, which is indistinguishable
+/// from a read timeout, so I/O cancelled from another thread with `CancelIoEx()` or
+/// `CancelSynchronousIo()` is reported as `Interrupted`, like a read interrupted by a signal on
+/// POSIX.
+///
+/// As for a signal, std's `read_exact()`, `write_all()` and the other adapters retry on
+/// `Interrupted`, so a cancel only ends a plain `read()` or `write()`. The crate's own loops don't
+/// retry on it on Windows.
+pub fn last_io_error() -> io::Error {
+    let errno = errno();
+    match error_kind(errno) {
+        ErrorKind::Io(io::ErrorKind::Other) => io::Error::from_raw_os_error(errno as i32),
+        kind => io::Error::from(Error::new(kind, error_string(errno).trim())),
+    }
+}
 
-    Error::new(kind, error_string(errno).trim())
+fn error_kind(errno: u32) -> ErrorKind {
+    match errno {
+        ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND | ERROR_ACCESS_DENIED
+        | ERROR_DEVICE_REMOVED | ERROR_BAD_COMMAND => ErrorKind::NoDevice,
+        ERROR_OPERATION_ABORTED => ErrorKind::Io(io::ErrorKind::Interrupted),
+        _ => ErrorKind::Io(io::ErrorKind::Other),
+    }
 }
 
 // the rest of this module is borrowed from libstd
@@ -67,3 +89,22 @@ fn error_string(errnum: u32) -> String {
         }
     }
 }
+
+#[test]
+fn test_last_io_error() {
+    use winapi::um::errhandlingapi::SetLastError;
+
+    unsafe { SetLastError(ERROR_OPERATION_ABORTED) };
+    let error = last_io_error();
+    assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    assert_eq!(
+        Error::from(error).kind(),
+        ErrorKind::Io(io::ErrorKind::Interrupted)
+    );
+
+    unsafe { SetLastError(ERROR_DEVICE_REMOVED) };
+    assert_eq!(Error::from(last_io_error()).kind(), ErrorKind::NoDevice);
+
+    unsafe { SetLastError(ERROR_SEM_TIMEOUT) };
+    assert_eq!(last_io_error().kind(), io::ErrorKind::TimedOut);
+}
@@ -1,4 +1,5 @@
 pub use self::com::*;
+pub(crate) use self::dcb::RawFlowControl;
 pub use self::enumerate::*;
 
 mod com;
@@ -0,0 +1,89 @@
+//! Tests for opening Windows virtual ports by their device path.
+//!
+//! These tests require [com0com](https://sourceforge.net/projects/com0com/) with its default
+//! `CNCA0`/`CNCB0` pair installed and are ignored by default. Run them with
+//! `cargo test --test test_com0com -- --ignored`.
+#![cfg(windows)]
+
+use std::io::{Read, Write};
+use std::time::Duration;
+
+#[test]
+#[ignore]
+fn test_com0com_pair_by_device_path() {
+    let mut a = serialport::new(r"\\.\CNCA0", 115_200)
+        .timeout(Duration::from_millis(100))
+        .open()
+        .expect("Unable to open CNCA0");
+    let mut b = serialport::new(r"\\.\CNCB0", 115_200)
+        .timeout(Duration::from_millis(100))
+        .open()
+        .expect("Unable to open CNCB0");
+
+    a.write_all(b"Test Message").unwrap();
+    let mut buf = [0u8; 12];
+    b.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"Test Message");
+}
+
+#[test]
+#[ignore]
+fn test_com0com_wait_comm_event_timeout() {
+    let mut port = serialport::new(r"\\.\CNCA0", 115_200)
+        .open_native()
+        .expect("Unable to open CNCA0");
+    port.set_comm_mask(serialport::CommEventMask::RING).unwrap();
+
+    let error = port
+        .wait_comm_event(Some(Duration::from_millis(100)))
+        .expect_err("No ring should be signalled on an idle pair");
+    assert_eq!(
+        error.kind(),
+        serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut)
+    );
+}
+
+#[test]
+#[ignore]
+fn test_com0com_cancelled_read_is_not_retried() {
+    use std::os::windows::io::AsRawHandle;
+    use std::sync::mpsc;
+    use std::thread;
+
+    use serialport::{Endian, LengthFieldSize, SerialPort};
+    use winapi::um::ioapiset::CancelSynchronousIo;
+
+    let mut port = serialport::new(r"\\.\CNCA0", 115_200)
+        .open()
+        .expect("Unable to open CNCA0");
+
+    let (sender, receiver) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        let result = port.read_length_prefixed_max(
+            2,
+            0,
+            LengthFieldSize::Two,
+            Endian::Big,
+            64,
+            Duration::from_secs(10),
+        );
+        let _ = sender.send(result);
+    });
+
+    // Keep cancelling in case the thread didn't start reading yet
+    let result = loop {
+        unsafe { CancelSynchronousIo(reader.as_raw_handle() as _) };
+        match receiver.recv_timeout(Duration::from_millis(10)) {
+            Ok(result) => break result,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => panic!("Reader thread panicked"),
+        }
+    };
+    reader.join().unwrap();
+
+    let error = result.expect_err("A cancelled read must end the frame read");
+    assert_eq!(
+        error.kind(),
+        serialport::ErrorKind::Io(std::io::ErrorKind::Interrupted)
+    );
+}
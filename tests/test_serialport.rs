@@ -12,6 +12,147 @@ fn test_listing_ports() {
     }
 }
 
+#[test]
+fn test_listing_ports_iter() {
+    let ports: Vec<_> = serialport::available_ports_iter()
+        .expect("No ports found!")
+        .collect::<Result<_>>()
+        .unwrap();
+    let names: Vec<_> = ports.iter().map(|p| &p.port_name).collect();
+    let expected = serialport::available_ports().unwrap();
+    let expected_names: Vec<_> = expected.iter().map(|p| &p.port_name).collect();
+    assert_eq!(names, expected_names);
+}
+
+#[test]
+fn test_listing_ports_best_effort() {
+    let ports = serialport::available_ports_best_effort();
+    for expected in serialport::available_ports().unwrap() {
+        assert!(ports.iter().any(|p| p.port_name == expected.port_name));
+    }
+
+    let mut names: Vec<_> = ports.iter().map(|p| &p.port_name).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), ports.len());
+}
+
+#[test]
+fn test_find_port_rejected() {
+    // No port is accepted, and every port must be closed again after being probed
+    let settings = serialport::new("", 9600).timeout(Duration::from_millis(10));
+    let port = serialport::find_port(&settings, |_| Ok(false)).unwrap();
+    assert!(port.is_none());
+}
+
+#[test]
+fn test_listing_ports_with_signals() {
+    let names: Vec<_> = serialport::available_ports_with_signals()
+        .unwrap()
+        .into_iter()
+        .map(|(info, signals)| {
+            if let Some(signals) = signals {
+                assert!(!signals.intersects(ModemLines::RTS | ModemLines::DTR));
+            }
+            info.port_name
+        })
+        .collect();
+    let expected: Vec<_> = serialport::available_ports()
+        .unwrap()
+        .into_iter()
+        .map(|p| p.port_name)
+        .collect();
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn test_default_port() {
+    let ports = serialport::available_usb_ports().unwrap();
+    let port = serialport::default_port().unwrap();
+    if ports.is_empty() {
+        assert_eq!(port, None);
+    }
+    if let Some(port) = port {
+        assert!(ports.contains(&port));
+    }
+}
+
+#[test]
+fn test_port_set() {
+    let ports = serialport::available_ports().unwrap();
+    let set = serialport::port_set().unwrap();
+    for port in &set {
+        assert!(ports.contains(port));
+    }
+}
+
+#[test]
+fn test_available_ports_matching_product() {
+    let ports = serialport::available_usb_ports().unwrap();
+    for port in serialport::available_ports_matching_product("").unwrap() {
+        assert!(ports.contains(&port));
+    }
+}
+
+#[test]
+fn test_wait_for_port() {
+    let err = serialport::wait_for_port(|_| false, Some(Duration::from_millis(100))).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NoDevice);
+
+    if let Some(first) = serialport::available_ports().unwrap().into_iter().next() {
+        let info = serialport::wait_for_port(|info| info.port_name == first.port_name, None);
+        assert_eq!(info.unwrap().port_name, first.port_name);
+    }
+}
+
+#[test]
+fn test_listing_usb_ports() {
+    let expected: Vec<_> = serialport::available_ports()
+        .unwrap()
+        .into_iter()
+        .filter(|p| matches!(p.port_type, SerialPortType::UsbPort(_)))
+        .map(|p| p.port_name)
+        .collect();
+    let ports: Vec<_> = serialport::available_usb_ports()
+        .unwrap()
+        .into_iter()
+        .map(|p| p.port_name)
+        .collect();
+    assert_eq!(ports, expected);
+}
+
+#[test]
+fn test_listing_ports_matching_name() {
+    let expected: Vec<_> = serialport::available_ports()
+        .unwrap()
+        .into_iter()
+        .filter(|p| {
+            // Without udev, Linux reports the names of the ports below `/sys/class/tty/`
+            let name = p.port_name.rsplit('/').next().unwrap();
+            name.starts_with("ttyS")
+        })
+        .map(|p| p.port_name)
+        .collect();
+    let ports: Vec<_> = serialport::available_ports_matching_name("ttyS*")
+        .unwrap()
+        .into_iter()
+        .map(|p| p.port_name)
+        .collect();
+    assert_eq!(ports, expected);
+
+    assert!(serialport::available_ports_matching_name("ttyS[0-9").is_err());
+}
+
+#[test]
+fn test_opening_missing_usb_interface() {
+    // No device uses the reserved vendor ID 0
+    let settings = serialport::new("", 9600);
+    match serialport::open_usb_interface(0, 0, 1, None, &settings) {
+        Ok(_) => panic!("opened a port of a nonexistent device"),
+        Err(e) => assert_eq!(e.kind(), serialport::ErrorKind::NoDevice),
+    }
+}
+
 #[test]
 fn test_opening_found_ports() {
     let ports = serialport::available_ports().unwrap();
@@ -20,6 +161,15 @@ fn test_opening_found_ports() {
     }
 }
 
+#[test]
+fn test_opening_found_ports_from_info() {
+    let settings = serialport::new("", 9600);
+    let ports = serialport::available_ports().unwrap();
+    for p in ports {
+        let _port = p.open(settings.clone());
+    }
+}
+
 #[test]
 fn test_opening_port() {
     let _port = serialport::new("/dev/ttyUSB0", 9600).open();
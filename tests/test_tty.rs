@@ -1,5 +1,8 @@
 //! Tests for the `posix::TTYPort` struct.
 #![cfg(unix)]
+// `Box<dyn SerialPort>` implements `Debug`, which makes clippy prefer `expect_err()` over
+// `.err().expect()`
+#![allow(clippy::err_expect)]
 
 extern crate serialport;
 
@@ -8,7 +11,13 @@ use std::os::unix::prelude::*;
 use std::str;
 use std::time::Duration;
 
-use serialport::{SerialPort, TTYPort};
+#[cfg(target_os = "linux")]
+use serialport::{ClosingWait, FifoLevel};
+use serialport::{
+    DataBits, Endian, ErrorKind, FlowControl, LengthFieldSize, LineEnding, ModemLines,
+    OverflowPolicy, Parity, RawPortConfig, SerialPort, SerialPortType, StopBits, SupportedOps,
+    TTYPort, TeePort, WakePattern,
+};
 
 #[test]
 fn test_ttyport_pair() {
@@ -133,6 +142,1157 @@ fn test_ttyport_set_standard_baud() {
     assert_eq!(slave.baud_rate().unwrap(), 115_200);
 }
 
+#[test]
+#[cfg_attr(any(target_os = "ios", target_os = "macos"), ignore)]
+fn test_ttyport_with_temporary_baud() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_baud_rate(9600).unwrap();
+
+    let rate = slave
+        .with_temporary_baud(115_200, |port| port.baud_rate())
+        .unwrap();
+    assert_eq!(rate, 115_200);
+    assert_eq!(slave.baud_rate().unwrap(), 9600);
+
+    // The original baud rate is restored when the closure fails
+    let result: serialport::Result<()> = slave.with_temporary_baud(57600, |_| {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "transfer failed",
+        ))
+    });
+    assert_eq!(result.unwrap_err().description, "transfer failed");
+    assert_eq!(slave.baud_rate().unwrap(), 9600);
+}
+
+#[test]
+fn test_ttyport_with_flow_control() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_flow_control(FlowControl::Software).unwrap();
+
+    let mode = slave
+        .with_flow_control(FlowControl::None, |port| port.flow_control())
+        .unwrap();
+    assert_eq!(mode, FlowControl::None);
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::Software);
+
+    // The original mode is restored when the closure fails
+    let result: serialport::Result<()> = slave.with_flow_control(FlowControl::None, |_| {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "transfer failed",
+        ))
+    });
+    assert_eq!(result.unwrap_err().description, "transfer failed");
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::Software);
+}
+
+#[test]
+fn test_ttyport_with_flow_control_keeps_overflow_policy() {
+    use nix::sys::termios::{tcgetattr, InputFlags};
+
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_flow_control(FlowControl::Software).unwrap();
+    slave
+        .set_overflow_policy(OverflowPolicy::DropIncoming)
+        .unwrap();
+
+    // Restoring `FlowControl::Software` would set `IXOFF` again
+    slave
+        .with_flow_control(FlowControl::None, |_| Ok(()))
+        .unwrap();
+    let flags = tcgetattr(slave.as_raw_fd()).unwrap().input_flags;
+    assert!(flags.contains(InputFlags::IXON));
+    assert!(!flags.contains(InputFlags::IXOFF));
+
+    // The same goes for passthrough, which clears both flags
+    slave.set_flow_control_passthrough(true).unwrap();
+    slave
+        .with_flow_control(FlowControl::Software, |port| port.flow_control())
+        .unwrap();
+    let flags = tcgetattr(slave.as_raw_fd()).unwrap().input_flags;
+    assert!(!flags.intersects(InputFlags::IXON | InputFlags::IXOFF));
+}
+
+#[test]
+#[cfg_attr(any(target_os = "ios", target_os = "macos"), ignore)]
+fn test_ttyport_configure() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(250)).unwrap();
+
+    slave.configure("57600,8,N,1").unwrap();
+    assert_eq!(slave.baud_rate().unwrap(), 57600);
+    assert_eq!(slave.timeout(), Duration::from_millis(250));
+
+    let err = slave.configure("9600,8,X,1").unwrap_err();
+    assert_eq!(err.kind(), serialport::ErrorKind::InvalidInput);
+    assert_eq!(slave.baud_rate().unwrap(), 57600);
+}
+
+#[test]
+fn test_ttyport_set_binary_mode() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_secs(1)).unwrap();
+    let mut buf = [0u8; 1];
+
+    // Without binary mode, a received CR is translated to NL
+    slave.set_binary_mode(false).unwrap();
+    master.write_all(b"\r").unwrap();
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\n");
+
+    slave.set_binary_mode(true).unwrap();
+    master.write_all(b"\r").unwrap();
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\r");
+    assert_eq!(slave.data_bits().unwrap(), serialport::DataBits::Eight);
+}
+
+#[test]
+fn test_ttyport_rx_watermark() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_secs(1)).unwrap();
+    assert_eq!(slave.rx_watermark(), 0);
+
+    master.write_all(b"12345").unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(slave.bytes_to_read().unwrap(), 5);
+    let mut buf = [0u8; 5];
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(slave.bytes_to_read().unwrap(), 0);
+    assert_eq!(slave.rx_watermark(), 5);
+
+    slave.reset_rx_watermark();
+    assert_eq!(slave.rx_watermark(), 0);
+}
+
+#[test]
+fn test_ttyport_close_on_exec() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    assert!(master.close_on_exec().unwrap());
+
+    let mut port = serialport::new(slave.name().unwrap(), 9600)
+        .open()
+        .expect("Unable to open the slave");
+    assert!(port.close_on_exec().unwrap());
+    assert!(port.try_clone().unwrap().close_on_exec().unwrap());
+
+    port.set_close_on_exec(false).unwrap();
+    assert!(!port.close_on_exec().unwrap());
+    port.set_close_on_exec(true).unwrap();
+    assert!(port.close_on_exec().unwrap());
+}
+
+#[test]
+fn test_ttyport_open_with_raw_config() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+
+    // Capture a configuration that translates CR to NL, then change it
+    slave.set_binary_mode(false).unwrap();
+    let config = RawPortConfig::from_port(&slave).unwrap();
+    slave.set_binary_mode(true).unwrap();
+    drop(slave);
+
+    let mut port = TTYPort::open_with_raw_config(&name, &config).expect("Unable to open the slave");
+    port.set_timeout(Duration::from_secs(1)).unwrap();
+    master.write_all(b"\r").unwrap();
+    let mut buf = [0u8; 1];
+    port.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\n");
+}
+
+#[test]
+fn test_ttyport_raw_config_new() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+
+    // Build a configuration that translates CR to NL from the raw mode of the slave
+    let mut termios = *RawPortConfig::from_port(&slave).unwrap().termios();
+    termios.c_iflag |= nix::libc::ICRNL;
+    let config = RawPortConfig::new(termios);
+    drop(slave);
+
+    let mut port =
+        serialport::open_with_raw_config(&name, config).expect("Unable to open the slave");
+    port.set_timeout(Duration::from_secs(1)).unwrap();
+    master.write_all(b"\r").unwrap();
+    let mut buf = [0u8; 1];
+    port.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\n");
+}
+
+#[test]
+fn test_ttyport_inter_byte_write_delay() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    assert_eq!(slave.inter_byte_write_delay(), Duration::from_secs(0));
+    slave
+        .set_inter_byte_write_delay(Duration::from_millis(20))
+        .unwrap();
+    assert_eq!(slave.inter_byte_write_delay(), Duration::from_millis(20));
+
+    let start = std::time::Instant::now();
+    slave.write_all(b"abc").unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(40));
+
+    let mut buf = [0u8; 3];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"abc");
+}
+
+#[test]
+fn test_ttyport_port_type() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Pseudo terminals aren't listed by `available_ports()`
+    assert_eq!(slave.port_type(), SerialPortType::Unknown);
+    let port = TTYPort::open(&serialport::new(slave.name().unwrap(), 9600)).unwrap();
+    assert_eq!(port.port_type(), SerialPortType::Unknown);
+}
+
+#[test]
+fn test_ttyport_spawn_reader() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let reader = slave
+        .spawn_reader(move |data| tx.send(data.to_vec()).unwrap())
+        .unwrap();
+
+    master.write_all(b"pushed").unwrap();
+    let mut received = Vec::new();
+    while received.len() < 6 {
+        received.extend(rx.recv_timeout(Duration::from_secs(1)).unwrap());
+    }
+    assert_eq!(received, b"pushed");
+
+    assert!(!reader.is_finished());
+    reader.stop().unwrap();
+}
+
+#[test]
+fn test_ttyport_spawn_reader_boxed() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(10)).unwrap();
+
+    // Like the ports returned by `open()`
+    let slave: Box<dyn SerialPort> = Box::new(slave);
+    let (tx, rx) = std::sync::mpsc::channel();
+    let reader = slave
+        .spawn_reader(move |data| tx.send(data.to_vec()).unwrap())
+        .unwrap();
+
+    master.write_all(b"boxed").unwrap();
+    let mut received = Vec::new();
+    while received.len() < 5 {
+        received.extend(rx.recv_timeout(Duration::from_secs(1)).unwrap());
+    }
+    assert_eq!(received, b"boxed");
+
+    // The thread only waits for the port's timeout before checking whether to stop
+    let start = std::time::Instant::now();
+    reader.stop().unwrap();
+    assert!(start.elapsed() < Duration::from_millis(90));
+}
+
+#[test]
+fn test_ttyport_read_to_channel() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(50)).unwrap();
+
+    let (tx, rx) = std::sync::mpsc::sync_channel(1);
+    assert_eq!(
+        slave.read_to_channel(tx.clone(), 0).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+    let reader = slave.read_to_channel(tx, 4).unwrap();
+
+    master.write_all(b"chunked").unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), b"chun");
+    assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), b"ked");
+
+    assert!(!reader.is_finished());
+    reader.stop().unwrap();
+}
+
+#[test]
+fn test_ttyport_set_data_bits_verified() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Pseudo terminals always use 8 data bits
+    slave.set_data_bits(DataBits::Eight).unwrap();
+    let err = slave.set_data_bits(DataBits::Seven).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(slave.data_bits().unwrap(), DataBits::Eight);
+}
+
+#[test]
+fn test_ttyport_buffer_capacity() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let err = slave.rx_buffer_capacity().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unknown);
+    let err = slave.tx_buffer_capacity().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unknown);
+}
+
+#[test]
+fn test_ttyport_read_to_end_times_out() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(50)).unwrap();
+
+    // A timeout must not be mistaken for the end of the stream
+    let mut text = String::new();
+    let err = slave.read_to_string(&mut text).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(text.is_empty());
+
+    master.write_all(b"partial").unwrap();
+    let mut data = Vec::new();
+    let err = slave.read_to_end(&mut data).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert_eq!(data, b"partial");
+
+    // The same holds with a `VMIN` of 0, where the driver itself may return no data
+    slave.set_read_mode(0, Duration::from_millis(100)).unwrap();
+    let err = slave.read_to_end(&mut data).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
+
+#[test]
+fn test_ttyport_reopen() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let mut port = TTYPort::open(&serialport::new(slave.name().unwrap(), 9600)).unwrap();
+    port.set_baud_rate(19200).unwrap();
+    port.set_timeout(Duration::from_millis(250)).unwrap();
+    port.set_line_ending(LineEnding::CrLf);
+
+    port.reopen().unwrap();
+    assert_eq!(port.baud_rate().unwrap(), 19200);
+    assert_eq!(port.timeout(), Duration::from_millis(250));
+    assert_eq!(port.line_ending(), LineEnding::CrLf);
+
+    master.write_all(b"x").unwrap();
+    let mut buf = [0u8; 1];
+    port.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"x");
+
+    // `master` has no path to reopen
+    let (mut master, _slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let err = master.reopen().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_set_modem_lines_masked_validation() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Input signals can't be changed, and a signal can't be both set and cleared
+    let err = slave
+        .set_modem_lines_masked(ModemLines::CTS, ModemLines::empty())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = slave
+        .set_modem_lines_masked(ModemLines::RTS, ModemLines::RTS | ModemLines::DTR)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_set_burst_read_timeout() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave
+        .set_burst_read_timeout(Duration::from_millis(500), Duration::from_millis(200))
+        .expect("Unable to set the burst read timeout");
+    assert_eq!(slave.timeout(), Duration::from_millis(500));
+
+    let writer = std::thread::spawn(move || {
+        master.write_all(b"ab").unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        master.write_all(b"cd").unwrap();
+        master
+    });
+
+    // The whole burst is returned by one read, even though it's shorter than `buf`
+    let mut buf = [0u8; 8];
+    let nbytes = slave.read(&mut buf).expect("Unable to read bytes.");
+    assert_eq!(&buf[..nbytes], b"abcd");
+    let _master = writer.join().unwrap();
+
+    let err = slave.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    let err = slave
+        .set_burst_read_timeout(Duration::from_millis(500), Duration::from_secs(0))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_supports_hardware_flow_control() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (master, _slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Pseudo terminals have no chipset to look up
+    let err = master.supports_hardware_flow_control().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unknown);
+}
+
+#[test]
+fn test_ttyport_tee() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let mut tee = TeePort::new(Box::new(slave), Vec::new());
+
+    tee.write_all(b"AT\r").unwrap();
+    let mut buf = [0u8; 3];
+    master.read_exact(&mut buf).unwrap();
+    master.write_all(b"OK").unwrap();
+    let mut buf = [0u8; 2];
+    tee.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"OK");
+    tee.set_break().unwrap();
+    tee.clear_break().unwrap();
+
+    let (_port, log) = tee.into_inner();
+    let log = String::from_utf8(log).unwrap();
+    let lines: Vec<&str> = log.lines().map(|line| &line[15..]).collect();
+    assert_eq!(lines[0], format!("TX 41 54 0d{:40}|AT.|", ""));
+    assert_eq!(lines[1], format!("RX 4f 4b{:43}|OK|", ""));
+    assert_eq!(&lines[2..], ["break on", "break off"]);
+}
+
+#[test]
+fn test_ttyport_swap_settings() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let before = slave.settings().unwrap();
+
+    // The path of `new` is ignored. Pseudo terminals force 8 data bits and no parity.
+    let new = serialport::new("", 19200)
+        .stop_bits(StopBits::Two)
+        .timeout(Duration::from_millis(250));
+    let old = slave.swap_settings(&new).unwrap();
+    assert_eq!(old, before);
+    assert_eq!(slave.baud_rate().unwrap(), 19200);
+    assert_eq!(slave.stop_bits().unwrap(), StopBits::Two);
+    assert_eq!(slave.timeout(), Duration::from_millis(250));
+
+    let swapped = slave.swap_settings(&old).unwrap();
+    assert_eq!(swapped, new.path(slave.name().unwrap()));
+    assert_eq!(slave.settings().unwrap(), before);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_ttyport_swap_settings_rolls_back() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let before = slave.settings().unwrap();
+
+    // Linux pseudo terminals accept the baud rate and stop bits but silently drop the parity
+    let new = serialport::new("", 19200)
+        .stop_bits(StopBits::Two)
+        .parity(Parity::Odd)
+        .timeout(Duration::from_millis(250));
+    let err = slave.swap_settings(&new).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(slave.settings().unwrap(), before);
+}
+
+#[test]
+fn test_ttyport_is_virtual() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    assert!(master.is_virtual());
+    assert!(slave.is_virtual());
+
+    let port = TTYPort::open(&serialport::new(slave.name().unwrap(), 9600)).unwrap();
+    assert!(port.is_virtual());
+}
+
+#[test]
+fn test_ttyport_set_overflow_policy() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let err = slave
+        .set_overflow_policy(OverflowPolicy::AssertFlowControl)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    slave
+        .set_overflow_policy(OverflowPolicy::DropIncoming)
+        .unwrap();
+
+    slave.set_flow_control(FlowControl::Software).unwrap();
+    slave
+        .set_overflow_policy(OverflowPolicy::DropIncoming)
+        .unwrap();
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::Software);
+    slave
+        .set_overflow_policy(OverflowPolicy::AssertFlowControl)
+        .unwrap();
+
+    slave.set_flow_control(FlowControl::Hardware).unwrap();
+    let err = slave
+        .set_overflow_policy(OverflowPolicy::DropIncoming)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_port_index() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    assert_eq!(master.port_index(), None);
+
+    // The slave is `/dev/pts/N` on Linux and `/dev/ttysNNN` on macOS
+    let name = slave.name().unwrap();
+    let number = name.trim_start_matches(|c: char| !c.is_ascii_digit());
+    assert_eq!(slave.port_index(), number.parse().ok());
+}
+
+#[test]
+fn test_ttyport_queue_status() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    master.write_all(b"queued").unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    let status = slave.queue_status().unwrap();
+    assert_eq!(status.bytes_to_read, 6);
+    assert_eq!(status.bytes_to_write, 0);
+    assert_eq!(status.rx_capacity, None);
+    assert_eq!(status.tx_capacity, None);
+}
+
+#[test]
+fn test_ttyport_set_write_unbuffered() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    assert!(!slave.write_unbuffered());
+    slave.set_write_unbuffered(true).unwrap();
+    assert!(slave.write_unbuffered());
+
+    slave.write_all(b"drained").unwrap();
+    assert_eq!(slave.bytes_to_write().unwrap(), 0);
+    let mut buf = [0u8; 7];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"drained");
+
+    // Clones keep the setting
+    let clone = slave.try_clone_native().unwrap();
+    assert!(clone.write_unbuffered());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_ttyport_disconnect_is_no_device() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(100)).unwrap();
+
+    // Closing the master hangs up the slave the same way unplugging a USB adapter does
+    drop(master);
+
+    let mut buf = [0u8; 4];
+    let err = slave.read(&mut buf).unwrap_err();
+    assert_eq!(serialport::Error::from(err).kind(), ErrorKind::NoDevice);
+
+    let err = slave.write(b"data").unwrap_err();
+    assert_eq!(serialport::Error::from(err).kind(), ErrorKind::NoDevice);
+}
+
+#[test]
+fn test_ttyport_open_flags() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+
+    let link = std::env::temp_dir().join(format!("serialport-test-link-{}", std::process::id()));
+    let _ = std::fs::remove_file(&link);
+    std::os::unix::fs::symlink(&name, &link).unwrap();
+    let link_name = link.to_str().unwrap().to_owned();
+
+    let followed = serialport::new(&link_name, 9600).open_native();
+    let refused = serialport::new(&link_name, 9600)
+        .follow_symlinks(false)
+        .open_native();
+    std::fs::remove_file(&link).unwrap();
+    drop(followed.expect("Unable to open the port through a symbolic link"));
+    assert!(refused.is_err());
+
+    let mut port = serialport::new(&name, 9600)
+        .append(true)
+        .open_native()
+        .unwrap();
+    port.write_all(b"append").unwrap();
+    let mut buf = [0u8; 6];
+    master.set_timeout(Duration::from_millis(100)).unwrap();
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"append");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_ttyport_closing_wait() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Pseudo terminals don't support `TIOCGSERIAL`, but invalid durations are rejected first
+    assert!(slave.closing_wait().is_err());
+    let err = slave
+        .set_closing_wait(ClosingWait::Duration(Duration::from_secs(0)))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(slave.set_closing_wait(ClosingWait::None).is_err());
+}
+
+#[test]
+fn test_ttyport_open_raw() {
+    use nix::sys::termios::{tcgetattr, tcsetattr, LocalFlags, SetArg};
+
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+
+    // Configure the device the way `stty echo icanon` would
+    let mut termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    termios.local_flags |= LocalFlags::ECHO | LocalFlags::ICANON;
+    tcsetattr(slave.as_raw_fd(), SetArg::TCSANOW, &termios).unwrap();
+
+    let port = serialport::new(&name, 9600)
+        .stop_bits(StopBits::Two)
+        .raw(false)
+        .open_native()
+        .unwrap();
+    let termios = tcgetattr(port.as_raw_fd()).unwrap();
+    assert!(termios
+        .local_flags
+        .contains(LocalFlags::ECHO | LocalFlags::ICANON));
+    assert_eq!(port.stop_bits().unwrap(), StopBits::Two);
+    drop(port);
+
+    let port = serialport::new(&name, 9600).open_native().unwrap();
+    let termios = tcgetattr(port.as_raw_fd()).unwrap();
+    assert!(!termios
+        .local_flags
+        .intersects(LocalFlags::ECHO | LocalFlags::ICANON));
+}
+
+#[test]
+fn test_ttyport_set_timeout_for_bytes() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // 96 characters of 10 bits at 9600 baud take 100ms
+    slave.set_baud_rate(9600).unwrap();
+    slave
+        .set_timeout_for_bytes(96, Duration::from_millis(20))
+        .unwrap();
+    assert_eq!(slave.timeout(), Duration::from_millis(120));
+}
+
+#[test]
+fn test_ttyport_capabilities() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Pseudo terminals have no chipset, so only the platform's limits apply
+    let capabilities = slave.capabilities().unwrap();
+    assert_eq!(capabilities.baud_rates, None);
+    assert_eq!(capabilities.max_baud_rate, None);
+    assert_eq!(capabilities.data_bits.len(), 4);
+    assert_eq!(capabilities.stop_bits, vec![StopBits::One, StopBits::Two]);
+    assert!(capabilities.flow_control.contains(&FlowControl::Hardware));
+    #[cfg(all(
+        target_os = "linux",
+        target_env = "gnu",
+        not(any(target_arch = "powerpc", target_arch = "powerpc64"))
+    ))]
+    assert_eq!(capabilities.arbitrary_baud_rates, Some(true));
+}
+
+#[test]
+fn test_ttyport_set_flow_control_passthrough() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_flow_control(FlowControl::Software).unwrap();
+
+    slave.set_flow_control_passthrough(true).unwrap();
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::None);
+    master.write_all(&[0x13, b'x', 0x11]).unwrap();
+    let mut buf = [0u8; 3];
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [0x13, b'x', 0x11]);
+
+    slave.set_flow_control_passthrough(false).unwrap();
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::Software);
+
+    slave.set_flow_control(FlowControl::Hardware).unwrap();
+    slave.set_flow_control_passthrough(true).unwrap();
+    let err = slave.set_flow_control_passthrough(false).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_wake() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(100)).unwrap();
+
+    slave.wake(WakePattern::NulBytes(3)).unwrap();
+    let mut buf = [0xffu8; 3];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [0, 0, 0]);
+
+    slave.wake(WakePattern::NulBytes(0)).unwrap();
+    assert_eq!(master.bytes_to_read().unwrap(), 0);
+}
+
+#[test]
+fn test_ttyport_measure_throughput() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Without anything echoing the data, none of it comes back
+    let stats = slave
+        .measure_throughput(100, 10, Duration::from_millis(50))
+        .unwrap();
+    assert_eq!(stats.bytes_written, 100);
+    assert_eq!(stats.bytes_read, 0);
+    assert_eq!(stats.missing(), 100);
+    let mut buf = [0u8; 100];
+    master.read_exact(&mut buf).unwrap();
+
+    let echo = std::thread::spawn(move || {
+        let mut buf = [0u8; 64];
+        let mut echoed = 0;
+        while echoed < 1000 {
+            let n = master.read(&mut buf).unwrap();
+            master.write_all(&buf[..n]).unwrap();
+            echoed += n;
+        }
+        master
+    });
+    let stats = slave
+        .measure_throughput(1000, 64, Duration::from_secs(1))
+        .unwrap();
+    let _master = echo.join().unwrap();
+    assert_eq!(stats.bytes_read, 1000);
+    assert_eq!(stats.missing(), 0);
+    assert_eq!(stats.corrupted, 0);
+    assert!(stats.read_time > Duration::from_secs(0));
+    assert!(stats.read_rate() > 0.0);
+    assert!(stats.write_rate() > 0.0);
+
+    let err = slave
+        .measure_throughput(10, 0, Duration::from_millis(50))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_hardware_flow_diagnostic() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // A pseudo terminal has no modem lines, so nothing is reported as blocked
+    for &mode in &[FlowControl::None, FlowControl::Hardware] {
+        slave.set_flow_control(mode).unwrap();
+        let status = slave.hardware_flow_diagnostic().unwrap();
+        assert_eq!(status.flow_control, mode);
+        assert_eq!(status.clear_to_send, None);
+        assert_eq!(status.rts, None);
+        assert!(!status.write_blocked);
+    }
+}
+
+#[test]
+fn test_ttyport_open_with_baud_rate_tolerance() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+
+    let port = serialport::new(&name, 9600).open_native().unwrap();
+    assert_eq!(port.baud_rate_tolerance(), 2);
+    drop(port);
+
+    let port = serialport::new(&name, 9600)
+        .baud_rate_tolerance(5)
+        .open_native()
+        .unwrap();
+    assert_eq!(port.baud_rate_tolerance(), 5);
+}
+
+#[test]
+fn test_ttyport_supported_operations() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // A pseudo terminal has no line to break and no UART behind it
+    for port in &[master, slave] {
+        let ops = port.supported_operations();
+        assert!(!ops.contains(SupportedOps::BREAK));
+        assert!(!ops.contains(SupportedOps::CUSTOM_DIVISOR));
+        assert!(!ops.contains(SupportedOps::RS485_RTS_TOGGLE));
+        assert_eq!(
+            ops.contains(SupportedOps::MODEM_LINES),
+            port.modem_lines().is_ok()
+        );
+    }
+}
+
+#[test]
+fn test_ttyport_describe() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(250)).unwrap();
+
+    let description = slave.describe();
+    assert!(description.starts_with(&slave.name().unwrap()));
+    assert!(description.contains("8N1"));
+    assert!(description.ends_with("timeout 250ms"));
+
+    let port: Box<dyn SerialPort> = Box::new(slave);
+    assert_eq!(format!("{:?}", port), description);
+}
+
+#[test]
+fn test_open_all() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master1, slave1) = TTYPort::pair().expect("Unable to create ptty pair");
+    let (_master2, slave2) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name1 = slave1.name().unwrap();
+    let name2 = slave2.name().unwrap();
+
+    let ports = serialport::open_all(&[
+        serialport::new(&name1, 9600),
+        serialport::new(&name2, 115_200),
+    ])
+    .unwrap();
+    assert_eq!(ports.len(), 2);
+    assert_eq!(ports[0].name(), Some(name1.clone()));
+    assert_eq!(ports[1].name(), Some(name2.clone()));
+    assert_eq!(ports[1].baud_rate().unwrap(), 115_200);
+    drop(ports);
+
+    let err = serialport::open_all(&[
+        serialport::new(&name1, 9600),
+        serialport::new("/dev/nonexistent-serial-port", 9600),
+        serialport::new(&name2, 9600),
+    ])
+    .unwrap_err();
+    assert!(err
+        .description
+        .starts_with("/dev/nonexistent-serial-port: "));
+
+    // The ports opened before the failure were closed again
+    serialport::new(&name1, 9600).open().unwrap();
+}
+
+#[test]
+fn test_ttyport_baud_rate_info() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Without a builder the requested rate falls back to the one from the driver
+    slave.set_baud_rate(19_200).unwrap();
+    let info = slave.baud_rate_info().unwrap();
+    assert_eq!(info.requested, 19_200);
+    #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+    assert_eq!(info.actual, Some(19_200));
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    assert_eq!(info.actual, None);
+
+    let port = serialport::new(slave.name().unwrap(), 57_600)
+        .open()
+        .unwrap();
+    let info = port.baud_rate_info().unwrap();
+    assert_eq!(info.requested, 57_600);
+    #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+    assert_eq!(info.actual, Some(57_600));
+}
+
+#[test]
+fn test_ttyport_read_with_parity() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let mut buf = [0; 4];
+    let mut bits = [false; 4];
+    let err = slave
+        .read_with_parity(&mut buf, &mut bits[..2])
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.description.contains("shorter"));
+
+    let err = slave.read_with_parity(&mut buf, &mut bits).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.description.contains("parity disabled"));
+
+    slave.set_parity(Parity::Even).unwrap();
+    slave.set_parity_marking(true).unwrap();
+    if cfg!(target_os = "linux") {
+        // Linux pseudo terminals clear `PARENB`, so the parity reads back as disabled
+        assert_eq!(slave.parity().unwrap(), Parity::None);
+        let err = slave.read_with_parity(&mut buf, &mut bits).unwrap_err();
+        assert!(err.description.contains("parity disabled"));
+    } else {
+        slave.set_parity_marking(false).unwrap();
+        let err = slave.read_with_parity(&mut buf, &mut bits).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.description.contains("marking"));
+    }
+    slave.set_parity_marking(false).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_ttyport_rx_fifo_trigger() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Pseudo terminals have no UART behind them
+    let err = slave.rx_fifo_trigger().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = slave.set_rx_fifo_trigger(FifoLevel::Bytes1).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    // The transmit level is checked first, as no UART supports setting it
+    let err = slave
+        .set_fifo_trigger(FifoLevel::Bytes1, Some(FifoLevel::Bytes4))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.description.contains("transmit"));
+
+    // Without a transmit level only the receive level is applied
+    let err = slave.set_fifo_trigger(FifoLevel::Bytes1, None).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(!err.description.contains("transmit"));
+}
+
+#[test]
+fn test_ttyport_ignore_hup() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let mut port = serialport::new(slave.name().unwrap(), 9600)
+        .open_native()
+        .unwrap();
+    assert!(port.ignore_hup().unwrap());
+    port.set_ignore_hup(false).unwrap();
+    assert!(!port.ignore_hup().unwrap());
+    port.set_ignore_hup(true).unwrap();
+    assert!(port.ignore_hup().unwrap());
+}
+
+#[test]
+fn test_ttyport_max_read_size() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    assert_eq!(slave.max_read_size(), None);
+
+    slave.set_max_read_size(Some(3)).unwrap();
+    assert_eq!(slave.max_read_size(), Some(3));
+    master.write_all(b"fragmented").unwrap();
+    let mut buf = [0u8; 16];
+    let mut received = Vec::new();
+    while received.len() < 10 {
+        let n = slave.read(&mut buf).unwrap();
+        assert!(n <= 3);
+        received.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(received, b"fragmented");
+
+    let err = slave.set_max_read_size(Some(0)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(slave.max_read_size(), Some(3));
+
+    slave.set_max_read_size(None).unwrap();
+    master.write_all(b"whole").unwrap();
+    let mut buf = [0u8; 5];
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"whole");
+}
+
+#[test]
+fn test_ttyport_safe_reconfigure() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Input received with the old settings is discarded
+    master.write_all(b"stale").unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    slave
+        .safe_reconfigure(&serialport::new("", 19_200).stop_bits(StopBits::Two))
+        .unwrap();
+    assert_eq!(slave.bytes_to_read().unwrap(), 0);
+    assert_eq!(slave.baud_rate().unwrap(), 19_200);
+    assert_eq!(slave.stop_bits().unwrap(), StopBits::Two);
+
+    // Linux pseudo terminals always clear the parity bit
+    #[cfg(target_os = "linux")]
+    {
+        let err = slave
+            .safe_reconfigure(&serialport::new("", 9600).parity(Parity::Even))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(slave.baud_rate().unwrap(), 19_200);
+        assert_eq!(slave.parity().unwrap(), Parity::None);
+    }
+}
+
+#[test]
+fn test_ttyport_timeout_resolution() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let resolution = slave.timeout_resolution();
+    assert_eq!(resolution, Duration::from_millis(1));
+
+    // A timeout below the resolution doesn't wait at all
+    slave.set_timeout(resolution / 2).unwrap();
+    let start = std::time::Instant::now();
+    let mut buf = [0u8; 1];
+    let err = slave.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(start.elapsed() < Duration::from_millis(100));
+
+    master.write_all(b"x").unwrap();
+    slave.set_timeout(Duration::from_millis(100)).unwrap();
+    slave.read_exact(&mut buf).unwrap();
+}
+
+#[test]
+fn test_with_port() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let settings = serialport::new(slave.name().unwrap(), 9600).timeout(Duration::from_millis(100));
+
+    let baud_rate = serialport::with_port(&settings, |port| {
+        port.write_all(b"hi")?;
+        port.baud_rate()
+    })
+    .unwrap();
+    assert_eq!(baud_rate, 9600);
+    let mut buf = [0u8; 2];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hi");
+
+    // Errors of the closure are passed on
+    let err = serialport::with_port(&settings, |port| {
+        let mut buf = [0u8; 1];
+        port.read_exact(&mut buf)?;
+        Ok(())
+    })
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(std::io::ErrorKind::TimedOut));
+
+    // The port is closed while unwinding from a panic
+    let result = std::panic::catch_unwind(|| {
+        serialport::with_port(&settings, |_| -> serialport::Result<()> { panic!("oops") })
+    });
+    assert!(result.is_err());
+    settings.clone().open().unwrap();
+}
+
+#[test]
+fn test_ttyport_can_write() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (master, _slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // The tty layer doesn't report the size of its output buffer
+    let err = master
+        .can_write(1)
+        .expect_err("Output buffer size is unknown");
+    assert_eq!(err.kind(), ErrorKind::Unknown);
+}
+
+#[test]
+fn test_ttyport_close_behavior() {
+    use nix::sys::termios::{tcgetattr, tcsetattr, ControlFlags, SetArg};
+
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+
+    let mut termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    termios.control_flags |= ControlFlags::HUPCL;
+    tcsetattr(slave.as_raw_fd(), SetArg::TCSANOW, &termios).unwrap();
+
+    // Closing a port without a close behavior leaves `HUPCL` alone
+    let port = serialport::new(&name, 9600).open_native().unwrap();
+    drop(port);
+    let termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    assert!(termios.control_flags.contains(ControlFlags::HUPCL));
+
+    // A pseudo terminal has no modem lines to write, but `HUPCL` is cleared anyway
+    let mut port = serialport::new(&name, 9600).open_native().unwrap();
+    port.set_close_behavior(Some(true), None).unwrap();
+    drop(port);
+    let termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    assert!(!termios.control_flags.contains(ControlFlags::HUPCL));
+
+    // The close behavior is applied as well when `reopen()` closes the old descriptor
+    let mut termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    termios.control_flags |= ControlFlags::HUPCL;
+    tcsetattr(slave.as_raw_fd(), SetArg::TCSANOW, &termios).unwrap();
+    let mut port = serialport::new(&name, 9600).open_native().unwrap();
+    port.set_close_behavior(Some(true), None).unwrap();
+    port.reopen().unwrap();
+    let termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    assert!(!termios.control_flags.contains(ControlFlags::HUPCL));
+}
+
+#[test]
+fn test_ttyport_set_parity_check() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    slave.set_parity_check(true).unwrap();
+    assert!(slave.parity_check().unwrap());
+    slave.set_parity_check(false).unwrap();
+    assert!(!slave.parity_check().unwrap());
+}
+
 // On mac this fails because you can't set nonstandard baud rates for these virtual ports
 #[test]
 #[cfg_attr(
@@ -158,3 +1318,514 @@ fn test_ttyport_set_nonstandard_baud() {
     slave.set_baud_rate(1_200_000).unwrap();
     assert_eq!(slave.baud_rate().unwrap(), 1_200_000);
 }
+
+#[test]
+fn test_ttyport_settings_reflect_other_handles() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let mut clone = slave.try_clone_native().expect("Failed to clone");
+
+    // Linux pseudo terminals always force 8 data bits and no parity, so only change the settings
+    // that will be kept by every platform.
+    clone.set_flow_control(FlowControl::Software).unwrap();
+    clone.set_stop_bits(StopBits::Two).unwrap();
+    let settings = slave.settings().unwrap();
+    assert_eq!(
+        settings,
+        serialport::new(slave.name().unwrap(), slave.baud_rate().unwrap())
+            .data_bits(slave.data_bits().unwrap())
+            .flow_control(FlowControl::Software)
+            .parity(slave.parity().unwrap())
+            .stop_bits(StopBits::Two)
+            .timeout(slave.timeout())
+    );
+
+    clone.set_stop_bits(StopBits::One).unwrap();
+    assert_eq!(slave.stop_bits().unwrap(), StopBits::One);
+    assert_ne!(slave.settings().unwrap(), settings);
+}
+
+#[test]
+#[cfg_attr(any(target_os = "ios", target_os = "macos"), ignore)]
+fn test_ttyport_is_baud_rate_supported() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    slave.set_baud_rate(9600).unwrap();
+    assert!(slave.is_baud_rate_supported(115_200).unwrap());
+    assert_eq!(slave.baud_rate().unwrap(), 9600);
+}
+
+#[test]
+fn test_ttyport_detect_flow_control() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_secs(1)).unwrap();
+    slave.set_flow_control(FlowControl::Software).unwrap();
+
+    let responder = std::thread::spawn(move || {
+        let mut buf = [0u8; 2];
+        master.read_exact(&mut buf).unwrap();
+        master.write_all(b"OK").unwrap();
+        master.read_exact(&mut buf).unwrap();
+        master.write_all(b"\x13OK").unwrap();
+        // Keep the master open until the slave is done with it.
+        master
+    });
+
+    let timeout = Duration::from_millis(500);
+    assert_eq!(
+        slave.detect_flow_control(b"AT", timeout).unwrap(),
+        FlowControl::None
+    );
+    assert_eq!(
+        slave.detect_flow_control(b"AT", timeout).unwrap(),
+        FlowControl::Software
+    );
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::Software);
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+
+    responder.join().unwrap();
+}
+
+#[test]
+fn test_ttyport_transaction() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_secs(1)).unwrap();
+
+    // Stale input must not be mistaken for the reply
+    master.write_all(b"stale").unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+
+    let responder = std::thread::spawn(move || {
+        let mut buf = [0u8; 4];
+        master.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"PING");
+        master.write_all(b"PONG").unwrap();
+        // Keep the master open until the slave is done with it.
+        master
+    });
+
+    let mut response = [0u8; 8];
+    let n = slave
+        .transaction(b"PING", &mut response, Duration::from_millis(500))
+        .unwrap();
+    assert_eq!(&response[..n], b"PONG");
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+
+    responder.join().unwrap();
+}
+
+#[test]
+fn test_ttyport_measure_latency() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_secs(1)).unwrap();
+
+    // Answer the first two probes and ignore the third one
+    let responder = std::thread::spawn(move || {
+        let mut buf = [0u8; 4];
+        for _ in 0..2 {
+            master.read_exact(&mut buf).unwrap();
+            master.write_all(b"PONG").unwrap();
+        }
+        master.read_exact(&mut buf).unwrap();
+        master
+    });
+
+    let stats = slave
+        .measure_latency(b"PING", b"PONG", 3, Duration::from_millis(200))
+        .unwrap();
+    assert_eq!(stats.replies, 2);
+    assert_eq!(stats.timeouts, 1);
+    let (min, max, mean) = (stats.min.unwrap(), stats.max.unwrap(), stats.mean.unwrap());
+    assert!(min <= mean && mean <= max);
+    assert!(max < Duration::from_millis(200));
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+
+    let _master = responder.join().unwrap();
+}
+
+#[test]
+fn test_ttyport_write_line() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_secs(1)).unwrap();
+
+    let mut port = serialport::new(slave.name().unwrap(), 9600)
+        .line_ending(LineEnding::CrLf)
+        .open()
+        .expect("Unable to open the slave");
+    assert_eq!(port.line_ending(), LineEnding::CrLf);
+    port.write_line("AT\nI").unwrap();
+    let mut buf = [0u8; 7];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"AT\r\nI\r\n");
+
+    port.set_line_ending(LineEnding::None);
+    port.write_str("x\r\n").unwrap();
+    let mut buf = [0u8; 3];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"x\r\n");
+}
+
+#[test]
+fn test_ttyport_read_length_prefixed() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // A frame split over several writes, with a little-endian length at offset 1
+    let sender = std::thread::spawn(move || {
+        master.write_all(&[0xAA, 0x03]).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        master.write_all(&[0x00, 1, 2]).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        master.write_all(&[3, 0xFF]).unwrap();
+        master
+    });
+    let frame = slave
+        .read_length_prefixed(
+            3,
+            1,
+            LengthFieldSize::Two,
+            Endian::Little,
+            Duration::from_secs(1),
+        )
+        .unwrap();
+    assert_eq!(frame, [0xAA, 0x03, 0x00, 1, 2, 3]);
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+    let mut master = sender.join().unwrap();
+
+    // The trailing byte is left in the buffer, and the payload never arrives
+    master.write_all(&[0x05]).unwrap();
+    let err = slave
+        .read_length_prefixed_max(
+            2,
+            0,
+            LengthFieldSize::One,
+            Endian::Big,
+            512,
+            Duration::from_millis(100),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut)
+    );
+
+    // A length field announcing more than `max_len` is rejected without reading the payload
+    master.write_all(&[0xFF, 0xFF, 0xFF, 0xFF, 0x42]).unwrap();
+    let err = slave
+        .read_length_prefixed_max(
+            4,
+            0,
+            LengthFieldSize::Four,
+            Endian::Big,
+            1024,
+            Duration::from_millis(100),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        serialport::ErrorKind::Io(std::io::ErrorKind::InvalidData)
+    );
+    let mut byte = [0u8];
+    slave.read_exact(&mut byte).unwrap();
+    assert_eq!(byte, [0x42]);
+
+    let err = slave
+        .read_length_prefixed_max(
+            2,
+            1,
+            LengthFieldSize::Two,
+            Endian::Big,
+            64,
+            Duration::from_millis(100),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), serialport::ErrorKind::InvalidInput);
+
+    let err = slave
+        .read_length_prefixed_max(
+            4,
+            0,
+            LengthFieldSize::Two,
+            Endian::Big,
+            3,
+            Duration::from_millis(100),
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), serialport::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_flush_and_wait_quiescent() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    slave.write_all(b"bye").unwrap();
+    slave
+        .flush_and_wait_quiescent(Duration::from_millis(50), Duration::from_secs(1))
+        .unwrap();
+
+    // A steady stream of input never becomes quiet
+    let sender = std::thread::spawn(move || {
+        for _ in 0..40 {
+            master.write_all(b".").unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        master
+    });
+    let err = slave
+        .flush_and_wait_quiescent(Duration::from_millis(100), Duration::from_millis(250))
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut)
+    );
+    let _master = sender.join().unwrap();
+
+    // The received bytes were left in the buffer
+    assert!(slave.bytes_to_read().unwrap() > 0);
+}
+
+#[test]
+fn test_ttyport_has_data() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_secs(1)).unwrap();
+
+    assert!(!slave.has_data(Duration::from_secs(0)).unwrap());
+    assert!(!slave.has_data(Duration::from_millis(50)).unwrap());
+
+    master.write_all(b"x").unwrap();
+    assert!(slave.has_data(Duration::from_secs(1)).unwrap());
+    // The byte is still there to be read
+    assert!(slave.has_data(Duration::from_secs(0)).unwrap());
+    let mut buf = [0u8; 1];
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"x");
+}
+
+#[test]
+fn test_ttyport_open_settle_delay() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+    drop(slave);
+
+    let start = std::time::Instant::now();
+    let port = serialport::new(name, 9600)
+        .settle_delay(Some(Duration::from_millis(50)))
+        .open()
+        .expect("Unable to open the slave");
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn test_ttyport_wait_for_sequence() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    master.write_all(b"noise aaab rest").unwrap();
+    let received = slave
+        .wait_for_sequence(b"aab", Duration::from_millis(500))
+        .unwrap();
+    assert_eq!(received, b"noise aaab");
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+
+    // The bytes following the sequence are left for the next read
+    let mut buf = [0u8; 5];
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b" rest");
+
+    let err = slave
+        .wait_for_sequence(b"OK", Duration::from_millis(50))
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut)
+    );
+}
+
+#[test]
+fn test_ttyport_read_until_any() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let needles: &[&[u8]] = &[b"OK\r\n", b"ERROR\r\n"];
+
+    master.write_all(b"AT+X\r\nERROR\r\nOK\r\n").unwrap();
+    let (index, received) = slave
+        .read_until_any(needles, Duration::from_millis(500))
+        .unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(received, b"AT+X\r\nERROR\r\n");
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+
+    let (index, received) = slave
+        .read_until_any(needles, Duration::from_millis(500))
+        .unwrap();
+    assert_eq!(index, 0);
+    assert_eq!(received, b"OK\r\n");
+
+    let err = slave
+        .read_until_any(needles, Duration::from_millis(50))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(std::io::ErrorKind::TimedOut));
+    let err = slave
+        .read_until_any(&[b"OK", b""], Duration::from_millis(50))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_read_uninit() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    master.write_all(b"Test Message").unwrap();
+    let mut buf = [std::mem::MaybeUninit::<u8>::uninit(); 128];
+    let nbytes = slave.read_uninit(&mut buf).expect("Unable to read bytes.");
+    let received: Vec<u8> = buf[..nbytes]
+        .iter()
+        .map(|b| unsafe { b.assume_init() })
+        .collect();
+    assert_eq!(received, b"Test Message");
+}
+
+#[test]
+fn test_ttyport_read_with_keepalive() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_secs(1)).unwrap();
+
+    // Only answer after the second prompt
+    let responder = std::thread::spawn(move || {
+        let mut buf = [0u8; 2];
+        master.read_exact(&mut buf).unwrap();
+        master.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"?\n");
+        master.write_all(b"OK").unwrap();
+        // Keep the master open until the slave is done with it.
+        master
+    });
+
+    let mut buf = [0u8; 8];
+    let n = slave
+        .read_with_keepalive(
+            &mut buf,
+            Duration::from_millis(20),
+            b"?\n",
+            Duration::from_millis(500),
+        )
+        .unwrap();
+    assert_eq!(&buf[..n], b"OK");
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+
+    responder.join().unwrap();
+}
+
+#[test]
+fn test_ttyport_set_read_mode() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave
+        .set_read_mode(4, Duration::from_millis(500))
+        .expect("Unable to set the read mode");
+
+    let writer = std::thread::spawn(move || {
+        master.write_all(b"ab").unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        master.write_all(b"cd").unwrap();
+        // Keep the master open until the slave is done with it.
+        master
+    });
+
+    let mut buf = [0u8; 8];
+    let nbytes = slave.read(&mut buf).expect("Unable to read bytes.");
+    assert_eq!(&buf[..nbytes], b"abcd");
+
+    writer.join().unwrap();
+}
+
+#[test]
+fn test_ttyport_set_read_mode_without_timeout() {
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    let (_master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let error = slave
+        .set_read_mode(4, Duration::from_secs(0))
+        .expect_err("Waiting for 4 bytes without a timeout was accepted");
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    slave
+        .set_read_mode(1, Duration::from_secs(0))
+        .expect("Unable to set the read mode");
+}
+
+#[test]
+fn test_ttyport_open_verified() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+    drop(slave);
+
+    serialport::new(&name, 9600)
+        .open_verified()
+        .expect("Default settings should be applied");
+
+    // Linux pseudo terminals always clear the parity bit
+    #[cfg(target_os = "linux")]
+    {
+        let err = serialport::new(&name, 9600)
+            .parity(serialport::Parity::Even)
+            .open_verified()
+            .err()
+            .expect("Parity should not be applied");
+        assert_eq!(err.kind(), serialport::ErrorKind::InvalidInput);
+        assert!(err.description.contains("parity"));
+    }
+}
+
+#[test]
+fn test_ttyport_open_with_settings_retry() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+    drop(slave);
+
+    serialport::new(&name, 9600)
+        .open_with_settings_retry(3)
+        .expect("Default settings should be applied");
+
+    let err = serialport::new(&name, 9600)
+        .open_with_settings_retry(0)
+        .expect_err("No attempt should be made");
+    assert_eq!(err.kind(), serialport::ErrorKind::InvalidInput);
+
+    // Linux pseudo terminals clear the parity bit on every attempt
+    #[cfg(target_os = "linux")]
+    {
+        let err = serialport::new(&name, 9600)
+            .parity(serialport::Parity::Even)
+            .open_with_settings_retry(3)
+            .expect_err("Parity should not be applied");
+        assert_eq!(err.kind(), serialport::ErrorKind::InvalidInput);
+        assert!(err.description.contains("parity"));
+    }
+
+    let err = serialport::new("/dev/nonexistent-serial-port", 9600)
+        .open_with_settings_retry(3)
+        .expect_err("The port should not exist");
+    assert_ne!(err.kind(), serialport::ErrorKind::InvalidInput);
+}